@@ -0,0 +1,32 @@
+use std::{env, path::Path, process::ExitCode};
+
+use btree::{inspect::dump, node_type::Offset};
+
+/// Prints the header and contents of pages of a tree file: dump <tree file> [page offset...]
+/// Every page is printed when no offset is given.
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    let Some((path, offsets)) = args.split_first() else {
+        eprintln!("usage: dump <tree file> [page offset...]");
+        return ExitCode::FAILURE;
+    };
+    if !Path::new(path).is_file() {
+        eprintln!("no tree file at {}", path);
+        return ExitCode::FAILURE;
+    }
+    let offsets: Result<Vec<Offset>, _> = offsets.iter().map(|offset| offset.parse().map(Offset)).collect();
+    let Ok(offsets) = offsets else {
+        eprintln!("page offsets must be byte offsets into the file");
+        return ExitCode::FAILURE;
+    };
+    match dump(Path::new(path), &offsets) {
+        Ok(out) => {
+            print!("{}", out);
+            ExitCode::SUCCESS
+        }
+        Err(err) => {
+            eprintln!("failed to dump {}: {:?}", path, err);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -1,35 +1,56 @@
-use std::{cmp, path::Path};
+use std::{cmp::Ordering, collections::HashMap, ops::{Bound, RangeBounds}, path::{Path, PathBuf}, sync::Arc};
 
-use crate::{error::Error, node::Node, node_type::{Key, KeyValuePair, NodeType, Offset}, page::Page, pager::Pager, wal::Wal};
+use crate::{buffer_pool::{BufferPool, CacheStats, Shared}, cursor::{prefix_end, Cursor}, error::Error, header::FileHeader, inspect::{verify_tree, Violation}, node::Node, node_type::{bytewise, Comparator, Key, KeyValuePair, LeafEntry, NodeType, Offset, StoredValue}, overflow::{overflow_page, read_overflow_page}, page::Page, page_layout::{HEADER_PAGE_OFFSET, INLINE_VALUE_SIZE, INTERNAL_NODE_MAX_CHILDREN, LEAF_NODE_MAX_PAIRS, OVERFLOW_PAGE_CAPACITY, PAGE_SIZE}, pager::Pager, reader::TreeReader, wal::Wal};
 
-pub const MAX_BRANCHING_FACTOR:usize = 200;
+/// Largest b for which full nodes still fit in a page.
+pub const MAX_BRANCHING_FACTOR: usize = if LEAF_NODE_MAX_PAIRS < INTERNAL_NODE_MAX_CHILDREN { LEAF_NODE_MAX_PAIRS / 2 } else { INTERNAL_NODE_MAX_CHILDREN / 2 };
 pub const NODE_KEYS_LIMIT: usize = MAX_BRANCHING_FACTOR - 1;
+/// Size the WAL may grow to before its pages are checkpointed into the tree file.
+pub const WAL_CHECKPOINT_SIZE: usize = 4 << 20;
+/// Number of pages the buffer pool holds unless configured otherwise.
+pub const DEFAULT_CACHE_CAPACITY: usize = 256;
 
 /// BTree struct represents an on-disk B+tree.
 /// Each node is persisted in the table file, the leaf nodes contains the values.
 pub struct BTree {
-    pager: Pager,
+    pool: BufferPool,
     b: usize,
-    wal: Wal
+    wal: Wal,
+    cmp: Comparator,
+    /// Root as of the last operation of the open transaction, None outside of one.
+    txn_root: Option<Offset>
 }
 
 /// BTreeeBuilder is a Builder for the BTree struct.
 pub struct BTreeBuilder {
     /// Path to the tree file.
-    path: &'static Path,
+    path: PathBuf,
     /// The BTree parameter, an inner node contains no more than 2*b-1 keys and no less than b-1 keys
-    /// and no more than 2*b children and no less than b children.
-    b: usize
+    /// and no more than 2*b children and no less than b children. A tree file that already
+    /// exists keeps the one it was created with.
+    b: usize,
+    /// Whether to discard an existing tree file instead of reopening it.
+    truncate: bool,
+    /// Number of pages kept in the buffer pool.
+    cache_capacity: usize,
+    /// Orders the keys, bytewise unless configured otherwise.
+    cmp: Comparator
 }
 
 
 impl BTreeBuilder {
     pub fn new() -> BTreeBuilder {
-        BTreeBuilder { path: Path::new(""), b: 0 }
+        BTreeBuilder { path: PathBuf::new(), b: 0, truncate: false, cache_capacity: DEFAULT_CACHE_CAPACITY, cmp: bytewise }
     }
 
-    pub fn path(mut self, path: &'static Path) -> BTreeBuilder {
-        self.path = path;
+    pub fn path(mut self, path: impl Into<PathBuf>) -> BTreeBuilder {
+        self.path = path.into();
+        self
+    }
+
+    /// truncate makes build start from an empty tree even if the file already holds one.
+    pub fn truncate(mut self, truncate: bool) -> BTreeBuilder {
+        self.truncate = truncate;
         self
     }
 
@@ -38,36 +59,77 @@ impl BTreeBuilder {
         self
     }
 
+    /// cache_capacity sets how many pages the buffer pool keeps in memory.
+    pub fn cache_capacity(mut self, pages: usize) -> BTreeBuilder {
+        self.cache_capacity = pages;
+        self
+    }
+
+    /// comparator sets the order of the keys, the same comparator must be given every time the
+    /// tree file is opened.
+    pub fn comparator(mut self, cmp: Comparator) -> BTreeBuilder {
+        self.cmp = cmp;
+        self
+    }
+
     pub fn build(&self) -> Result<BTree, Error> {
         if self.path.to_string_lossy() == "" {
             return Err(Error::UnexpectedError);
         }
-        if self.b == 0 {
+        if self.b == 0 || self.b > MAX_BRANCHING_FACTOR || self.cache_capacity == 0 {
             return Err(Error::UnexpectedError);
         }
 
-        let mut pager = Pager::new(self.path)?;
-        let root = Node::new(NodeType::Leaf(vec![]), true, None);
-        let root_offset = pager.write_page(Page::try_from(&root)?)?;
-        let parent_directory = self.path.parent().unwrap_or_else(|| Path::new("/tmp"));
-        let mut wal = Wal::new(parent_directory.to_path_buf())?;
-        wal.set_root(root_offset)?;
-        Ok(BTree { pager, b: self.b, wal })
+        let mut pager = Pager::new(&self.path, self.truncate)?;
+        let mut wal = Wal::new(&self.path, self.truncate)?;
+
+        // Redo every committed operation whose pages may not have reached the tree file.
+        let (pages, root_offset) = wal.recover()?;
+        pager.apply(pages)?;
+        pager.check_length()?;
+
+        let (root_offset, b) = if pager.is_empty() {
+            let root = Node::new(NodeType::Leaf(vec![]), true, None);
+            let pages = vec![
+                (Offset(HEADER_PAGE_OFFSET), Page::try_from(&FileHeader::new(self.b))?),
+                (Offset(PAGE_SIZE), Page::try_from(&root)?),
+            ];
+            wal.commit(&pages, Offset(PAGE_SIZE))?;
+            pager.apply(pages)?;
+            (Offset(PAGE_SIZE), self.b)
+        } else {
+            let root_offset = root_offset.ok_or(Error::CorruptedFile("no root offset recorded in the WAL"))?;
+            let header = FileHeader::try_from(pager.get_page(&Offset(HEADER_PAGE_OFFSET))?)?;
+            if header.b == 0 || header.b > MAX_BRANCHING_FACTOR {
+                return Err(Error::CorruptedFile("branching factor out of range"));
+            }
+            for offset in pager.page_offsets().skip(1) {
+                pager.get_page(&offset)?.check_header()?;
+            }
+            if !Node::try_from(pager.get_page(&root_offset)?)?.is_root {
+                return Err(Error::CorruptedFile("recorded root offset does not point at a root page"));
+            }
+            (root_offset, header.b)
+        };
+        pager.sync()?;
+        wal.checkpoint()?;
+        let pool = BufferPool::new(Arc::new(Shared::new(pager, self.cache_capacity, root_offset)))?;
+        Ok(BTree { pool, b, wal, cmp: self.cmp, txn_root: None })
     }
 }
 
 
 impl Default for BTreeBuilder {
     fn default() -> Self {
-        BTreeBuilder::new().b_parameter(200).path(Path::new("/tmp/db"))
+        BTreeBuilder::new().b_parameter(MAX_BRANCHING_FACTOR).path(Path::new("/tmp/db"))
     }
 }
 
 impl BTree {
     fn is_node_full(&self, node: &Node) -> Result<bool, Error> {
         match &node.node_type {
-            NodeType::Internal(_, keys) => Ok(keys.len() == (2* self.b -1)),
-            NodeType::Leaf(pairs) => Ok(pairs.len() == (2*self.b)),
+            NodeType::Internal(_, keys) => Ok(keys.len() >= (2* self.b -1)),
+            NodeType::Leaf(pairs) => Ok(pairs.len() >= (2*self.b)),
             NodeType::Unexpected => Err(Error::UnexpectedError)
         }
     }
@@ -81,230 +143,512 @@ impl BTree {
         }
     }
 
-    pub fn insert(&mut self, kv: KeyValuePair) -> Result<(), Error> {
+    /// begin starts a transaction: the inserts and deletes that follow see each other's writes,
+    /// but are kept out of the WAL and the tree file until commit applies them all at once.
+    /// A crash or a drop before then discards them.
+    pub fn begin(&mut self) -> Result<(), Error> {
+        if self.txn_root.is_some() {
+            return Err(Error::TransactionError("a transaction is already open"));
+        }
+        self.txn_root = Some(self.wal.get_root()?);
+        Ok(())
+    }
+
+    /// commit makes every operation of the open transaction durable as a single WAL commit.
+    pub fn commit(&mut self) -> Result<(), Error> {
+        let root_offset = self.txn_root.take().ok_or(Error::TransactionError("no transaction is open"))?;
+        self.commit_pages(root_offset)
+    }
+
+    /// rollback discards every operation of the open transaction.
+    pub fn rollback(&mut self) -> Result<(), Error> {
+        self.txn_root.take().ok_or(Error::TransactionError("no transaction is open"))?;
+        self.pool.rollback();
+        Ok(())
+    }
+
+    /// end_operation undoes the writes of an operation that failed half way. A successful one is
+    /// committed right away, or becomes part of the open transaction.
+    fn end_operation(&mut self, result: Result<Offset, Error>) -> Result<(), Error> {
+        let root_offset = match result {
+            Ok(root_offset) => root_offset,
+            Err(err) => {
+                self.pool.rollback_to_savepoint();
+                return Err(err);
+            }
+        };
+        match &mut self.txn_root {
+            Some(txn_root) => {
+                *txn_root = root_offset;
+                Ok(())
+            }
+            None => self.commit_pages(root_offset)
+        }
+    }
+
+    /// commit_pages makes the pages written since the last commit durable in the WAL before they
+    /// are applied to the tree file, or discards them if they cannot be logged. Pages that are
+    /// logged but fail to apply poison the tree until it is reopened and recovers them.
+    fn commit_pages(&mut self, root_offset: Offset) -> Result<(), Error> {
+        let pages = self.pool.take_dirty();
+        if let Err(err) = self.wal.commit(&pages, root_offset.clone()) {
+            self.pool.rollback();
+            return Err(err);
+        }
+        self.pool.apply(pages, root_offset)?;
+        if self.wal.len() > WAL_CHECKPOINT_SIZE {
+            self.flush()?;
+        }
+        Ok(())
+    }
+
+    /// shared is the committed state of the tree that snapshots read from.
+    pub(crate) fn shared(&self) -> Arc<Shared> {
+        self.pool.shared()
+    }
+
+    /// flush writes every cached dirty page to the tree file and empties the WAL.
+    pub fn flush(&mut self) -> Result<(), Error> {
+        self.pool.flush()?;
+        self.wal.checkpoint()
+    }
+
+    /// bulk_load fills an empty tree from pairs sorted by the comparator, building packed leaves
+    /// and then every internal level bottom up instead of inserting the pairs one at a time.
+    ///
+    /// fill_factor, within (0, 1], is how full the nodes are packed: 1.0 suits a tree that is
+    /// mostly read, lower values leave room for later inserts. Nodes are never left below the
+    /// minimum that b requires. The whole load is a single operation, like an insert.
+    pub fn bulk_load(&mut self, pairs: impl IntoIterator<Item = KeyValuePair>, fill_factor: f64) -> Result<(), Error> {
+        if !(fill_factor > 0.0 && fill_factor <= 1.0) {
+            return Err(Error::BulkLoadError("fill factor must be within (0, 1]"));
+        }
+        let root_offset = self.root_offset()?;
+        if !matches!(self.load_node(&root_offset)?.node_type, NodeType::Leaf(entries) if entries.is_empty()) {
+            return Err(Error::BulkLoadError("cannot bulk load a tree that is not empty"));
+        }
+        self.pool.set_savepoint();
+        let result = self.load_sorted(pairs, fill_factor, root_offset);
+        self.end_operation(result)
+    }
+
+    fn load_sorted(&mut self, pairs: impl IntoIterator<Item = KeyValuePair>, fill_factor: f64, root_offset: Offset) -> Result<Offset, Error> {
+        let mut entries: Vec<LeafEntry> = Vec::new();
+        for kv in pairs {
+            if entries.last().is_some_and(|last| (self.cmp)(&last.key.0, &kv.key) != Ordering::Less) {
+                return Err(Error::BulkLoadError("keys must be sorted and unique"));
+            }
+            let value = self.store_value(kv.value)?;
+            entries.push(LeafEntry { key: Key(kv.key), value });
+        }
+        if entries.is_empty() {
+            return Ok(root_offset);
+        }
+
+        // Plan the size of every node first, so that every page offset is known before any node
+        // that links to it is written.
+        let fill = |min: usize| ((2 * self.b) as f64 * fill_factor).ceil().clamp(min as f64, (2 * self.b) as f64) as usize;
+        let mut levels = vec![group_sizes(entries.len(), fill((self.b - 1).max(1)), (self.b - 1).max(1))];
+        while let Some(sizes) = levels.last().filter(|sizes| sizes.len() > 1) {
+            levels.push(group_sizes(sizes.len(), fill(self.b.max(2)), self.b.max(2)));
+        }
+        let mut offsets: Vec<Vec<Offset>> = Vec::new();
+        for (depth, sizes) in levels.iter().enumerate() {
+            let level_offsets = match depth == levels.len() - 1 {
+                // The empty root page is reused for the new root.
+                true => vec![root_offset.clone()],
+                false => sizes.iter().map(|_| self.pool.allocate_page(Page::new([0x00; PAGE_SIZE]))).collect::<Result<_, _>>()?
+            };
+            offsets.push(level_offsets);
+        }
+        let parents = |depth: usize| -> Vec<Option<Offset>> {
+            match levels.get(depth + 1) {
+                Some(parent_sizes) => parent_sizes.iter().enumerate()
+                    .flat_map(|(idx, size)| std::iter::repeat_n(Some(offsets[depth + 1][idx].clone()), *size))
+                    .collect(),
+                None => vec![None]
+            }
+        };
+
+        // Every node is passed up with the last key of its subtree, the separator in its parent.
+        let mut children: Vec<(Offset, Key)> = Vec::new();
+        let mut entries = entries.into_iter();
+        let leaf_offsets = &offsets[0];
+        for (idx, (size, parent)) in levels[0].iter().zip(parents(0)).enumerate() {
+            let leaf_entries: Vec<LeafEntry> = entries.by_ref().take(*size).collect();
+            let last_key = leaf_entries.last().ok_or(Error::UnexpectedError)?.key.clone();
+            let mut leaf = Node::new(NodeType::Leaf(leaf_entries), parent.is_none(), parent);
+            leaf.prev_leaf = idx.checked_sub(1).map(|prev| leaf_offsets[prev].clone());
+            leaf.next_leaf = leaf_offsets.get(idx + 1).cloned();
+            self.store_node(&leaf, &leaf_offsets[idx])?;
+            children.push((leaf_offsets[idx].clone(), last_key));
+        }
+        for depth in 1..levels.len() {
+            let mut level_children = children.into_iter();
+            children = Vec::new();
+            for (idx, (size, parent)) in levels[depth].iter().zip(parents(depth)).enumerate() {
+                let (child_offsets, mut keys): (Vec<Offset>, Vec<Key>) = level_children.by_ref().take(*size).unzip();
+                let last_key = keys.pop().ok_or(Error::UnexpectedError)?;
+                let node = Node::new(NodeType::Internal(child_offsets, keys), parent.is_none(), parent);
+                self.store_node(&node, &offsets[depth][idx])?;
+                children.push((offsets[depth][idx].clone(), last_key));
+            }
+        }
+        Ok(root_offset)
+    }
+
+    /// compact rewrites the tree file densely: the header, then every node in depth first order,
+    /// then the overflow pages, leaving out the free pages. It returns the number of pages reclaimed.
+    ///
+    /// The rewrite is committed to the WAL as a single operation, a crash before the file is
+    /// truncated leaves the old tail unreferenced until the next compact.
+    pub fn compact(&mut self) -> Result<usize, Error> {
+        if self.txn_root.is_some() {
+            return Err(Error::TransactionError("cannot compact with a transaction open"));
+        }
+        self.flush()?;
+        let old_len = self.pool.file_len();
         let root_offset = self.wal.get_root()?;
-        let root_page = self.pager.get_page(&root_offset)?;
-        let new_root_offset: Offset;
-        let mut new_root: Node;
-        let mut root = Node::try_from(root_page)?;
+
+        // Every page is loaded before any is moved, as the new offsets overlap the old ones.
+        let mut nodes = Vec::new();
+        let mut overflow_pages = Vec::new();
+        let mut stack = vec![root_offset];
+        while let Some(offset) = stack.pop() {
+            let node = self.load_node(&offset)?;
+            match &node.node_type {
+                NodeType::Internal(children, _) => stack.extend(children.iter().rev().cloned()),
+                NodeType::Leaf(entries) => {
+                    for entry in entries {
+                        overflow_pages.extend(self.overflow_chain(&entry.value)?);
+                    }
+                }
+                NodeType::Unexpected => return Err(Error::UnexpectedError)
+            }
+            nodes.push((offset, node));
+        }
+        let new_len = (nodes.len() + overflow_pages.len() + 1) * PAGE_SIZE;
+
+        self.pool.set_savepoint();
+        let result = self.store_densely(nodes, overflow_pages);
+        self.end_operation(result)?;
+        self.flush()?;
+        self.pool.truncate(new_len)?;
+        Ok((old_len - new_len) / PAGE_SIZE)
+    }
+
+    /// store_densely writes a fresh header followed by nodes and overflow pages in order,
+    /// translating the offsets they hold, and returns the new offset of the root which comes first.
+    fn store_densely(&mut self, nodes: Vec<(Offset, Node)>, overflow_pages: Vec<(Offset, Page)>) -> Result<Offset, Error> {
+        let new_offsets: HashMap<usize, Offset> = nodes.iter().map(|(offset, _)| offset)
+            .chain(overflow_pages.iter().map(|(offset, _)| offset))
+            .enumerate()
+            .map(|(idx, offset)| (offset.0, Offset((idx + 1) * PAGE_SIZE)))
+            .collect();
+        let relocate = |offset: Offset| new_offsets.get(&offset.0).cloned().ok_or(Error::UnexpectedError);
+
+        self.pool.write_page_at_offset(Page::try_from(&FileHeader::new(self.b))?, &Offset(HEADER_PAGE_OFFSET))?;
+        for (offset, mut node) in nodes {
+            node.parent_offset = node.parent_offset.map(relocate).transpose()?;
+            node.prev_leaf = node.prev_leaf.map(relocate).transpose()?;
+            node.next_leaf = node.next_leaf.map(relocate).transpose()?;
+            match &mut node.node_type {
+                NodeType::Internal(children, _) => {
+                    *children = children.drain(..).map(relocate).collect::<Result<_, _>>()?;
+                }
+                NodeType::Leaf(entries) => {
+                    for entry in entries {
+                        if let StoredValue::Overflow { first_page, .. } = &mut entry.value {
+                            *first_page = relocate(first_page.clone())?;
+                        }
+                    }
+                }
+                NodeType::Unexpected => return Err(Error::UnexpectedError)
+            }
+            self.store_node(&node, &relocate(offset)?)?;
+        }
+        for (offset, page) in overflow_pages {
+            let (next, chunk) = read_overflow_page(&page)?;
+            let next = next.map(relocate).transpose()?;
+            self.pool.write_page_at_offset(overflow_page(next.as_ref(), chunk)?, &relocate(offset)?)?;
+        }
+        Ok(Offset(PAGE_SIZE))
+    }
+
+    /// cache_stats reports buffer pool hits, misses and evictions since the tree was opened.
+    pub fn cache_stats(&self) -> CacheStats {
+        self.pool.stats()
+    }
+
+    fn store_node(&mut self, node: &Node, offset: &Offset) -> Result<(), Error> {
+        self.pool.write_page_at_offset(Page::try_from(node)?, offset)
+    }
+
+    /// store_value keeps short values inline and writes longer ones to a chain of overflow pages.
+    fn store_value(&mut self, value: Vec<u8>) -> Result<StoredValue, Error> {
+        if value.len() <= INLINE_VALUE_SIZE {
+            return Ok(StoredValue::Inline(value));
+        }
+        // Write the chain back to front so that every page knows the offset of its successor.
+        let mut next = None;
+        for chunk in value.chunks(OVERFLOW_PAGE_CAPACITY).rev() {
+            next = Some(self.pool.allocate_page(overflow_page(next.as_ref(), chunk)?)?);
+        }
+        Ok(StoredValue::Overflow { len: value.len(), first_page: next.ok_or(Error::UnexpectedError)? })
+    }
+
+    /// free_value returns the overflow pages of a deleted value to the free list.
+    fn free_value(&mut self, value: &StoredValue) -> Result<(), Error> {
+        for (offset, _) in self.overflow_chain(value)? {
+            self.pool.free_page(&offset)?;
+        }
+        Ok(())
+    }
+
+    pub fn insert(&mut self, kv: KeyValuePair) -> Result<(), Error> {
+        self.pool.set_savepoint();
+        let result = self.store_value(kv.value).and_then(|value| self.insert_into_root(LeafEntry { key: Key(kv.key), value }));
+        self.end_operation(result)
+    }
+
+    fn insert_into_root(&mut self, entry: LeafEntry) -> Result<Offset, Error> {
+        let mut root_offset = self.root_offset()?;
+        let mut root = self.load_node(&root_offset)?;
         if self.is_node_full(&root)? {
-            new_root = Node::new(NodeType::Internal(vec![], vec![]), true, None);
-            new_root_offset = self.pager.write_page(Page::try_from(&new_root)?)?;
-            root.parent_offset = Some(new_root_offset.clone());
+            // Grow the tree by one level, the old root becomes the first child of a new root.
+            let new_root_offset = self.pool.allocate_page(Page::try_from(&Node::new(NodeType::Internal(vec![], vec![]), true, None))?)?;
             root.is_root = false;
-            let (median, sibling) = root.split(self.b)?;
-            let old_root_offset = self.pager.write_page(Page::try_from(&root)?)?;
-            let sibling_offset = self.pager.write_page(Page::try_from(&sibling)?)?;
-            new_root.node_type = NodeType::Internal(vec![old_root_offset, sibling_offset], vec![median]);
-            self.pager.write_page_at_offset(Page::try_from(&new_root)?, &new_root_offset)?;
-        } else {
-            new_root = root.clone();
-            new_root_offset = self.pager.write_page(Page::try_from(&new_root)?)?;
+            root.parent_offset = Some(new_root_offset.clone());
+            let (median, _, sibling_offset) = self.split_child(&mut root, &root_offset)?;
+            root = Node::new(NodeType::Internal(vec![root_offset, sibling_offset], vec![median]), true, None);
+            root_offset = new_root_offset;
+            self.store_node(&root, &root_offset)?;
         }
-        self.insert_non_full(&mut new_root, new_root_offset.clone(), kv)?;
-        self.wal.set_root(new_root_offset)
+        self.insert_non_full(&mut root, &root_offset, entry)?;
+        Ok(root_offset)
     }
 
-    fn insert_non_full(
-        &mut self,
-        node: &mut Node,
-        node_offset: Offset,
-        kv: KeyValuePair,
-    ) -> Result<(), Error> {
+    fn insert_non_full(&mut self, node: &mut Node, node_offset: &Offset, entry: LeafEntry) -> Result<(), Error> {
         match &mut node.node_type {
-            NodeType::Leaf(pairs) => {
-                let idx = pairs.binary_search(&kv).unwrap_or_else(|x| x);
-                pairs.insert(idx, kv);
-                self.pager
-                    .write_page_at_offset(Page::try_from(&*node)?, &node_offset)
+            NodeType::Leaf(entries) => {
+                let idx = match self.entry_index(entries, &entry.key.0) {
+                    Ok(_) => return Err(Error::KeyAlreadyExist),
+                    Err(idx) => idx
+                };
+                entries.insert(idx, entry);
+                self.store_node(node, node_offset)
             }
             NodeType::Internal(children, keys) => {
-                let idx = keys
-                    .binary_search(&Key(kv.key.clone()))
-                    .unwrap_or_else(|x| x);
-                let child_offset = children.get(idx).ok_or(Error::UnexpectedError)?.clone();
-                let child_page = self.pager.get_page(&child_offset)?;
-                let mut child = Node::try_from(child_page)?;
-                // Copy each branching-node on the root-to-leaf walk.
-                // write_page appends the given page to the db file thus creating a new node.
-                let new_child_offset = self.pager.write_page(Page::try_from(&child)?)?;
-                // Assign copied child at the proper place.
-                children[idx] = new_child_offset.to_owned();
+                let idx = self.child_index(keys, &entry.key.0);
+                let mut child_offset = children.get(idx).ok_or(Error::UnexpectedError)?.clone();
+                let mut child = self.load_node(&child_offset)?;
                 if self.is_node_full(&child)? {
-                    // split will split the child at b leaving the [0, b-1] keys
-                    // while moving the set of [b, 2b-1] keys to the sibling.
-                    let (median, mut sibling) = child.split(self.b)?;
-                    self.pager
-                        .write_page_at_offset(Page::try_from(&child)?, &new_child_offset)?;
-                    // Write the newly created sibling to disk.
-                    let sibling_offset = self.pager.write_page(Page::try_from(&sibling)?)?;
+                    let (median, sibling, sibling_offset) = self.split_child(&mut child, &child_offset)?;
                     // Siblings keys are larger than the splitted child thus need to be inserted
                     // at the next index.
                     children.insert(idx + 1, sibling_offset.clone());
-                    keys.insert(idx, median.clone());
-
-                    // Write the parent page to disk.
-                    self.pager
-                        .write_page_at_offset(Page::try_from(&*node)?, &node_offset)?;
-                    // Continue recursively.
-                    if kv.key <= median.0 {
-                        self.insert_non_full(&mut child, new_child_offset, kv)
-                    } else {
-                        self.insert_non_full(&mut sibling, sibling_offset, kv)
+                    let descend_into_sibling = (self.cmp)(&entry.key.0, &median.0) == Ordering::Greater;
+                    keys.insert(idx, median);
+                    self.store_node(node, node_offset)?;
+                    if descend_into_sibling {
+                        child = sibling;
+                        child_offset = sibling_offset;
                     }
-                } else {
-                    self.pager
-                        .write_page_at_offset(Page::try_from(&*node)?, &node_offset)?;
-                    self.insert_non_full(&mut child, new_child_offset, kv)
                 }
+                self.insert_non_full(&mut child, &child_offset, entry)
             }
             NodeType::Unexpected => Err(Error::UnexpectedError),
         }
     }
 
-    pub fn search(&mut self, key: String) -> Result<KeyValuePair, Error> {
-        let root_offset = self.wal.get_root()?;
-        let root_page = self.pager.get_page(&root_offset)?;
-        let root = Node::try_from(root_page)?;
-        self.search_node(root, &key)
+    /// split_child splits a full node in place around its median and writes the new right
+    /// sibling, relinking the leaf chain or re-parenting the children moved to the sibling.
+    fn split_child(&mut self, child: &mut Node, child_offset: &Offset) -> Result<(Key, Node, Offset), Error> {
+        let (median, mut sibling) = child.split(self.b)?;
+        let sibling_offset = self.pool.allocate_page(Page::try_from(&sibling)?)?;
+        match &sibling.node_type {
+            NodeType::Leaf(_) => {
+                sibling.prev_leaf = Some(child_offset.clone());
+                sibling.next_leaf = child.next_leaf.replace(sibling_offset.clone());
+                if let Some(next_offset) = &sibling.next_leaf {
+                    let mut next = self.load_node(next_offset)?;
+                    next.prev_leaf = Some(sibling_offset.clone());
+                    self.store_node(&next, next_offset)?;
+                }
+            }
+            NodeType::Internal(children, _) => self.set_parent(children, &sibling_offset)?,
+            NodeType::Unexpected => return Err(Error::UnexpectedError),
+        }
+        self.store_node(child, child_offset)?;
+        self.store_node(&sibling, &sibling_offset)?;
+        Ok((median, sibling, sibling_offset))
     }
 
-    fn search_node(&mut self, node: Node, search: &str) -> Result<KeyValuePair, Error> {
-        match node.node_type {
-            NodeType::Internal(children, keys) => {
-                let idx = keys.binary_search(&Key(search.to_string())).unwrap_or_else(|x| x);
-                let child_offset = children.get(idx).ok_or(Error::UnexpectedError)?;
-                let page = self.pager.get_page(child_offset)?;
-                let child_node = Node::try_from(page)?;
-                self.search_node(child_node, search)
-            }
-            NodeType::Leaf(pairs) => {
-                if let Ok(idx) = pairs.binary_search_by_key(&search.to_string(), |pair| pair.key.clone()) {
-                    return Ok(pairs[idx].clone());
-                }
-                Err(Error::KeyNotFound)
-            },
-            NodeType::Unexpected => Err(Error::UnexpectedError)
+    /// set_parent points the parent offset of every given child at parent_offset.
+    fn set_parent(&mut self, children: &[Offset], parent_offset: &Offset) -> Result<(), Error> {
+        for child_offset in children {
+            let mut child = self.load_node(child_offset)?;
+            child.parent_offset = Some(parent_offset.clone());
+            self.store_node(&child, child_offset)?;
         }
+        Ok(())
+    }
+
+    pub fn search(&mut self, key: impl AsRef<[u8]>) -> Result<KeyValuePair, Error> {
+        let entry = self.find_entry(key.as_ref())?;
+        self.load_pair(entry)
     }
 
     pub fn delete(&mut self, key: Key) -> Result<(), Error> {
-        let root_offset = self.wal.get_root()?;
-        let root_page = self.pager.get_page(&root_offset)?;
-        let mut new_root = Node::try_from(root_page)?;
-        let new_root_page = Page::try_from(&new_root)?;
-        let new_root_offset = self.pager.write_page(new_root_page)?;
-        self.delete_key_from_subtree(key, &mut new_root, &new_root_offset)?;
-        self.wal.set_root(new_root_offset)
+        self.pool.set_savepoint();
+        let result = self.delete_from_root(key);
+        self.end_operation(result)
+    }
 
+    fn delete_from_root(&mut self, key: Key) -> Result<Offset, Error> {
+        let root_offset = self.root_offset()?;
+        let mut root = self.load_node(&root_offset)?;
+        self.delete_key_from_subtree(&key, &mut root, &root_offset)?;
+        match root.node_type {
+            // The root lost its last key, its only child becomes the new root.
+            NodeType::Internal(children, _) if children.len() == 1 => {
+                let mut new_root = self.load_node(&children[0])?;
+                new_root.is_root = true;
+                new_root.parent_offset = None;
+                self.store_node(&new_root, &children[0])?;
+                self.pool.free_page(&root_offset)?;
+                Ok(children[0].clone())
+            }
+            _ => Ok(root_offset)
+        }
     }
 
-    fn delete_key_from_subtree(&mut self, key: Key, node: &mut Node, node_offset: &Offset) -> Result<(), Error> {
+    fn delete_key_from_subtree(&mut self, key: &Key, node: &mut Node, node_offset: &Offset) -> Result<(), Error> {
         match &mut node.node_type {
-            NodeType::Leaf(pairs) => {
-                let key_idx = pairs.binary_search_by_key(&key, |kv| Key(kv.key.clone())).map_err(|_| Error::KeyNotFound)?;
-                pairs.remove(key_idx);
-                self.pager.write_page_at_offset(Page::try_from(&*node)?, node_offset)?;
-                self.borrow_if_needed(node.to_owned(), &key)?;
+            NodeType::Leaf(entries) => {
+                let key_idx = self.entry_index(entries, &key.0).map_err(|_| Error::KeyNotFound)?;
+                let entry = entries.remove(key_idx);
+                self.free_value(&entry.value)?;
+                self.store_node(node, node_offset)
             },
             NodeType::Internal(children, keys) => {
-                let node_idx = keys.binary_search(&key).unwrap_or_else(|x| x);
-
-                let child_offset = children.get(node_idx).ok_or(Error::UnexpectedError)?;
-                let child_page = self.pager.get_page(child_offset)?;
-                let mut child_node = Node::try_from(child_page)?;
-                child_node.parent_offset = Some(node_offset.to_owned());
-                let new_child_page = Page::try_from(&child_node)?;
-                let new_child_offset = self.pager.write_page(new_child_page)?;
-                children[node_idx] = new_child_offset.to_owned();
-                self.pager.write_page_at_offset(Page::try_from(&*node)?, node_offset)?;
-                return self.delete_key_from_subtree(key, &mut child_node, &new_child_offset);
+                let node_idx = self.child_index(keys, &key.0);
+                let child_offset = children.get(node_idx).ok_or(Error::UnexpectedError)?.clone();
+                let mut child = self.load_node(&child_offset)?;
+                self.delete_key_from_subtree(key, &mut child, &child_offset)?;
+                if self.is_node_underflow(&child)? {
+                    self.rebalance(node, node_offset, node_idx, child)?;
+                }
+                Ok(())
             }
-            NodeType::Unexpected => return Err(Error::UnexpectedError)
+            NodeType::Unexpected => Err(Error::UnexpectedError)
         }
-        Ok(())
     }
 
-    fn borrow_if_needed(&mut self, node: Node, key: &Key) -> Result<(), Error> {
-        if self.is_node_underflow(&node)? {
-            let parent_offset = node.parent_offset.clone().ok_or(Error::UnexpectedError)?;
-            let parent_page = self.pager.get_page(&parent_offset)?;
-            let mut parent_node = Node::try_from(parent_page)?;
-            match parent_node.node_type {
-                NodeType::Internal(ref mut children, ref mut keys ) => {
-                    let idx = keys.binary_search(key).unwrap_or_else(|x| x);
-                    let sibling_idx;
-                    match idx > 0 {
-                        false => sibling_idx = idx + 1,
-                        true => sibling_idx = idx - 1
-                    }
-
-                    let sibling_offset = children.get(sibling_idx).ok_or(Error::UnexpectedError)?;
-                    let sibling_page = self.pager.get_page(sibling_offset)?;
-                    let sibling = Node::try_from(sibling_page)?;
-
-                    let merged_node = self.merge(node, sibling)?;
-                    let merged_node_offset = self.pager.write_page(Page::try_from(&merged_node)?)?;
-                    let merged_node_idx = cmp::min(idx, sibling_idx);
+    fn can_lend(&self, node: &Node) -> Result<bool, Error> {
+        match &node.node_type {
+            NodeType::Leaf(pairs) => Ok(pairs.len() > self.b - 1),
+            NodeType::Internal(_, keys) => Ok(keys.len() > self.b - 1),
+            NodeType::Unexpected => Err(Error::UnexpectedError)
+        }
+    }
 
-                    children.remove(merged_node_idx);
-                    children.remove(merged_node_idx);
+    /// rebalance refills the underflowing child at idx of parent by moving one entry over from
+    /// a neighbouring sibling, or merges the two when the sibling has none to spare.
+    fn rebalance(&mut self, parent: &mut Node, parent_offset: &Offset, idx: usize, child: Node) -> Result<(), Error> {
+        let NodeType::Internal(children, keys) = &mut parent.node_type else {
+            return Err(Error::UnexpectedError);
+        };
+        // Pair the child with its left sibling, or with its right one if it is the first child.
+        let left_idx = idx.saturating_sub(1);
+        let left_offset = children.get(left_idx).ok_or(Error::UnexpectedError)?.clone();
+        let right_offset = children.get(left_idx + 1).ok_or(Error::UnexpectedError)?.clone();
+        let from_left = idx > 0;
+        let (mut left, mut right) = if from_left {
+            (self.load_node(&left_offset)?, child)
+        } else {
+            (child, self.load_node(&right_offset)?)
+        };
 
-                    if parent_node.is_root && children.is_empty() {
-                        self.wal.set_root(merged_node_offset)?;
-                        return Ok(());
+        if self.can_lend(if from_left { &left } else { &right })? {
+            match (&mut left.node_type, &mut right.node_type) {
+                (NodeType::Leaf(left_pairs), NodeType::Leaf(right_pairs)) => {
+                    if from_left {
+                        right_pairs.insert(0, left_pairs.pop().ok_or(Error::UnexpectedError)?);
+                    } else {
+                        left_pairs.push(right_pairs.remove(0));
+                    }
+                    keys[left_idx] = left_pairs.last().ok_or(Error::UnexpectedError)?.key.clone();
+                }
+                (NodeType::Internal(left_children, left_keys), NodeType::Internal(right_children, right_keys)) => {
+                    // Rotate through the parent: the separator moves down, the sibling's edge key up.
+                    if from_left {
+                        let separator = std::mem::replace(&mut keys[left_idx], left_keys.pop().ok_or(Error::UnexpectedError)?);
+                        right_keys.insert(0, separator);
+                        let moved = left_children.pop().ok_or(Error::UnexpectedError)?;
+                        self.set_parent(std::slice::from_ref(&moved), &right_offset)?;
+                        right_children.insert(0, moved);
+                    } else {
+                        let separator = std::mem::replace(&mut keys[left_idx], right_keys.remove(0));
+                        left_keys.push(separator);
+                        let moved = right_children.remove(0);
+                        self.set_parent(std::slice::from_ref(&moved), &left_offset)?;
+                        left_children.push(moved);
                     }
-
-                    keys.remove(idx);
-                    children.insert(merged_node_idx, merged_node_offset);
-                    self.pager.write_page_at_offset(Page::try_from(&parent_node)?, &parent_offset)?;
-                    return self.borrow_if_needed(parent_node, key);
                 }
                 _ => return Err(Error::UnexpectedError)
             }
-        }
-        Ok(())
-    }
-
-
-    fn merge(&self, first: Node, second: Node) -> Result<Node, Error> {
-        match first.node_type {
-            NodeType::Leaf(first_pairs) => {
-                if let NodeType::Leaf(second_pairs) = second.node_type {
-                    let merged_pairs: Vec<KeyValuePair> = first_pairs
-                        .into_iter()
-                        .chain(second_pairs.into_iter())
-                        .collect();
-                    let node_type = NodeType::Leaf(merged_pairs);
-                    Ok(Node::new(node_type, first.is_root, first.parent_offset))
-                } else {
-                    Err(Error::UnexpectedError)
+            self.store_node(&left, &left_offset)?;
+            self.store_node(&right, &right_offset)?;
+        } else {
+            let separator = keys.remove(left_idx);
+            children.remove(left_idx + 1);
+            match (&mut left.node_type, right.node_type) {
+                (NodeType::Leaf(left_pairs), NodeType::Leaf(right_pairs)) => {
+                    left_pairs.extend(right_pairs);
+                    left.next_leaf = right.next_leaf;
+                    if let Some(next_offset) = &left.next_leaf {
+                        let mut next = self.load_node(next_offset)?;
+                        next.prev_leaf = Some(left_offset.clone());
+                        self.store_node(&next, next_offset)?;
+                    }
                 }
-            }
-            NodeType::Internal(first_offsets, first_keys) => {
-                if let NodeType::Internal(second_offsets, second_keys) = second.node_type {
-                    let merged_keys: Vec<Key> = first_keys
-                        .into_iter()
-                        .chain(second_keys.into_iter())
-                        .collect();
-                    let merged_offsets: Vec<Offset> = first_offsets
-                        .into_iter()
-                        .chain(second_offsets.into_iter())
-                        .collect();
-                    let node_type = NodeType::Internal(merged_offsets, merged_keys);
-                    Ok(Node::new(node_type, first.is_root, first.parent_offset))
-                } else {
-                    Err(Error::UnexpectedError)
+                (NodeType::Internal(left_children, left_keys), NodeType::Internal(right_children, right_keys)) => {
+                    left_keys.push(separator);
+                    left_keys.extend(right_keys);
+                    self.set_parent(&right_children, &left_offset)?;
+                    left_children.extend(right_children);
                 }
+                _ => return Err(Error::UnexpectedError)
             }
-            NodeType::Unexpected => Err(Error::UnexpectedError),
+            self.store_node(&left, &left_offset)?;
+            self.pool.free_page(&right_offset)?;
         }
+        self.store_node(parent, parent_offset)
+    }
+
+    /// range returns a cursor over the pairs whose keys fall within range, in key order.
+    pub fn range<K: AsRef<[u8]>, R: RangeBounds<K>>(&mut self, range: R) -> Cursor<'_> {
+        let to_owned = |bound: Bound<&K>| bound.map(|key| key.as_ref().to_vec());
+        Cursor::new(self, to_owned(range.start_bound()), to_owned(range.end_bound()))
+    }
+
+    /// scan_prefix returns a cursor over the pairs whose keys start with prefix.
+    /// It relies on the comparator sorting such keys right after the prefix, as bytewise does.
+    pub fn scan_prefix(&mut self, prefix: impl AsRef<[u8]>) -> Cursor<'_> {
+        let prefix = prefix.as_ref();
+        Cursor::new(self, Bound::Included(prefix.to_vec()), prefix_end(prefix))
+    }
+
+    /// iter returns a cursor over every pair in key order.
+    pub fn iter(&mut self) -> Cursor<'_> {
+        Cursor::new(self, Bound::Unbounded, Bound::Unbounded)
     }
 
     fn print_sub_tree(&mut self, prefix: String, offset: Offset) -> Result<(), Error> {
         println!("{}Node at offset: {}", prefix, offset.0);
         let curr_prefix = format!("{}|->", prefix);
-        let page = self.pager.get_page(&offset)?;
+        let page = self.pool.get_page(&offset)?;
         let node = Node::try_from(page)?;
         match node.node_type {
             NodeType::Internal(children, keys) => {
@@ -326,8 +670,481 @@ impl BTree {
     
     pub fn print(&mut self) -> Result<(), Error> {
         println!();
-        let root_offset = self.wal.get_root()?;
+        let root_offset = self.root_offset()?;
         self.print_sub_tree("".to_string(), root_offset)
     }
 
-}
\ No newline at end of file
+    /// verify checks key order, node fill against b, parent offsets and leaf links, and that
+    /// every page of the file is either reachable from the root or on the free list.
+    pub fn verify(&mut self) -> Result<Vec<Violation>, Error> {
+        let (b, file_len) = (self.b, self.pool.file_len());
+        verify_tree(self, b, file_len)
+    }
+
+}
+
+/// group_sizes splits count entries into as few nodes of at most fill entries as possible,
+/// spread evenly, with at least min entries in each when there is more than one node.
+fn group_sizes(count: usize, fill: usize, min: usize) -> Vec<usize> {
+    let mut groups = count.div_ceil(fill);
+    if groups > 1 && count / groups < min {
+        groups = count / min;
+    }
+    (0..groups).map(|idx| count / groups + usize::from(idx < count % groups)).collect()
+}
+
+impl TreeReader for BTree {
+    fn get_page(&mut self, offset: &Offset) -> Result<Page, Error> {
+        self.pool.get_page(offset)
+    }
+
+    /// root_offset is the offset of the root as of the last operation, committed or part of
+    /// the open transaction.
+    fn root_offset(&mut self) -> Result<Offset, Error> {
+        match &self.txn_root {
+            Some(txn_root) => Ok(txn_root.clone()),
+            None => self.wal.get_root()
+        }
+    }
+
+    fn comparator(&self) -> Comparator {
+        self.cmp
+    }
+}
+
+impl Drop for BTree {
+    /// Write cached pages back on close so the next open has no WAL to replay,
+    /// a failure here only means a longer recovery.
+    fn drop(&mut self) {
+        let _ = self.flush();
+    }
+}
+#[cfg(test)]
+mod tests {
+    use std::{fs::{self, OpenOptions}, io::Write};
+
+    use super::*;
+    use crate::{inspect::dump, page_layout::{MAX_KEY_SIZE, PAGE_SIZE}, wal::{checkpoint_path, wal_path}};
+
+    fn open(path: &Path, truncate: bool) -> BTree {
+        BTreeBuilder::new().path(path).b_parameter(2).truncate(truncate).build().unwrap()
+    }
+
+    #[test]
+    fn test_reopen_keeps_data() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("db");
+
+        let mut tree = open(&path, false);
+        for i in 0..20 {
+            tree.insert(KeyValuePair::new(format!("key{:02}", i), format!("value{}", i))).unwrap();
+        }
+        drop(tree);
+
+        let mut tree = open(&path, false);
+        for i in 0..20 {
+            let kv = tree.search(format!("key{:02}", i)).unwrap();
+            assert_eq!(kv.value, format!("value{}", i).into_bytes());
+        }
+        tree.insert(KeyValuePair::new("key20".to_string(), "value20".to_string())).unwrap();
+        drop(tree);
+
+        let mut tree = open(&path, false);
+        assert_eq!(tree.search("key20").unwrap().value, b"value20");
+        assert_eq!(tree.search("key05").unwrap().value, b"value5");
+
+        let mut tree = open(&path, true);
+        assert!(matches!(tree.search("key05"), Err(Error::KeyNotFound)));
+    }
+
+    #[test]
+    fn test_reopen_keeps_the_branching_factor_of_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("db");
+        let mut tree = BTreeBuilder::new().path(&path).b_parameter(6).build().unwrap();
+        for i in 0..40 {
+            tree.insert(KeyValuePair::new(format!("key{:02}", i), "v")).unwrap();
+        }
+        drop(tree);
+
+        // Leaves already hold more than 2*2 pairs, the tree must keep splitting them at 2*6.
+        let mut tree = open(&path, false);
+        for i in 40..80 {
+            tree.insert(KeyValuePair::new(format!("key{:02}", i), "v")).unwrap();
+        }
+        assert_eq!(tree.verify().unwrap(), vec![]);
+        assert_eq!(tree.iter().count(), 80);
+        drop(tree);
+
+        assert!(dump(&path, &[Offset(HEADER_PAGE_OFFSET)]).unwrap().starts_with("page 0: header, b: 6,"));
+        let mut tree = open(&path, true);
+        tree.insert(KeyValuePair::new("key00", "v")).unwrap();
+        drop(tree);
+        assert!(dump(&path, &[Offset(HEADER_PAGE_OFFSET)]).unwrap().starts_with("page 0: header, b: 2,"));
+    }
+
+    #[test]
+    fn test_trees_in_one_directory_keep_their_own_log() {
+        let dir = tempfile::tempdir().unwrap();
+        let (first_path, second_path) = (dir.path().join("first"), dir.path().join("second"));
+
+        let mut first = open(&first_path, false);
+        let mut second = open(&second_path, false);
+        first.insert(KeyValuePair::new("a".to_string(), "first".to_string())).unwrap();
+        second.insert(KeyValuePair::new("a".to_string(), "second".to_string())).unwrap();
+        second.insert(KeyValuePair::new("b".to_string(), "second".to_string())).unwrap();
+        // Kill the process: both trees must be recovered from their own log.
+        std::mem::forget(first);
+        std::mem::forget(second);
+
+        let mut first = open(&first_path, false);
+        let mut second = open(&second_path, false);
+        assert_eq!(first.search("a").unwrap().value, b"first");
+        assert!(matches!(first.search("b"), Err(Error::KeyNotFound)));
+        assert_eq!(second.search("a").unwrap().value, b"second");
+        assert_eq!(second.search("b").unwrap().value, b"second");
+    }
+
+    #[test]
+    fn test_reopen_rejects_corrupted_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("db");
+
+        let mut tree = open(&path, false);
+        tree.insert(KeyValuePair::new("a".to_string(), "1".to_string())).unwrap();
+        drop(tree);
+
+        // A torn trailing page.
+        let file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.set_len(file.metadata().unwrap().len() + 100).unwrap();
+        let result = BTreeBuilder::new().path(&path).b_parameter(2).build();
+        assert!(matches!(result, Err(Error::CorruptedFile(_))));
+
+        // A full page of garbage.
+        file.set_len(file.metadata().unwrap().len() - 100).unwrap();
+        (&file).write_all(&[0xff; PAGE_SIZE]).unwrap();
+        let result = BTreeBuilder::new().path(&path).b_parameter(2).build();
+        assert!(matches!(result, Err(Error::CorruptedFile(_))));
+    }
+
+    #[test]
+    fn test_failed_operation_is_rolled_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("db");
+
+        let mut tree = open(&path, false);
+        for i in 0..4 {
+            tree.insert(KeyValuePair::new(format!("key{}", i), "v".to_string())).unwrap();
+        }
+        // The overflow pages and the root split are written before the oversized key is rejected.
+        let len = tree.pool.file_len();
+        let result = tree.insert(KeyValuePair::new("key4".repeat(20), "v".repeat(10_000)));
+        assert!(matches!(result, Err(Error::KeyOverflowError)));
+        assert_eq!(tree.pool.file_len(), len);
+        tree.insert(KeyValuePair::new("key5".to_string(), "v".to_string())).unwrap();
+        drop(tree);
+
+        let mut tree = open(&path, false);
+        assert!(matches!(tree.search("key4".repeat(20)), Err(Error::KeyNotFound)));
+        for key in ["key0", "key3", "key5"] {
+            assert_eq!(tree.search(key).unwrap().value, b"v");
+        }
+    }
+
+    #[test]
+    fn test_recovers_from_crash_at_any_point() {
+        let key = |i: usize| format!("key{:02}", i);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("db");
+
+        let mut tree = open(&path, false);
+        let initial_db = fs::read(&path).unwrap();
+        let mut commit_ends = vec![tree.wal.len()];
+        for i in 0..8 {
+            tree.insert(KeyValuePair::new(key(i), "value".to_string())).unwrap();
+            commit_ends.push(tree.wal.len());
+        }
+        // Kill the process: nothing is flushed or checkpointed.
+        std::mem::forget(tree);
+        let wal = fs::read(wal_path(&path)).unwrap();
+
+        // Recovers from the given tree file, log and, when a checkpoint was cut short, the
+        // new log it left aside.
+        let recover_with = |db: &[u8], wal: &[u8], checkpoint: Option<&[u8]>| {
+            let crash_dir = tempfile::tempdir().unwrap();
+            let crash_path = crash_dir.path().join("db");
+            fs::write(&crash_path, db).unwrap();
+            fs::write(wal_path(&crash_path), wal).unwrap();
+            if let Some(checkpoint) = checkpoint {
+                fs::write(checkpoint_path(&wal_path(&crash_path)), checkpoint).unwrap();
+            }
+            let mut tree = open(&crash_path, false);
+            (0..8).filter(|i| tree.search(key(*i)).is_ok()).count()
+        };
+        let recover = |db: &[u8], wal: &[u8]| recover_with(db, wal, None);
+
+        // Killed while appending to the log, before any page reached the tree file.
+        let cuts = (commit_ends[0]..wal.len()).step_by(331).chain(commit_ends.iter().copied());
+        for cut in cuts {
+            let committed = commit_ends.iter().filter(|end| **end <= cut).count() - 1;
+            assert_eq!(recover(&initial_db, &wal[..cut]), committed, "cut at {}", cut);
+        }
+
+        // Killed while writing committed pages back, leaving a garbage page and a torn one.
+        let mut torn = initial_db.clone();
+        torn.extend_from_slice(&[0xff; PAGE_SIZE + PAGE_SIZE / 2]);
+        assert_eq!(recover(&torn, &wal), 8);
+
+        // Killed while checkpointing, once every page reached the tree file: before the new log
+        // was written, while it was, and after it replaced the old one.
+        let checkpoint_dir = tempfile::tempdir().unwrap();
+        let checkpoint_db = checkpoint_dir.path().join("db");
+        fs::write(&checkpoint_db, &initial_db).unwrap();
+        fs::write(wal_path(&checkpoint_db), &wal).unwrap();
+        std::mem::forget(open(&checkpoint_db, false));
+        let flushed_db = fs::read(&checkpoint_db).unwrap();
+        let checkpointed_wal = fs::read(wal_path(&checkpoint_db)).unwrap();
+        assert!(checkpointed_wal.len() < commit_ends[1]);
+        assert_eq!(recover(&flushed_db, &wal), 8);
+        for cut in 0..checkpointed_wal.len() {
+            assert_eq!(recover_with(&flushed_db, &wal, Some(&checkpointed_wal[..cut])), 8, "checkpoint cut at {}", cut);
+        }
+        assert_eq!(recover(&flushed_db, &checkpointed_wal), 8);
+    }
+
+    #[test]
+    fn test_inserts_and_deletes_match_model() {
+        for b in [2, 3] {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("db");
+            let open = || BTreeBuilder::new().path(&path).b_parameter(b).build().unwrap();
+            let mut tree = open();
+            let mut model = std::collections::BTreeMap::new();
+
+            let mut seed: u64 = 42;
+            for step in 0..600usize {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                let key = format!("k{}", (seed >> 33) % 120);
+                if (seed >> 20).is_multiple_of(3) {
+                    match model.remove(&key) {
+                        Some(_) => tree.delete(Key::from(key)).unwrap(),
+                        None => assert!(matches!(tree.delete(Key::from(key)), Err(Error::KeyNotFound))),
+                    }
+                } else if model.contains_key(&key) {
+                    let result = tree.insert(KeyValuePair::new(key, "dup".to_string()));
+                    assert!(matches!(result, Err(Error::KeyAlreadyExist)));
+                } else {
+                    model.insert(key.clone(), step.to_string());
+                    tree.insert(KeyValuePair::new(key, step.to_string())).unwrap();
+                }
+
+                if step.is_multiple_of(50) {
+                    let pairs: Vec<(Vec<u8>, Vec<u8>)> = tree.iter().map(|kv| kv.map(|kv| (kv.key, kv.value))).collect::<Result<_, _>>().unwrap();
+                    assert_eq!(pairs, model.iter().map(|(k, v)| (k.clone().into_bytes(), v.clone().into_bytes())).collect::<Vec<_>>());
+                    assert_eq!(tree.verify().unwrap(), vec![]);
+                }
+            }
+            drop(tree);
+
+            let mut tree = open();
+            let keys: Vec<Vec<u8>> = tree.iter().rev().map(|kv| kv.unwrap().key).collect();
+            assert_eq!(keys, model.keys().rev().map(|k| k.clone().into_bytes()).collect::<Vec<_>>());
+            for key in model.keys().cloned().collect::<Vec<_>>() {
+                tree.delete(Key::from(key)).unwrap();
+            }
+            assert_eq!(tree.iter().count(), 0);
+        }
+    }
+
+    #[test]
+    fn test_freed_pages_are_reused_and_compacted() {
+        let key = |i: usize| format!("key{:03}", i);
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("db");
+
+        let mut tree = open(&path, false);
+        for i in 0..200 {
+            tree.insert(KeyValuePair::new(key(i), "v".to_string())).unwrap();
+        }
+        for i in 0..150 {
+            tree.delete(Key::from(key(i))).unwrap();
+        }
+        // The merges freed more pages than the new keys need.
+        let len = tree.pool.file_len();
+        for i in 0..40 {
+            tree.insert(KeyValuePair::new(key(i), "w".to_string())).unwrap();
+        }
+        assert_eq!(tree.pool.file_len(), len);
+
+        let reclaimed = tree.compact().unwrap();
+        assert!(reclaimed > 0);
+        assert_eq!(fs::metadata(&path).unwrap().len() as usize, len - reclaimed * PAGE_SIZE);
+        assert_eq!(tree.verify().unwrap(), vec![]);
+        assert_eq!(tree.compact().unwrap(), 0);
+        tree.insert(KeyValuePair::new(key(300), "x".to_string())).unwrap();
+        drop(tree);
+
+        let mut tree = open(&path, false);
+        let keys: Vec<Vec<u8>> = tree.iter().map(|kv| kv.unwrap().key).collect();
+        let expected: Vec<Vec<u8>> = (0..40).chain(150..200).chain([300]).map(|i| key(i).into_bytes()).collect();
+        assert_eq!(keys, expected);
+        assert_eq!(tree.search(key(7)).unwrap().value, b"w");
+        assert_eq!(tree.iter().rev().count(), expected.len());
+    }
+
+    #[test]
+    fn test_binary_keys_and_overflow_values() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("db");
+        let value = |len: usize| (0..len).map(|i| (i * 31 % 251) as u8).collect::<Vec<u8>>();
+        let lens = [0, INLINE_VALUE_SIZE, INLINE_VALUE_SIZE + 1, OVERFLOW_PAGE_CAPACITY, OVERFLOW_PAGE_CAPACITY + 1, 20_000];
+
+        let mut tree = open(&path, false);
+        for (i, len) in lens.iter().enumerate() {
+            tree.insert(KeyValuePair::new(vec![0xff, 0x00, i as u8], value(*len))).unwrap();
+        }
+        tree.insert(KeyValuePair::new(vec![0x00; MAX_KEY_SIZE], "max")).unwrap();
+        drop(tree);
+
+        let mut tree = open(&path, false);
+        for (i, len) in lens.iter().enumerate() {
+            assert_eq!(tree.search([0xff, 0x00, i as u8]).unwrap().value, value(*len));
+        }
+        let keys: Vec<Vec<u8>> = tree.iter().map(|kv| kv.unwrap().key).collect();
+        assert_eq!(keys[0], vec![0x00; MAX_KEY_SIZE]);
+        assert_eq!(keys[1..], (0..lens.len()).map(|i| vec![0xff, 0x00, i as u8]).collect::<Vec<_>>());
+
+        // Deleting the large values frees their overflow pages for the next one.
+        let len = tree.pool.file_len();
+        for i in 3..lens.len() {
+            tree.delete(Key(vec![0xff, 0x00, i as u8])).unwrap();
+        }
+        tree.insert(KeyValuePair::new("big", value(20_000))).unwrap();
+        assert_eq!(tree.pool.file_len(), len);
+        assert!(tree.compact().unwrap() > 0);
+        assert_eq!(tree.search("big").unwrap().value, value(20_000));
+    }
+
+    #[test]
+    fn test_custom_comparator() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("db");
+        let reverse: Comparator = |a, b| b.cmp(a);
+        let open = || BTreeBuilder::new().path(&path).b_parameter(2).comparator(reverse).build().unwrap();
+
+        let mut tree = open();
+        for i in 0..30u8 {
+            tree.insert(KeyValuePair::new([i], [i])).unwrap();
+        }
+        for i in (0..30u8).step_by(3) {
+            tree.delete(Key(vec![i])).unwrap();
+        }
+        drop(tree);
+
+        let mut tree = open();
+        let keys: Vec<u8> = tree.iter().map(|kv| kv.unwrap().key[0]).collect();
+        assert_eq!(keys, (0..30u8).rev().filter(|i| i % 3 != 0).collect::<Vec<_>>());
+        let keys: Vec<u8> = tree.range([20u8]..[10u8]).map(|kv| kv.unwrap().key[0]).collect();
+        assert_eq!(keys, [20, 19, 17, 16, 14, 13, 11]);
+        assert_eq!(tree.search([7]).unwrap().value, [7]);
+        assert!(BTreeBuilder::new().path(&path).b_parameter(MAX_BRANCHING_FACTOR + 1).build().is_err());
+    }
+
+    #[test]
+    fn test_transactions() {
+        let key = |i: usize| format!("key{:03}", i);
+        let keys = |tree: &mut BTree| tree.iter().map(|kv| String::from_utf8(kv.unwrap().key).unwrap()).collect::<Vec<_>>();
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("db");
+
+        let mut tree = open(&path, false);
+        for i in 0..20 {
+            tree.insert(KeyValuePair::new(key(i), "v")).unwrap();
+        }
+        let committed = keys(&mut tree);
+        let len = tree.pool.file_len();
+
+        // A rolled back transaction leaves no trace, even after splits, merges and overflow pages.
+        tree.begin().unwrap();
+        for i in 20..60 {
+            tree.insert(KeyValuePair::new(key(i), "v".repeat(5000))).unwrap();
+        }
+        for i in 0..15 {
+            tree.delete(Key::from(key(i))).unwrap();
+        }
+        assert_eq!(keys(&mut tree).len(), 45);
+        tree.rollback().unwrap();
+        assert_eq!(keys(&mut tree), committed);
+        assert_eq!(tree.pool.file_len(), len);
+
+        // A failing operation only undoes itself.
+        tree.begin().unwrap();
+        tree.insert(KeyValuePair::new(key(20), "w")).unwrap();
+        assert!(matches!(tree.insert(KeyValuePair::new("k".repeat(100), "v".repeat(5000))), Err(Error::KeyOverflowError)));
+        assert!(matches!(tree.insert(KeyValuePair::new(key(0), "w")), Err(Error::KeyAlreadyExist)));
+        tree.delete(Key::from(key(1))).unwrap();
+        assert!(matches!(tree.begin(), Err(Error::TransactionError(_))));
+        tree.commit().unwrap();
+        assert!(matches!(tree.commit(), Err(Error::TransactionError(_))));
+        assert!(matches!(tree.rollback(), Err(Error::TransactionError(_))));
+
+        // An open transaction is lost with the process, the committed one survives.
+        tree.begin().unwrap();
+        tree.insert(KeyValuePair::new(key(21), "w")).unwrap();
+        tree.delete(Key::from(key(0))).unwrap();
+        std::mem::forget(tree);
+        let mut tree = open(&path, false);
+        let expected: Vec<String> = (0..21).filter(|i| *i != 1).map(key).collect();
+        assert_eq!(keys(&mut tree), expected);
+        assert_eq!(tree.search(key(20)).unwrap().value, b"w");
+    }
+
+    #[test]
+    fn test_bulk_load() {
+        let key = |i: usize| format!("key{:05}", i);
+        let value = |i: usize| if i.is_multiple_of(100) { "x".repeat(5000) } else { format!("v{}", i) };
+        for (b, fill_factor) in [(2, 1.0), (3, 0.5), (MAX_BRANCHING_FACTOR, 0.7), (MAX_BRANCHING_FACTOR, 0.01)] {
+            let dir = tempfile::tempdir().unwrap();
+            let path = dir.path().join("db");
+            let mut tree = BTreeBuilder::new().path(&path).b_parameter(b).build().unwrap();
+            tree.bulk_load((0..3000).map(|i| KeyValuePair::new(key(i), value(i))), fill_factor).unwrap();
+            assert_eq!(tree.verify().unwrap(), vec![]);
+            drop(tree);
+
+            let mut tree = BTreeBuilder::new().path(&path).b_parameter(b).build().unwrap();
+            let pairs: Vec<KeyValuePair> = tree.iter().collect::<Result<_, _>>().unwrap();
+            assert_eq!(pairs, (0..3000).map(|i| KeyValuePair::new(key(i), value(i))).collect::<Vec<_>>());
+            assert_eq!(tree.iter().rev().count(), 3000);
+            for i in (0..3000).step_by(7) {
+                tree.delete(Key::from(key(i))).unwrap();
+            }
+            tree.insert(KeyValuePair::new(key(7), "again".to_string())).unwrap();
+            assert_eq!(tree.verify().unwrap(), vec![]);
+            assert_eq!(tree.search(key(7)).unwrap().value, b"again");
+        }
+
+        // Packed leaves take fewer pages than the half full ones left behind by splits.
+        let (dir, inserted_dir) = (tempfile::tempdir().unwrap(), tempfile::tempdir().unwrap());
+        let mut loaded = open(&dir.path().join("db"), true);
+        loaded.bulk_load((0..500).map(|i| KeyValuePair::new(key(i), "v")), 1.0).unwrap();
+        let mut inserted = open(&inserted_dir.path().join("db"), false);
+        for i in 0..500 {
+            inserted.insert(KeyValuePair::new(key(i), "v")).unwrap();
+        }
+        assert!(loaded.pool.file_len() < inserted.pool.file_len());
+
+        // Rejected input leaves the tree empty.
+        drop(loaded);
+        let mut tree = open(&dir.path().join("db"), true);
+        let unsorted = [KeyValuePair::new("b", "x".repeat(5000)), KeyValuePair::new("a", "1")];
+        assert!(matches!(tree.bulk_load(unsorted, 1.0), Err(Error::BulkLoadError(_))));
+        let duplicate = [KeyValuePair::new("a", "1"), KeyValuePair::new("a", "2")];
+        assert!(matches!(tree.bulk_load(duplicate, 1.0), Err(Error::BulkLoadError(_))));
+        assert!(matches!(tree.bulk_load([KeyValuePair::new("a", "1")], 0.0), Err(Error::BulkLoadError(_))));
+        assert_eq!(tree.iter().count(), 0);
+        assert_eq!(tree.verify().unwrap(), vec![]);
+        tree.insert(KeyValuePair::new("a", "1")).unwrap();
+        assert!(matches!(tree.bulk_load([KeyValuePair::new("b", "1")], 1.0), Err(Error::BulkLoadError(_))));
+    }
+
+}
@@ -0,0 +1,524 @@
+use std::{collections::{BTreeMap, HashMap}, sync::{Arc, Mutex, MutexGuard}};
+
+use crate::{error::Error, header::{free_page, next_free_page, FileHeader}, node_type::Offset, page::Page, page_layout::{HEADER_PAGE_OFFSET, PAGE_SIZE}, pager::{DirtyPages, Pager}};
+
+/// CacheStats counts buffer pool lookups since the tree was opened.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct CacheStats {
+    pub hits: u64,
+    pub misses: u64,
+    pub evictions: u64,
+    /// Cached pages that are committed to the WAL but not yet written to the tree file.
+    pub dirty_pages: usize,
+    /// Copies of overwritten pages kept for the snapshots that can still read them.
+    pub retained_pages: usize
+}
+
+struct Frame {
+    offset: Offset,
+    page: Page,
+    dirty: bool,
+    /// Set on every access, cleared as the clock hand passes.
+    referenced: bool
+}
+
+/// Cache keeps up to `capacity` committed pages in memory in front of the Pager,
+/// evicting with the CLOCK algorithm.
+///
+/// Committed pages are cached as dirty and only reach the tree file when evicted or flushed,
+/// the WAL already holds them.
+struct Cache {
+    pager: Pager,
+    capacity: usize,
+    frames: Vec<Frame>,
+    /// Maps page offsets to their index in frames.
+    page_table: HashMap<usize, usize>,
+    hand: usize,
+    stats: CacheStats,
+    /// End of the committed pages, cached or written back.
+    len: usize
+}
+
+impl Cache {
+    fn get_page(&mut self, offset: &Offset) -> Result<Page, Error> {
+        if let Some(&idx) = self.page_table.get(&offset.0) {
+            self.stats.hits += 1;
+            let frame = &mut self.frames[idx];
+            frame.referenced = true;
+            return Ok(frame.page.clone());
+        }
+        self.stats.misses += 1;
+        let page = self.pager.get_page(offset)?;
+        self.install(offset.clone(), page.clone(), false)?;
+        Ok(page)
+    }
+
+    fn install(&mut self, offset: Offset, page: Page, dirty: bool) -> Result<(), Error> {
+        if let Some(&idx) = self.page_table.get(&offset.0) {
+            let frame = &mut self.frames[idx];
+            if dirty && !frame.dirty {
+                self.stats.dirty_pages += 1;
+            }
+            frame.page = page;
+            frame.dirty |= dirty;
+            frame.referenced = true;
+            return Ok(());
+        }
+
+        if dirty {
+            self.stats.dirty_pages += 1;
+            self.len = self.len.max(offset.0 + PAGE_SIZE);
+        }
+        let frame = Frame { offset: offset.clone(), page, dirty, referenced: true };
+        if self.frames.len() < self.capacity {
+            self.page_table.insert(offset.0, self.frames.len());
+            self.frames.push(frame);
+            return Ok(());
+        }
+
+        let idx = self.next_victim();
+        let victim = std::mem::replace(&mut self.frames[idx], frame);
+        self.page_table.remove(&victim.offset.0);
+        self.page_table.insert(offset.0, idx);
+        self.stats.evictions += 1;
+        if victim.dirty {
+            self.stats.dirty_pages -= 1;
+            self.pager.write_to_file(&victim.offset, &victim.page)?;
+        }
+        Ok(())
+    }
+
+    /// next_victim sweeps the clock hand over the frames, giving every recently referenced
+    /// frame a second chance, and returns the first one that was not.
+    fn next_victim(&mut self) -> usize {
+        loop {
+            let idx = self.hand;
+            self.hand = (self.hand + 1) % self.frames.len();
+            let frame = &mut self.frames[idx];
+            if !frame.referenced {
+                return idx;
+            }
+            frame.referenced = false;
+        }
+    }
+
+    fn flush(&mut self) -> Result<(), Error> {
+        let mut dirty: Vec<&mut Frame> = self.frames.iter_mut().filter(|frame| frame.dirty).collect();
+        dirty.sort_by_key(|frame| frame.offset.0);
+        for frame in dirty {
+            self.pager.write_to_file(&frame.offset, &frame.page)?;
+            frame.dirty = false;
+        }
+        self.stats.dirty_pages = 0;
+        self.pager.sync()
+    }
+}
+
+/// Versions tracks the commits pinned by open snapshots. A commit that overwrites a page one of
+/// them can still read first copies the committed page aside, the snapshot reads the copy
+/// instead of the cache until it is dropped.
+struct Versions {
+    /// Number of the last commit since the tree was opened.
+    commit: u64,
+    root: Offset,
+    /// Number of open snapshots of each pinned commit.
+    pinned: BTreeMap<u64, usize>,
+    /// The copies of every page overwritten while pinned, with the commit that overwrote them,
+    /// oldest first.
+    retained: HashMap<usize, Vec<(u64, Page)>>
+}
+
+impl Versions {
+    /// is_pinned tells whether a snapshot still reads the page at offset from the cache, which
+    /// commit is about to change.
+    fn is_pinned(&self, offset: usize, commit: u64) -> bool {
+        let since = self.retained.get(&offset).and_then(|copies| copies.last()).map_or(0, |(overwritten, _)| *overwritten);
+        self.pinned.range(since..commit).next().is_some()
+    }
+
+    /// page_as_of returns the copy of the page at offset as of commit, None if it has not been
+    /// overwritten since.
+    fn page_as_of(&self, offset: usize, commit: u64) -> Option<&Page> {
+        let copies = self.retained.get(&offset)?;
+        copies.iter().find(|(overwritten, _)| *overwritten > commit).map(|(_, page)| page)
+    }
+
+    /// release frees the copies that no pinned commit reads anymore.
+    fn release(&mut self) {
+        for copies in self.retained.values_mut() {
+            let mut since = 0;
+            copies.retain(|(overwritten, _)| {
+                let read = self.pinned.range(since..*overwritten).next().is_some();
+                since = *overwritten;
+                read
+            });
+        }
+        self.retained.retain(|_, copies| !copies.is_empty());
+    }
+
+    fn retained_pages(&self) -> usize {
+        self.retained.values().map(Vec::len).sum()
+    }
+}
+
+struct State {
+    cache: Cache,
+    versions: Versions,
+    /// Set when a commit reached the WAL but could not be applied in full, the cache then
+    /// holds only part of it and the tree must be reopened to recover the rest from the WAL.
+    poisoned: bool
+}
+
+impl State {
+    /// retain copies the committed page at offset aside if a snapshot taken before commit
+    /// can still read it.
+    fn retain(&mut self, offset: &Offset, commit: u64) -> Result<(), Error> {
+        if offset.0 >= self.cache.len || !self.versions.is_pinned(offset.0, commit) {
+            return Ok(());
+        }
+        let page = self.cache.get_page(offset)?;
+        self.versions.retained.entry(offset.0).or_default().push((commit, page));
+        Ok(())
+    }
+}
+
+/// Shared is the committed state of a tree, shared by its writer and any number of readers.
+/// Readers pin a commit and only hold the lock while they load a page, commits never wait
+/// for them to finish.
+pub(crate) struct Shared {
+    state: Mutex<State>
+}
+
+impl Shared {
+    pub(crate) fn new(pager: Pager, capacity: usize, root: Offset) -> Shared {
+        let len = pager.len();
+        let cache = Cache {
+            pager,
+            capacity: capacity.max(1),
+            frames: Vec::with_capacity(capacity.max(1)),
+            page_table: HashMap::new(),
+            hand: 0,
+            stats: CacheStats::default(),
+            len
+        };
+        let versions = Versions { commit: 0, root, pinned: BTreeMap::new(), retained: HashMap::new() };
+        Shared { state: Mutex::new(State { cache, versions, poisoned: false }) }
+    }
+
+    /// pin returns the last commit and its root, the pages reachable from it stay readable
+    /// through get_page_as_of until the commit is unpinned.
+    pub(crate) fn pin(&self) -> Result<(u64, Offset), Error> {
+        let mut state = self.state()?;
+        let versions = &mut state.versions;
+        *versions.pinned.entry(versions.commit).or_default() += 1;
+        Ok((versions.commit, versions.root.clone()))
+    }
+
+    /// unpin releases a commit pinned by pin, freeing the copies only it was reading.
+    pub(crate) fn unpin(&self, commit: u64) -> Result<(), Error> {
+        let mut state = self.state()?;
+        let versions = &mut state.versions;
+        let count = versions.pinned.get_mut(&commit).ok_or(Error::UnexpectedError)?;
+        *count -= 1;
+        if *count == 0 {
+            versions.pinned.remove(&commit);
+            versions.release();
+        }
+        Ok(())
+    }
+
+    /// get_page returns the last committed version of the page at offset.
+    pub(crate) fn get_page(&self, offset: &Offset) -> Result<Page, Error> {
+        self.state()?.cache.get_page(offset)
+    }
+
+    /// get_page_as_of returns the page at offset as it was when the pinned commit was made.
+    pub(crate) fn get_page_as_of(&self, offset: &Offset, commit: u64) -> Result<Page, Error> {
+        let mut state = self.state()?;
+        if let Some(page) = state.versions.page_as_of(offset.0, commit) {
+            return Ok(page.clone());
+        }
+        state.cache.get_page(offset)
+    }
+
+    fn state(&self) -> Result<MutexGuard<'_, State>, Error> {
+        let state = self.state.lock().map_err(|_| Error::UnexpectedError)?;
+        if state.poisoned {
+            return Err(Error::TransactionError("a commit failed to apply, the tree must be reopened"));
+        }
+        Ok(state)
+    }
+}
+
+/// Savepoint remembers what the pending pages looked like before the operation in progress,
+/// so that it can be undone on its own.
+struct Savepoint {
+    curser: usize,
+    /// The pending version of every page the operation wrote, None if there was none.
+    undo: HashMap<usize, Option<Page>>
+}
+
+/// BufferPool is the writer's view of the tree file: the committed pages in the shared cache,
+/// shadowed by the pages written since the last commit.
+///
+/// Pending pages stay private to the writer until they are committed, so that a rollback
+/// never has to touch the cache and readers only ever see committed pages.
+pub struct BufferPool {
+    shared: Arc<Shared>,
+    dirty: HashMap<usize, Page>,
+    /// End of the file including the pages allocated since the last commit.
+    curser: usize,
+    committed_curser: usize,
+    savepoint: Option<Savepoint>
+}
+
+impl BufferPool {
+    pub(crate) fn new(shared: Arc<Shared>) -> Result<BufferPool, Error> {
+        let len = shared.state()?.cache.len;
+        Ok(BufferPool { shared, dirty: HashMap::new(), curser: len, committed_curser: len, savepoint: None })
+    }
+
+    pub(crate) fn shared(&self) -> Arc<Shared> {
+        self.shared.clone()
+    }
+
+    pub fn get_page(&self, offset: &Offset) -> Result<Page, Error> {
+        match self.dirty.get(&offset.0) {
+            Some(page) => Ok(page.clone()),
+            None => self.shared.get_page(offset)
+        }
+    }
+
+    pub fn write_page(&mut self, page: Page) -> Result<Offset, Error> {
+        let res = Offset(self.curser);
+        self.write_page_at_offset(page, &res)?;
+        self.curser += PAGE_SIZE;
+        Ok(res)
+    }
+
+    /// allocate_page stores page in the first page of the free list, or appends it to the
+    /// file when the list is empty.
+    pub fn allocate_page(&mut self, page: Page) -> Result<Offset, Error> {
+        let mut header = FileHeader::try_from(self.get_page(&Offset(HEADER_PAGE_OFFSET))?)?;
+        let Some(offset) = header.free_list_head else {
+            return self.write_page(page);
+        };
+        header.free_list_head = next_free_page(&self.get_page(&offset)?)?;
+        self.write_page_at_offset(Page::try_from(&header)?, &Offset(HEADER_PAGE_OFFSET))?;
+        self.write_page_at_offset(page, &offset)?;
+        Ok(offset)
+    }
+
+    /// free_page pushes the page at offset onto the free list.
+    pub fn free_page(&mut self, offset: &Offset) -> Result<(), Error> {
+        if offset.0 == HEADER_PAGE_OFFSET {
+            return Err(Error::UnexpectedError);
+        }
+        let mut header = FileHeader::try_from(self.get_page(&Offset(HEADER_PAGE_OFFSET))?)?;
+        self.write_page_at_offset(free_page(header.free_list_head.as_ref())?, offset)?;
+        header.free_list_head = Some(offset.clone());
+        self.write_page_at_offset(Page::try_from(&header)?, &Offset(HEADER_PAGE_OFFSET))
+    }
+
+    pub fn write_page_at_offset(&mut self, page: Page, offset: &Offset) -> Result<(), Error> {
+        let previous = self.dirty.insert(offset.0, page);
+        if let Some(savepoint) = &mut self.savepoint {
+            savepoint.undo.entry(offset.0).or_insert(previous);
+        }
+        Ok(())
+    }
+
+    /// set_savepoint marks the start of an operation that rollback_to_savepoint can undo
+    /// without discarding the pages written before it.
+    pub fn set_savepoint(&mut self) {
+        self.savepoint = Some(Savepoint { curser: self.curser, undo: HashMap::new() });
+    }
+
+    /// rollback_to_savepoint forgets the pages written since the last call to set_savepoint.
+    pub fn rollback_to_savepoint(&mut self) {
+        let Some(savepoint) = self.savepoint.take() else {
+            return self.rollback();
+        };
+        for (offset, previous) in savepoint.undo {
+            match previous {
+                Some(page) => self.dirty.insert(offset, page),
+                None => self.dirty.remove(&offset)
+            };
+        }
+        self.curser = savepoint.curser;
+    }
+
+    /// take_dirty drains the pages written since the last commit, ordered by offset.
+    pub fn take_dirty(&mut self) -> DirtyPages {
+        self.savepoint = None;
+        let mut pages: DirtyPages = self.dirty.drain().map(|(offset, page)| (Offset(offset), page)).collect();
+        pages.sort_by_key(|(offset, _)| offset.0);
+        pages
+    }
+
+    /// rollback forgets every page written since the last commit.
+    pub fn rollback(&mut self) {
+        self.savepoint = None;
+        self.dirty.clear();
+        self.curser = self.committed_curser;
+    }
+
+    /// apply caches pages that were just committed to the WAL and publishes root to readers.
+    /// The pages they overwrite are copied aside first for the snapshots still reading them.
+    ///
+    /// A failure leaves part of the commit cached and poisons the shared state, every later
+    /// read, write and flush fails so that neither the tree file nor a reader sees half of it.
+    pub fn apply(&mut self, pages: DirtyPages, root: Offset) -> Result<(), Error> {
+        let mut state = self.shared.state()?;
+        let commit = state.versions.commit + 1;
+        for (offset, page) in pages {
+            let installed = state.retain(&offset, commit).and_then(|_| state.cache.install(offset, page, true));
+            if let Err(err) = installed {
+                state.poisoned = true;
+                return Err(err);
+            }
+        }
+        state.versions.commit = commit;
+        state.versions.root = root;
+        self.committed_curser = self.curser;
+        Ok(())
+    }
+
+    /// flush writes every dirty page to the tree file and syncs it.
+    pub fn flush(&self) -> Result<(), Error> {
+        self.shared.state()?.cache.flush()
+    }
+
+    /// file_len is the size of the tree file once every page written so far reaches it.
+    pub fn file_len(&self) -> usize {
+        self.curser
+    }
+
+    /// truncate drops every page from offset len on, from the cache and the file.
+    /// The cache must have been flushed and nothing written since.
+    pub fn truncate(&mut self, len: usize) -> Result<(), Error> {
+        let mut state = self.shared.state()?;
+        if state.cache.stats.dirty_pages > 0 || !self.dirty.is_empty() {
+            return Err(Error::UnexpectedError);
+        }
+        // The last commit no longer reaches the pages cut off, but older snapshots may.
+        let commit = state.versions.commit;
+        for offset in (len..state.cache.len).step_by(PAGE_SIZE) {
+            state.retain(&Offset(offset), commit)?;
+        }
+        let cache = &mut state.cache;
+        cache.frames.retain(|frame| frame.offset.0 < len);
+        cache.page_table = cache.frames.iter().enumerate().map(|(idx, frame)| (frame.offset.0, idx)).collect();
+        cache.hand = 0;
+        cache.len = len;
+        cache.pager.truncate(len)?;
+        self.curser = len;
+        self.committed_curser = len;
+        Ok(())
+    }
+
+    pub fn stats(&self) -> CacheStats {
+        self.shared.state().map(|state| CacheStats { retained_pages: state.versions.retained_pages(), ..state.cache.stats }).unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::path::Path;
+
+    use crate::{btree::BTreeBuilder, node_type::KeyValuePair, page_layout::PAGE_SIZE};
+
+    use super::*;
+
+    fn page(byte: u8) -> Page {
+        Page::new([byte; PAGE_SIZE])
+    }
+
+    fn pool(pager: Pager, capacity: usize) -> BufferPool {
+        BufferPool::new(Arc::new(Shared::new(pager, capacity, Offset(0)))).unwrap()
+    }
+
+    #[test]
+    fn test_clock_eviction_and_write_back() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("db");
+        let mut pool = pool(Pager::new(&path, false).unwrap(), 2);
+
+        for byte in 0..3 {
+            pool.write_page(page(byte)).unwrap();
+        }
+        let pages = pool.take_dirty();
+        pool.apply(pages, Offset(0)).unwrap();
+        // Caching three committed pages in two frames wrote the first one back.
+        assert_eq!(pool.stats(), CacheStats { hits: 0, misses: 0, evictions: 1, dirty_pages: 2, retained_pages: 0 });
+        assert_eq!(std::fs::metadata(&path).unwrap().len(), PAGE_SIZE as u64);
+
+        // Reading page 0 back evicts the unreferenced page at PAGE_SIZE, not the one just hit.
+        assert_eq!(pool.get_page(&Offset(2 * PAGE_SIZE)).unwrap().get_data()[0], 2);
+        assert_eq!(pool.get_page(&Offset(0)).unwrap().get_data()[0], 0);
+        assert_eq!(pool.stats().hits, 1);
+        assert_eq!(pool.stats().misses, 1);
+        assert_eq!(pool.stats().evictions, 2);
+
+        pool.flush().unwrap();
+        assert_eq!(pool.stats().dirty_pages, 0);
+        assert_eq!(std::fs::read(&path).unwrap().len(), 3 * PAGE_SIZE);
+        let mut pager = Pager::new(&path, false).unwrap();
+        for byte in 0..3 {
+            assert_eq!(pager.get_page(&Offset(byte * PAGE_SIZE)).unwrap().get_data()[0], byte as u8);
+        }
+    }
+
+    #[test]
+    fn test_pending_pages_shadow_the_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut pool = pool(Pager::new(&dir.path().join("db"), false).unwrap(), 4);
+        let offset = pool.write_page(page(1)).unwrap();
+        let pages = pool.take_dirty();
+        pool.apply(pages, Offset(0)).unwrap();
+
+        pool.write_page_at_offset(page(2), &offset).unwrap();
+        assert_eq!(pool.get_page(&offset).unwrap().get_data()[0], 2);
+        pool.rollback();
+        assert_eq!(pool.get_page(&offset).unwrap().get_data()[0], 1);
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn test_failed_apply_poisons_the_pool() {
+        // Every write to /dev/full fails, so caching the second page cannot evict the first.
+        let mut pool = pool(Pager::new(Path::new("/dev/full"), false).unwrap(), 1);
+        pool.write_page(page(1)).unwrap();
+        pool.write_page(page(2)).unwrap();
+        let pages = pool.take_dirty();
+        assert!(matches!(pool.apply(pages, Offset(0)), Err(Error::UnexpectedError)));
+
+        assert!(matches!(pool.get_page(&Offset(0)), Err(Error::TransactionError(_))));
+        assert!(matches!(pool.flush(), Err(Error::TransactionError(_))));
+        assert!(matches!(pool.apply(vec![], Offset(0)), Err(Error::TransactionError(_))));
+    }
+
+    #[test]
+    fn test_tree_lookups_hit_the_cache() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tree = BTreeBuilder::new().path(dir.path().join("db")).b_parameter(2).cache_capacity(8).build().unwrap();
+        for i in 0..60 {
+            tree.insert(KeyValuePair::new(format!("k{:02}", i), "v".to_string())).unwrap();
+        }
+        assert!(tree.cache_stats().evictions > 0);
+
+        let before = tree.cache_stats();
+        for _ in 0..10 {
+            tree.search("k07").unwrap();
+        }
+        let after = tree.cache_stats();
+        assert!(after.hits >= before.hits + 10);
+        assert!(after.misses <= before.misses + 8);
+
+        tree.flush().unwrap();
+        assert_eq!(tree.cache_stats().dirty_pages, 0);
+        drop(tree);
+        let mut tree = BTreeBuilder::new().path(dir.path().join("db")).b_parameter(2).build().unwrap();
+        assert_eq!(tree.iter().count(), 60);
+    }
+}
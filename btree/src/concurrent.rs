@@ -0,0 +1,234 @@
+use std::{ops::{Bound, RangeBounds}, sync::{Arc, Mutex}};
+
+use crate::{btree::BTree, buffer_pool::Shared, cursor::{prefix_end, Cursor}, error::Error, node_type::{Comparator, Key, KeyValuePair, Offset}, page::Page, reader::TreeReader};
+
+/// SharedBTree is a thread safe handle to a BTree: any number of threads read from snapshots
+/// of the last commit while a single writer at a time modifies the tree.
+///
+/// Commits never wait for snapshots: the pages a commit overwrites are copied aside for as long
+/// as an older snapshot can read them, so a snapshot kept open holds on to that memory.
+#[derive(Clone)]
+pub struct SharedBTree {
+    writer: Arc<Mutex<BTree>>,
+    shared: Arc<Shared>,
+    cmp: Comparator
+}
+
+impl SharedBTree {
+    pub fn new(tree: BTree) -> SharedBTree {
+        SharedBTree { shared: tree.shared(), cmp: tree.comparator(), writer: Arc::new(Mutex::new(tree)) }
+    }
+
+    /// snapshot pins the last commit for reading, pages written since are not visible through it.
+    pub fn snapshot(&self) -> Result<Snapshot<'_>, Error> {
+        let (commit, root) = self.shared.pin()?;
+        Ok(Snapshot { shared: &self.shared, commit, root, cmp: self.cmp })
+    }
+
+    pub fn search(&self, key: impl AsRef<[u8]>) -> Result<KeyValuePair, Error> {
+        self.snapshot()?.search(key)
+    }
+
+    pub fn insert(&self, kv: KeyValuePair) -> Result<(), Error> {
+        self.write(|tree| tree.insert(kv))
+    }
+
+    pub fn delete(&self, key: Key) -> Result<(), Error> {
+        self.write(|tree| tree.delete(key))
+    }
+
+    /// write runs f with exclusive access to the tree, e.g. to group operations in a transaction.
+    pub fn write<T>(&self, f: impl FnOnce(&mut BTree) -> Result<T, Error>) -> Result<T, Error> {
+        let mut tree = self.writer.lock().map_err(|_| Error::UnexpectedError)?;
+        f(&mut tree)
+    }
+}
+
+/// Snapshot is a consistent view of the tree as of the commit it was taken at.
+pub struct Snapshot<'a> {
+    shared: &'a Shared,
+    commit: u64,
+    root: Offset,
+    cmp: Comparator
+}
+
+impl Drop for Snapshot<'_> {
+    fn drop(&mut self) {
+        let _ = self.shared.unpin(self.commit);
+    }
+}
+
+impl Snapshot<'_> {
+    pub fn search(&mut self, key: impl AsRef<[u8]>) -> Result<KeyValuePair, Error> {
+        let entry = self.find_entry(key.as_ref())?;
+        self.load_pair(entry)
+    }
+
+    /// range returns a cursor over the pairs whose keys fall within range, in key order.
+    pub fn range<K: AsRef<[u8]>, R: RangeBounds<K>>(&mut self, range: R) -> Cursor<'_> {
+        let to_owned = |bound: Bound<&K>| bound.map(|key| key.as_ref().to_vec());
+        Cursor::new(self, to_owned(range.start_bound()), to_owned(range.end_bound()))
+    }
+
+    /// scan_prefix returns a cursor over the pairs whose keys start with prefix.
+    pub fn scan_prefix(&mut self, prefix: impl AsRef<[u8]>) -> Cursor<'_> {
+        let prefix = prefix.as_ref();
+        Cursor::new(self, Bound::Included(prefix.to_vec()), prefix_end(prefix))
+    }
+
+    /// iter returns a cursor over every pair in key order.
+    pub fn iter(&mut self) -> Cursor<'_> {
+        Cursor::new(self, Bound::Unbounded, Bound::Unbounded)
+    }
+}
+
+impl TreeReader for Snapshot<'_> {
+    fn get_page(&mut self, offset: &Offset) -> Result<Page, Error> {
+        self.shared.get_page_as_of(offset, self.commit)
+    }
+
+    fn root_offset(&mut self) -> Result<Offset, Error> {
+        Ok(self.root.clone())
+    }
+
+    fn comparator(&self) -> Comparator {
+        self.cmp
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::atomic::{AtomicBool, Ordering}, thread};
+
+    use crate::btree::BTreeBuilder;
+
+    use super::*;
+
+    fn open(dir: &tempfile::TempDir) -> SharedBTree {
+        let tree = BTreeBuilder::new().path(dir.path().join("db")).b_parameter(2).cache_capacity(4).build().unwrap();
+        SharedBTree::new(tree)
+    }
+
+    #[test]
+    fn test_snapshots_only_see_commits() {
+        let dir = tempfile::tempdir().unwrap();
+        let tree = open(&dir);
+        tree.insert(KeyValuePair::new("a", "1")).unwrap();
+
+        tree.write(|tree| {
+            tree.begin()?;
+            tree.insert(KeyValuePair::new("b", "2"))?;
+            tree.delete(Key::from("a"))
+        }).unwrap();
+        let mut snapshot = tree.snapshot().unwrap();
+        assert_eq!(snapshot.search("a").unwrap().value, b"1");
+        assert!(matches!(snapshot.search("b"), Err(Error::KeyNotFound)));
+        drop(snapshot);
+
+        tree.write(|tree| tree.commit()).unwrap();
+        assert!(matches!(tree.search("a"), Err(Error::KeyNotFound)));
+        assert_eq!(tree.snapshot().unwrap().iter().count(), 1);
+    }
+
+    #[test]
+    fn test_commits_do_not_wait_for_snapshots() {
+        let dir = tempfile::tempdir().unwrap();
+        let tree = open(&dir);
+        for i in 0..20 {
+            tree.insert(KeyValuePair::new(format!("k{:02}", i), vec![i as u8; 100 * i])).unwrap();
+        }
+        let mut snapshot = tree.snapshot().unwrap();
+
+        // The writer overwrites, frees, reuses and finally truncates pages the snapshot reads.
+        let writer = {
+            let tree = tree.clone();
+            thread::spawn(move || {
+                for i in 0..20 {
+                    tree.delete(Key::from(format!("k{:02}", i))).unwrap();
+                    tree.insert(KeyValuePair::new(format!("n{:02}", i), vec![0xff; 50 * i])).unwrap();
+                }
+                for i in 0..15 {
+                    tree.delete(Key::from(format!("n{:02}", i))).unwrap();
+                }
+                tree.write(|tree| tree.compact()).unwrap();
+            })
+        };
+        writer.join().unwrap();
+
+        // Searches through the handle do not block on the snapshot still open on this thread.
+        assert!(matches!(tree.search("k03"), Err(Error::KeyNotFound)));
+        assert_eq!(tree.search("n17").unwrap().value, vec![0xff; 850]);
+        let pairs: Vec<KeyValuePair> = snapshot.iter().map(|pair| pair.unwrap()).collect();
+        assert_eq!(pairs.len(), 20);
+        for (i, pair) in pairs.iter().enumerate() {
+            assert_eq!(pair.key, format!("k{:02}", i).into_bytes());
+            assert_eq!(pair.value, vec![i as u8; 100 * i]);
+        }
+        assert!(tree.write(|tree| Ok(tree.cache_stats().retained_pages)).unwrap() > 0);
+
+        // A snapshot taken now sees the writes, and the copies go away with the last old snapshot.
+        assert_eq!(tree.snapshot().unwrap().iter().count(), 5);
+        drop(snapshot);
+        assert_eq!(tree.write(|tree| Ok(tree.cache_stats().retained_pages)).unwrap(), 0);
+    }
+
+    #[test]
+    fn test_concurrent_readers_and_writer() {
+        let dir = tempfile::tempdir().unwrap();
+        let tree = open(&dir);
+        let done = Arc::new(AtomicBool::new(false));
+
+        // Every transaction inserts or deletes a{i} and b{i} together, readers must never see one without the other.
+        let writer = {
+            let tree = tree.clone();
+            let done = done.clone();
+            thread::spawn(move || {
+                for i in 0..300 {
+                    tree.write(|tree| {
+                        tree.begin()?;
+                        tree.insert(KeyValuePair::new(format!("a{:03}", i), format!("{}", i)))?;
+                        tree.insert(KeyValuePair::new(format!("b{:03}", i), format!("{}", i)))?;
+                        if i % 3 == 0 {
+                            tree.delete(Key::from(format!("a{:03}", i / 2)))?;
+                            tree.delete(Key::from(format!("b{:03}", i / 2)))?;
+                        }
+                        tree.commit()
+                    }).unwrap();
+                }
+                done.store(true, Ordering::SeqCst);
+            })
+        };
+
+        let readers: Vec<_> = (0..4).map(|_| {
+            let tree = tree.clone();
+            let done = done.clone();
+            thread::spawn(move || {
+                let mut rounds = 0;
+                while !done.load(Ordering::SeqCst) || rounds == 0 {
+                    let mut snapshot = tree.snapshot().unwrap();
+                    let keys: Vec<Vec<u8>> = snapshot.iter().map(|pair| pair.unwrap().key).collect();
+                    assert!(keys.windows(2).all(|pair| pair[0] < pair[1]));
+                    let a_keys = keys.iter().filter(|key| key[0] == b'a').count();
+                    assert_eq!(a_keys * 2, keys.len());
+                    for key in keys.iter().filter(|key| key[0] == b'a') {
+                        let mut twin = key.clone();
+                        twin[0] = b'b';
+                        assert_eq!(snapshot.search(&twin).unwrap().key, twin);
+                    }
+                    rounds += 1;
+                }
+            })
+        }).collect();
+
+        writer.join().unwrap();
+        for reader in readers {
+            reader.join().unwrap();
+        }
+
+        let mut snapshot = tree.snapshot().unwrap();
+        assert_eq!(snapshot.scan_prefix("a").count(), 200);
+        assert_eq!(snapshot.range("b100".."b200").count(), 67);
+        assert!(matches!(snapshot.search("a010"), Err(Error::KeyNotFound)));
+        assert_eq!(snapshot.search("b299").unwrap().value, b"299");
+    }
+}
@@ -0,0 +1,266 @@
+use std::ops::Bound;
+
+use crate::{error::Error, node::Node, node_type::{Comparator, KeyValuePair, LeafEntry, NodeType, Offset}, reader::TreeReader};
+
+/// Cursor lazily walks the leaf chain of a BTree, yielding the pairs within its bounds
+/// in key order from the front or in reverse from the back.
+pub struct Cursor<'t> {
+    tree: &'t mut dyn TreeReader,
+    /// Keys not yet returned from either end lie between start and end.
+    start: Bound<Vec<u8>>,
+    end: Bound<Vec<u8>>,
+    front: Option<LeafPosition>,
+    back: Option<LeafPosition>,
+    done: bool
+}
+
+/// LeafPosition is a loaded leaf and the index of the next entry to look at.
+/// Walking backwards, idx is one past the next entry.
+struct LeafPosition {
+    entries: Vec<LeafEntry>,
+    idx: usize,
+    prev_leaf: Option<Offset>,
+    next_leaf: Option<Offset>
+}
+
+impl LeafPosition {
+    fn new(node: Node, from_back: bool) -> Result<LeafPosition, Error> {
+        match node.node_type {
+            NodeType::Leaf(entries) => Ok(LeafPosition {
+                idx: if from_back { entries.len() } else { 0 },
+                entries,
+                prev_leaf: node.prev_leaf,
+                next_leaf: node.next_leaf
+            }),
+            _ => Err(Error::UnexpectedError)
+        }
+    }
+}
+
+impl<'t> Cursor<'t> {
+    pub(crate) fn new(tree: &'t mut dyn TreeReader, start: Bound<Vec<u8>>, end: Bound<Vec<u8>>) -> Cursor<'t> {
+        Cursor { tree, start, end, front: None, back: None, done: false }
+    }
+
+    /// seek_leaf descends from the root to the leaf where keys at bound would be stored.
+    fn seek_leaf(&mut self, bound: &Bound<Vec<u8>>, from_back: bool) -> Result<LeafPosition, Error> {
+        let root_offset = self.tree.root_offset()?;
+        let mut node = self.tree.load_node(&root_offset)?;
+        loop {
+            let child_offset = match &node.node_type {
+                NodeType::Internal(children, keys) => {
+                    let idx = match bound {
+                        Bound::Included(key) | Bound::Excluded(key) => self.tree.child_index(keys, key),
+                        Bound::Unbounded if from_back => children.len().saturating_sub(1),
+                        Bound::Unbounded => 0
+                    };
+                    children.get(idx).ok_or(Error::UnexpectedError)?.clone()
+                }
+                _ => return LeafPosition::new(node, from_back)
+            };
+            node = self.tree.load_node(&child_offset)?;
+        }
+    }
+
+    fn next_front(&mut self) -> Result<Option<KeyValuePair>, Error> {
+        if self.front.is_none() {
+            let start = self.start.clone();
+            self.front = Some(self.seek_leaf(&start, false)?);
+        }
+        loop {
+            let Some(position) = self.front.as_mut() else {
+                return Ok(None);
+            };
+            if let Some(entry) = position.entries.get(position.idx) {
+                position.idx += 1;
+                let cmp = self.tree.comparator();
+                if !is_after(cmp, &self.start, &entry.key.0) {
+                    continue;
+                }
+                if !is_before(cmp, &self.end, &entry.key.0) {
+                    return Ok(None);
+                }
+                self.start = Bound::Excluded(entry.key.0.clone());
+                let entry = entry.clone();
+                return self.tree.load_pair(entry).map(Some);
+            }
+            let Some(next_offset) = position.next_leaf.clone() else {
+                return Ok(None);
+            };
+            self.front = Some(LeafPosition::new(self.tree.load_node(&next_offset)?, false)?);
+        }
+    }
+
+    fn next_back(&mut self) -> Result<Option<KeyValuePair>, Error> {
+        if self.back.is_none() {
+            let end = self.end.clone();
+            self.back = Some(self.seek_leaf(&end, true)?);
+        }
+        loop {
+            let Some(position) = self.back.as_mut() else {
+                return Ok(None);
+            };
+            if position.idx > 0 {
+                position.idx -= 1;
+                let entry = &position.entries[position.idx];
+                let cmp = self.tree.comparator();
+                if !is_before(cmp, &self.end, &entry.key.0) {
+                    continue;
+                }
+                if !is_after(cmp, &self.start, &entry.key.0) {
+                    return Ok(None);
+                }
+                self.end = Bound::Excluded(entry.key.0.clone());
+                let entry = entry.clone();
+                return self.tree.load_pair(entry).map(Some);
+            }
+            let Some(prev_offset) = position.prev_leaf.clone() else {
+                return Ok(None);
+            };
+            self.back = Some(LeafPosition::new(self.tree.load_node(&prev_offset)?, true)?);
+        }
+    }
+
+    fn finish(&mut self, result: Result<Option<KeyValuePair>, Error>) -> Option<Result<KeyValuePair, Error>> {
+        match result {
+            Ok(Some(pair)) => Some(Ok(pair)),
+            Ok(None) => {
+                self.done = true;
+                None
+            }
+            Err(err) => {
+                self.done = true;
+                Some(Err(err))
+            }
+        }
+    }
+}
+
+impl Iterator for Cursor<'_> {
+    type Item = Result<KeyValuePair, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = self.next_front();
+        self.finish(result)
+    }
+}
+
+impl DoubleEndedIterator for Cursor<'_> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let result = Cursor::next_back(self);
+        self.finish(result)
+    }
+}
+
+fn is_after(cmp: Comparator, start: &Bound<Vec<u8>>, key: &[u8]) -> bool {
+    match start {
+        Bound::Included(start) => cmp(key, start).is_ge(),
+        Bound::Excluded(start) => cmp(key, start).is_gt(),
+        Bound::Unbounded => true
+    }
+}
+
+fn is_before(cmp: Comparator, end: &Bound<Vec<u8>>, key: &[u8]) -> bool {
+    match end {
+        Bound::Included(end) => cmp(key, end).is_le(),
+        Bound::Excluded(end) => cmp(key, end).is_lt(),
+        Bound::Unbounded => true
+    }
+}
+
+/// prefix_end is the smallest key above every key starting with prefix: the prefix with its
+/// last byte incremented, dropping trailing bytes that cannot be incremented.
+pub(crate) fn prefix_end(prefix: &[u8]) -> Bound<Vec<u8>> {
+    let mut end = prefix.to_vec();
+    while let Some(last) = end.pop() {
+        if last < u8::MAX {
+            end.push(last + 1);
+            return Bound::Excluded(end);
+        }
+    }
+    Bound::Unbounded
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::btree::{BTree, BTreeBuilder};
+
+    use super::*;
+
+    fn keys(cursor: impl Iterator<Item = Result<KeyValuePair, Error>>) -> Vec<String> {
+        cursor.map(|pair| String::from_utf8(pair.unwrap().key).unwrap()).collect()
+    }
+
+    fn tree_with_keys(dir: &tempfile::TempDir, count: usize) -> BTree {
+        let mut tree = BTreeBuilder::new().path(dir.path().join("db")).b_parameter(2).build().unwrap();
+        // Insert out of order so that splits happen all over the tree.
+        for i in (0..count).map(|i| (i * 7) % count) {
+            tree.insert(KeyValuePair::new(format!("k{:03}", i), format!("v{}", i))).unwrap();
+        }
+        tree
+    }
+
+    #[test]
+    fn test_iter_forward_and_reverse() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tree = tree_with_keys(&dir, 50);
+        let expected: Vec<String> = (0..50).map(|i| format!("k{:03}", i)).collect();
+
+        assert_eq!(keys(tree.iter()), expected);
+        assert_eq!(keys(tree.iter().rev()), expected.iter().rev().cloned().collect::<Vec<_>>());
+
+        // Both ends meet in the middle without yielding a pair twice.
+        let mut cursor = tree.iter();
+        let mut seen = Vec::new();
+        while let (Some(front), back) = (cursor.next(), cursor.next_back()) {
+            seen.push(front.unwrap().key);
+            seen.extend(back.map(|pair| pair.unwrap().key));
+        }
+        seen.sort();
+        assert_eq!(seen, expected.iter().map(|key| key.as_bytes()).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn test_range_bounds() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tree = tree_with_keys(&dir, 50);
+
+        assert_eq!(keys(tree.range("k010".."k014")), ["k010", "k011", "k012", "k013"]);
+        assert_eq!(keys(tree.range("k010"..="k012").rev()), ["k012", "k011", "k010"]);
+        assert_eq!(keys(tree.range("k0105".."k012")), ["k011"]);
+        assert_eq!(keys(tree.range(.."k002")), ["k000", "k001"]);
+        assert_eq!(keys(tree.range("k048".to_string()..)), ["k048", "k049"]);
+        assert!(keys(tree.range("x"..)).is_empty());
+        assert!(keys(tree.range("k020".."k010")).is_empty());
+
+        let pair = tree.range("k007"..).next().unwrap().unwrap();
+        assert_eq!(pair.value, b"v7");
+    }
+
+    #[test]
+    fn test_scan_prefix() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut tree = tree_with_keys(&dir, 50);
+        for key in ["k01", "k01a", "k02\u{10ffff}"] {
+            tree.insert(KeyValuePair::new(key.to_string(), String::new())).unwrap();
+        }
+
+        let expected = ["k01", "k010", "k011", "k012", "k013", "k014", "k015", "k016", "k017", "k018", "k019", "k01a"];
+        assert_eq!(keys(tree.scan_prefix("k01")), expected);
+        assert_eq!(keys(tree.scan_prefix("k01").rev()).len(), expected.len());
+        assert_eq!(keys(tree.scan_prefix("k02\u{10ffff}")), ["k02\u{10ffff}"]);
+        assert!(keys(tree.scan_prefix("j")).is_empty());
+        assert_eq!(keys(tree.scan_prefix("")).len(), 53);
+
+        assert_eq!(prefix_end(b"ab"), Bound::Excluded(b"ac".to_vec()));
+        assert_eq!(prefix_end(&[b'a', 0xff, 0xff]), Bound::Excluded(b"b".to_vec()));
+        assert_eq!(prefix_end(&[0xff]), Bound::Unbounded);
+        assert_eq!(prefix_end(b""), Bound::Unbounded);
+    }
+}
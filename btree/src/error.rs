@@ -1,3 +1,4 @@
+#[derive(Debug)]
 pub enum Error {
     KeyNotFound,
     KeyAlreadyExist,
@@ -5,11 +6,14 @@ pub enum Error {
     KeyOverflowError,
     ValueOverflowError,
     TryFromSliceError(&'static str),
-    UTF8Error
+    UTF8Error,
+    CorruptedFile(&'static str),
+    TransactionError(&'static str),
+    BulkLoadError(&'static str)
 }
 
 impl From<std::io::Error> for Error {
     fn from(_: std::io::Error) -> Self {
         Error::UnexpectedError
     }
-}
\ No newline at end of file
+}
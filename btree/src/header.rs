@@ -0,0 +1,84 @@
+use crate::{error::Error, node_type::Offset, page::Page, page_layout::{BRANCHING_FACTOR_OFFSET, FREE_LIST_HEAD_OFFSET, FREE_PAGE_NEXT_OFFSET, FREE_PAGE_TYPE, HEADER_MAGIC, HEADER_MAGIC_OFFSET, HEADER_PAGE_TYPE, NODE_TYPE_OFFSET, NO_FREE_PAGE, PAGE_SIZE}};
+
+/// FileHeader is stored in the first page of the tree file, it records the branching factor
+/// the tree was built with and points at the first page of the free list.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct FileHeader {
+    pub free_list_head: Option<Offset>,
+    pub b: usize
+}
+
+impl FileHeader {
+    pub fn new(b: usize) -> FileHeader {
+        FileHeader { free_list_head: None, b }
+    }
+}
+
+impl TryFrom<Page> for FileHeader {
+    type Error = Error;
+    fn try_from(page: Page) -> Result<Self, Self::Error> {
+        if page.get_data()[NODE_TYPE_OFFSET] != HEADER_PAGE_TYPE
+            || page.get_ptr_from_offset(HEADER_MAGIC_OFFSET, HEADER_MAGIC.len()) != HEADER_MAGIC {
+            return Err(Error::CorruptedFile("missing file header page"));
+        }
+        Ok(FileHeader {
+            free_list_head: offset_or_none(page.get_value_from_offset(FREE_LIST_HEAD_OFFSET)?),
+            b: page.get_value_from_offset(BRANCHING_FACTOR_OFFSET)?
+        })
+    }
+}
+
+impl TryFrom<&FileHeader> for Page {
+    type Error = Error;
+    fn try_from(header: &FileHeader) -> Result<Self, Self::Error> {
+        let mut page = Page::new([0x00; PAGE_SIZE]);
+        page.write_bytes_at_offset(&[HEADER_PAGE_TYPE], NODE_TYPE_OFFSET, 1)?;
+        page.write_bytes_at_offset(HEADER_MAGIC, HEADER_MAGIC_OFFSET, HEADER_MAGIC.len())?;
+        page.write_value_at_offset(FREE_LIST_HEAD_OFFSET, header.free_list_head.as_ref().map_or(NO_FREE_PAGE, |offset| offset.0))?;
+        page.write_value_at_offset(BRANCHING_FACTOR_OFFSET, header.b)?;
+        Ok(page)
+    }
+}
+
+/// free_page encodes a page on the free list, linking to the next free page.
+pub fn free_page(next: Option<&Offset>) -> Result<Page, Error> {
+    let mut page = Page::new([0x00; PAGE_SIZE]);
+    page.write_bytes_at_offset(&[FREE_PAGE_TYPE], NODE_TYPE_OFFSET, 1)?;
+    page.write_value_at_offset(FREE_PAGE_NEXT_OFFSET, next.map_or(NO_FREE_PAGE, |offset| offset.0))?;
+    Ok(page)
+}
+
+/// next_free_page decodes the link stored in a page on the free list.
+pub fn next_free_page(page: &Page) -> Result<Option<Offset>, Error> {
+    if page.get_data()[NODE_TYPE_OFFSET] != FREE_PAGE_TYPE {
+        return Err(Error::CorruptedFile("free list links to a page in use"));
+    }
+    Ok(offset_or_none(page.get_value_from_offset(FREE_PAGE_NEXT_OFFSET)?))
+}
+
+fn offset_or_none(value: usize) -> Option<Offset> {
+    match value {
+        NO_FREE_PAGE => None,
+        offset => Some(Offset(offset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_header_and_free_page_round_trip() {
+        for head in [None, Some(Offset(3 * PAGE_SIZE))] {
+            let header = FileHeader { free_list_head: head, b: 3 };
+            assert_eq!(FileHeader::try_from(Page::try_from(&header).unwrap()).unwrap(), header);
+            let page = free_page(header.free_list_head.as_ref()).unwrap();
+            assert_eq!(next_free_page(&page).unwrap(), header.free_list_head);
+            page.check_header().unwrap();
+        }
+
+        let page = Page::new([0x00; PAGE_SIZE]);
+        assert!(matches!(FileHeader::try_from(page.clone()), Err(Error::CorruptedFile(_))));
+        assert!(matches!(next_free_page(&page), Err(Error::CorruptedFile(_))));
+    }
+}
@@ -0,0 +1,302 @@
+use std::{collections::HashSet, path::Path};
+
+use crate::{error::Error, header::{next_free_page, FileHeader}, node::Node, node_type::{Key, LeafEntry, NodeType, Offset, StoredValue}, overflow::read_overflow_page, page::Page, page_layout::{FREE_PAGE_TYPE, HEADER_PAGE_OFFSET, HEADER_PAGE_TYPE, NODE_TYPE_OFFSET, OVERFLOW_PAGE_TYPE, PAGE_SIZE}, pager::Pager, reader::TreeReader};
+
+/// Violation is an inconsistency found in the page at offset.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Violation {
+    pub offset: Offset,
+    pub problem: &'static str
+}
+
+/// Visit is a node still to be checked, with the bounds its keys must fall within.
+struct Visit {
+    offset: Offset,
+    parent: Option<Offset>,
+    lower: Option<Key>,
+    upper: Option<Key>,
+    depth: usize
+}
+
+/// verify_tree walks every node, overflow chain and free page of a tree with branching factor b,
+/// reporting the inconsistencies found along the way.
+pub(crate) fn verify_tree(tree: &mut dyn TreeReader, b: usize, file_len: usize) -> Result<Vec<Violation>, Error> {
+    let cmp = tree.comparator();
+    let mut violations = Vec::new();
+    let mut report = |offset: &Offset, problem: &'static str| violations.push(Violation { offset: offset.clone(), problem });
+    let mut seen = HashSet::from([HEADER_PAGE_OFFSET]);
+    let mut leaves: Vec<(Offset, Option<Offset>, Option<Offset>)> = Vec::new();
+    let mut leaf_depth = None;
+
+    let mut stack = vec![Visit { offset: tree.root_offset()?, parent: None, lower: None, upper: None, depth: 0 }];
+    while let Some(visit) = stack.pop() {
+        let offset = visit.offset;
+        if !seen.insert(offset.0) {
+            report(&offset, "page is referenced twice");
+            continue;
+        }
+        let node = match tree.get_page(&offset).and_then(|page| {
+            page.check_header()?;
+            Node::try_from(page)
+        }) {
+            Ok(node) => node,
+            Err(Error::CorruptedFile(problem)) => {
+                report(&offset, problem);
+                continue;
+            }
+            Err(_) => {
+                report(&offset, "page does not hold a node");
+                continue;
+            }
+        };
+        if node.is_root != visit.parent.is_none() {
+            report(&offset, "root flag does not match the position of the node");
+        }
+        if visit.parent.is_some() && node.parent_offset != visit.parent {
+            report(&offset, "parent offset does not point at the parent");
+        }
+
+        let (keys, min, max): (Vec<&Key>, usize, usize) = match &node.node_type {
+            NodeType::Internal(_, keys) => (keys.iter().collect(), if node.is_root { 1 } else { b - 1 }, 2 * b - 1),
+            NodeType::Leaf(entries) => (entries.iter().map(|entry| &entry.key).collect(), if node.is_root { 0 } else { b - 1 }, 2 * b),
+            NodeType::Unexpected => return Err(Error::UnexpectedError)
+        };
+        if keys.len() < min {
+            report(&offset, "node holds fewer entries than b allows");
+        }
+        if keys.len() > max {
+            report(&offset, "node holds more entries than b allows");
+        }
+        if !keys.windows(2).all(|pair| cmp(&pair[0].0, &pair[1].0).is_lt()) {
+            report(&offset, "keys are out of order");
+        }
+        let below_lower = |key: &&Key| visit.lower.as_ref().is_some_and(|lower| cmp(&key.0, &lower.0).is_le());
+        let above_upper = |key: &&Key| visit.upper.as_ref().is_some_and(|upper| cmp(&key.0, &upper.0).is_gt());
+        if keys.first().is_some_and(below_lower) || keys.last().is_some_and(above_upper) {
+            report(&offset, "keys fall outside the separators of the parent");
+        }
+
+        match node.node_type {
+            NodeType::Internal(children, keys) => {
+                for (idx, child) in children.into_iter().enumerate().rev() {
+                    stack.push(Visit {
+                        offset: child,
+                        parent: Some(offset.clone()),
+                        lower: if idx == 0 { visit.lower.clone() } else { keys.get(idx - 1).cloned() },
+                        upper: keys.get(idx).cloned().or_else(|| visit.upper.clone()),
+                        depth: visit.depth + 1
+                    });
+                }
+            }
+            NodeType::Leaf(entries) => {
+                if *leaf_depth.get_or_insert(visit.depth) != visit.depth {
+                    report(&offset, "leaves are at different depths");
+                }
+                for entry in &entries {
+                    match tree.overflow_chain(&entry.value) {
+                        Ok(pages) => {
+                            for (page_offset, _) in pages {
+                                if !seen.insert(page_offset.0) {
+                                    report(&page_offset, "page is referenced twice");
+                                }
+                            }
+                        }
+                        Err(Error::CorruptedFile(problem)) => report(&offset, problem),
+                        Err(err) => return Err(err)
+                    }
+                }
+                leaves.push((offset, node.prev_leaf, node.next_leaf));
+            }
+            NodeType::Unexpected => return Err(Error::UnexpectedError)
+        }
+    }
+
+    for (idx, (offset, prev, next)) in leaves.iter().enumerate() {
+        let expected_prev = idx.checked_sub(1).map(|prev_idx| leaves[prev_idx].0.clone());
+        let expected_next = leaves.get(idx + 1).map(|(next_offset, _, _)| next_offset.clone());
+        if *prev != expected_prev || *next != expected_next {
+            report(offset, "leaf sibling links are out of key order");
+        }
+    }
+
+    match FileHeader::try_from(tree.get_page(&Offset(HEADER_PAGE_OFFSET))?) {
+        Ok(header) => {
+            let mut next = header.free_list_head;
+            while let Some(offset) = next {
+                if !seen.insert(offset.0) {
+                    report(&offset, "free list links to a page in use");
+                    break;
+                }
+                match tree.get_page(&offset).and_then(|page| next_free_page(&page)) {
+                    Ok(next_page) => next = next_page,
+                    Err(Error::CorruptedFile(problem)) => {
+                        report(&offset, problem);
+                        break;
+                    }
+                    Err(err) => return Err(err)
+                }
+            }
+        }
+        Err(Error::CorruptedFile(problem)) => report(&Offset(HEADER_PAGE_OFFSET), problem),
+        Err(err) => return Err(err)
+    }
+
+    for offset in (PAGE_SIZE..file_len).step_by(PAGE_SIZE) {
+        if !seen.contains(&offset) {
+            report(&Offset(offset), "page is unreachable");
+        }
+    }
+    Ok(violations)
+}
+
+/// dump describes the pages of the tree file at path, or every page when offsets is empty.
+/// Pages only committed to the WAL are not in the file yet, opening the tree checkpoints them.
+pub fn dump(path: &Path, offsets: &[Offset]) -> Result<String, Error> {
+    let mut pager = Pager::new(path, false)?;
+    let offsets = match offsets {
+        [] => pager.page_offsets().collect(),
+        offsets => offsets.to_vec()
+    };
+    let mut out = String::new();
+    for offset in offsets {
+        out.push_str(&format!("page {}: {}\n", offset.0, describe_page(&pager.get_page(&offset)?)));
+    }
+    Ok(out)
+}
+
+/// describe_page decodes the header and contents of a page of any type.
+pub fn describe_page(page: &Page) -> String {
+    match page.get_data()[NODE_TYPE_OFFSET] {
+        HEADER_PAGE_TYPE => match FileHeader::try_from(page.clone()) {
+            Ok(header) => format!("header, b: {}, free list head: {}", header.b, describe_offset(header.free_list_head.as_ref())),
+            Err(_) => "header with a bad magic".to_string()
+        },
+        FREE_PAGE_TYPE => match next_free_page(page) {
+            Ok(next) => format!("free, next: {}", describe_offset(next.as_ref())),
+            Err(_) => "free with a bad link".to_string()
+        },
+        OVERFLOW_PAGE_TYPE => match read_overflow_page(page) {
+            Ok((next, chunk)) => format!("overflow, next: {}, {} bytes: {}", describe_offset(next.as_ref()), chunk.len(), chunk.escape_ascii()),
+            Err(_) => "overflow longer than a page".to_string()
+        },
+        _ => match page.check_header().and_then(|_| Node::try_from(page.clone())) {
+            Ok(node) => describe_node(&node),
+            Err(Error::CorruptedFile(problem)) => format!("unreadable, {}", problem),
+            Err(_) => "unreadable".to_string()
+        }
+    }
+}
+
+fn describe_node(node: &Node) -> String {
+    let mut out = format!("{}parent: {}", if node.is_root { "root, " } else { "" }, describe_offset(node.parent_offset.as_ref()));
+    match &node.node_type {
+        NodeType::Internal(children, keys) => {
+            out = format!("internal, {}, {} children", out, children.len());
+            for (idx, child) in children.iter().enumerate() {
+                out.push_str(&format!("\n  child {}", child.0));
+                if let Some(key) = keys.get(idx) {
+                    out.push_str(&format!("\n  key {}", key.0.escape_ascii()));
+                }
+            }
+        }
+        NodeType::Leaf(entries) => {
+            out = format!("leaf, {}, prev: {}, next: {}, {} pairs", out, describe_offset(node.prev_leaf.as_ref()), describe_offset(node.next_leaf.as_ref()), entries.len());
+            for LeafEntry { key, value } in entries {
+                let value = match value {
+                    StoredValue::Inline(value) => format!("{}", value.escape_ascii()),
+                    StoredValue::Overflow { len, first_page } => format!("{} bytes from overflow page {}", len, first_page.0)
+                };
+                out.push_str(&format!("\n  {} => {}", key.0.escape_ascii(), value));
+            }
+        }
+        NodeType::Unexpected => out = "unknown node type".to_string()
+    }
+    out
+}
+
+fn describe_offset(offset: Option<&Offset>) -> String {
+    offset.map_or("none".to_string(), |offset| offset.0.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{btree::{BTree, BTreeBuilder}, node_type::KeyValuePair};
+
+    use super::*;
+
+    fn open(path: &Path) -> BTree {
+        BTreeBuilder::new().path(path).b_parameter(2).build().unwrap()
+    }
+
+    /// rewrite_first_leaf decodes the first non root leaf of the file, changes it with f and writes it back.
+    fn rewrite_first_leaf(path: &Path, f: impl FnOnce(&mut Node)) -> Offset {
+        let mut pager = Pager::new(path, false).unwrap();
+        for offset in pager.page_offsets().collect::<Vec<_>>() {
+            let Ok(mut node) = Node::try_from(pager.get_page(&offset).unwrap()) else {
+                continue;
+            };
+            if matches!(node.node_type, NodeType::Leaf(_)) && !node.is_root {
+                f(&mut node);
+                pager.write_to_file(&offset, &Page::try_from(&node).unwrap()).unwrap();
+                return offset;
+            }
+        }
+        panic!("no leaf to rewrite");
+    }
+
+    #[test]
+    fn test_verify_reports_violations() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("db");
+        let mut tree = open(&path);
+        for i in 0..30 {
+            tree.insert(KeyValuePair::new(format!("k{:02}", i), "v".repeat(i * 10))).unwrap();
+        }
+        assert_eq!(tree.verify().unwrap(), vec![]);
+        drop(tree);
+
+        let leaf = rewrite_first_leaf(&path, |node| {
+            if let NodeType::Leaf(entries) = &mut node.node_type {
+                entries.swap(0, 1);
+            }
+            node.parent_offset = Some(Offset(HEADER_PAGE_OFFSET));
+        });
+        let violations = open(&path).verify().unwrap();
+        assert!(violations.contains(&Violation { offset: leaf.clone(), problem: "keys are out of order" }));
+        assert!(violations.contains(&Violation { offset: leaf, problem: "parent offset does not point at the parent" }));
+
+        // A page nothing links to.
+        let mut pager = Pager::new(&path, false).unwrap();
+        let len = pager.len();
+        pager.write_to_file(&Offset(len), &Page::new([0x00; PAGE_SIZE])).unwrap();
+        let mut page = pager.get_page(&Offset(len)).unwrap();
+        page.write_bytes_at_offset(&[FREE_PAGE_TYPE], NODE_TYPE_OFFSET, 1).unwrap();
+        pager.write_to_file(&Offset(len), &page).unwrap();
+        drop(pager);
+        let violations = open(&path).verify().unwrap();
+        assert!(violations.contains(&Violation { offset: Offset(len), problem: "page is unreachable" }));
+    }
+
+    #[test]
+    fn test_dump_describes_every_page() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("db");
+        let mut tree = open(&path);
+        for i in 0..5 {
+            tree.insert(KeyValuePair::new(format!("k{}", i), format!("v{}", i))).unwrap();
+        }
+        tree.insert(KeyValuePair::new(b"\x00bin".to_vec(), "x".repeat(5000))).unwrap();
+        tree.delete("k0".into()).unwrap();
+        drop(tree);
+
+        let out = dump(&path, &[]).unwrap();
+        assert!(out.starts_with("page 0: header, b: 2, free list head: none\n"));
+        assert!(out.contains("internal, root, parent: none, 2 children"));
+        assert!(out.contains("\\x00bin => 5000 bytes from overflow page"));
+        assert!(out.contains("k3 => v3"));
+        assert!(out.contains("overflow, next: none, 922 bytes: xxx"));
+
+        let out = dump(&path, &[Offset(PAGE_SIZE)]).unwrap();
+        assert_eq!(out, format!("page {}: leaf, parent: {}, prev: none, next: {}, 2 pairs\n  \\x00bin => 5000 bytes from overflow page {}\n  k1 => v1\n", PAGE_SIZE, 2 * PAGE_SIZE, 3 * PAGE_SIZE, 5 * PAGE_SIZE));
+    }
+}
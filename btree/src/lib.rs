@@ -1,8 +1,15 @@
 pub mod btree;
+pub mod buffer_pool;
+pub mod concurrent;
+pub mod cursor;
 pub mod error;
+mod header;
+pub mod inspect;
 pub mod node;
 pub mod node_type;
+mod overflow;
 pub mod page;
 mod page_layout;
 mod pager;
+mod reader;
 mod wal;
\ No newline at end of file
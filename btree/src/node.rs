@@ -1,11 +1,14 @@
-use crate::{error::Error, node_type::{Key, KeyValuePair, NodeType, Offset}, page::Page, page_layout::{FromByte, INTERNAL_NODE_HEADER_SIZE, INTERNAL_NODE_NUM_CHILDREN_OFFSET, IS_ROOT_OFFSET, KEY_SIZE, LEAF_NODE_HEADER_SIZE, LEAF_NODE_NUM_PAIRS_OFFSET, NODE_TYPE_OFFSET, PARENT_POINTER_OFFSET, PTR_SIZE, VALUE_SIZE}};
+use crate::{error::Error, node_type::{Key, LeafEntry, NodeType, Offset, StoredValue}, page::{Page, Value}, page_layout::{FromByte, INLINE_VALUE_SIZE, INTERNAL_NODE_HEADER_SIZE, INTERNAL_NODE_NUM_CHILDREN_OFFSET, IS_ROOT_OFFSET, KEY_LEN_SIZE, KEY_SIZE, LEAF_NODE_HEADER_SIZE, LEAF_NODE_NEXT_LEAF_OFFSET, LEAF_NODE_NUM_PAIRS_OFFSET, LEAF_NODE_PREV_LEAF_OFFSET, MAX_KEY_SIZE, NODE_TYPE_OFFSET, NO_SIBLING, PARENT_POINTER_OFFSET, PTR_SIZE, VALUE_LEN_SIZE, VALUE_SIZE}};
 
 /// Node represents a node in the BTree occupied by a single page in memory.
 #[derive(Clone, Debug)]
 pub struct Node {
     pub node_type: NodeType,
     pub is_root: bool,
-    pub parent_offset: Option<Offset>
+    pub parent_offset: Option<Offset>,
+    /// Neighbouring leaves in key order, unused for internal nodes.
+    pub prev_leaf: Option<Offset>,
+    pub next_leaf: Option<Offset>
 }
 
 impl Node {
@@ -13,7 +16,9 @@ impl Node {
         Node {
             node_type,
             is_root,
-            parent_offset
+            parent_offset,
+            prev_leaf: None,
+            next_leaf: None
         }
     }
 
@@ -35,7 +40,7 @@ impl Node {
                 let median_pair = pairs.get(b - 1).ok_or(Error::UnexpectedError)?.clone();
 
                 Ok((
-                    Key(median_pair.key),
+                    median_pair.key,
                     Node::new(
                         NodeType::Leaf(sibling_pairs),
                         false,
@@ -54,12 +59,11 @@ impl TryFrom<Page> for Node {
         let raw = page.get_data();
         let node_type = NodeType::from(raw[NODE_TYPE_OFFSET]);
         let is_root = raw[IS_ROOT_OFFSET].from_byte();
-        let parent_offset: Option<Offset>;
-        if is_root {
-            parent_offset = None;
+        let parent_offset = if is_root {
+            None
         } else {
-            parent_offset = Some(Offset(page.get_value_from_offset(PARENT_POINTER_OFFSET)?));
-        }
+            Some(Offset(page.get_value_from_offset(PARENT_POINTER_OFFSET)?))
+        };
         match node_type {
             NodeType::Internal(mut children, mut keys ) => {
                 let num_children = page.get_value_from_offset(INTERNAL_NODE_NUM_CHILDREN_OFFSET)?;
@@ -72,13 +76,8 @@ impl TryFrom<Page> for Node {
                 }
 
                 for _i in 1..num_children {
-                    let key_raw = page.get_ptr_from_offset(offset, KEY_SIZE);
-                    let key = match str::from_utf8(key_raw) {
-                        Ok(key) => key,
-                        Err(_) => return Err(Error::UTF8Error),
-                    };
+                    keys.push(decode_key(page.get_ptr_from_offset(offset, KEY_SIZE))?);
                     offset += KEY_SIZE;
-                    keys.push(Key(key.trim_matches(char::from(0)).to_string()));
                 }
                 Ok(Node::new(
                     NodeType::Internal(children, keys),
@@ -86,32 +85,46 @@ impl TryFrom<Page> for Node {
                     parent_offset,
                 ))
             }
-            NodeType::Leaf(mut pairs) => {
-                let mut offset = LEAF_NODE_NUM_PAIRS_OFFSET;
-                let num_keys_val_pairs = page.get_value_from_offset(offset)?;
-                offset = LEAF_NODE_HEADER_SIZE;
-                for _i in 0..num_keys_val_pairs {
-                    let key_raw = page.get_ptr_from_offset(offset, KEY_SIZE);
-                    let key = match str::from_utf8(key_raw) {
-                        Ok(key) => key,
-                        Err(_) => return Err(Error::UTF8Error)
-                    };
+            NodeType::Leaf(mut entries) => {
+                let num_entries = page.get_value_from_offset(LEAF_NODE_NUM_PAIRS_OFFSET)?;
+                let mut offset = LEAF_NODE_HEADER_SIZE;
+                for _i in 0..num_entries {
+                    let key = decode_key(page.get_ptr_from_offset(offset, KEY_SIZE))?;
                     offset += KEY_SIZE;
-
-                    let value_raw = page.get_ptr_from_offset(offset, VALUE_SIZE);
-                    let value = match str::from_utf8(value_raw) {
-                        Ok(val) => val,
-                        Err(_) => return Err(Error::UTF8Error)
-                    };
+                    let value = decode_value(page.get_ptr_from_offset(offset, VALUE_SIZE))?;
                     offset += VALUE_SIZE;
-                    pairs.push(KeyValuePair::new(
-                        key.trim_matches(char::from(0)).to_string(),
-                        value.trim_matches(char::from(0)).to_string(),
-                    ))
+                    entries.push(LeafEntry { key, value });
                 }
-                Ok(Node::new(NodeType::Leaf(pairs), is_root, parent_offset))
+                let mut node = Node::new(NodeType::Leaf(entries), is_root, parent_offset);
+                node.prev_leaf = sibling_from_offset(&page, LEAF_NODE_PREV_LEAF_OFFSET)?;
+                node.next_leaf = sibling_from_offset(&page, LEAF_NODE_NEXT_LEAF_OFFSET)?;
+                Ok(node)
             }
             NodeType::Unexpected => Err(Error::UnexpectedError),
         }
     }
-}
\ No newline at end of file
+}
+
+fn sibling_from_offset(page: &Page, offset: usize) -> Result<Option<Offset>, Error> {
+    match page.get_value_from_offset(offset)? {
+        NO_SIBLING => Ok(None),
+        sibling => Ok(Some(Offset(sibling)))
+    }
+}
+
+fn decode_key(raw_key: &[u8]) -> Result<Key, Error> {
+    let len = u16::from_be_bytes([raw_key[0], raw_key[1]]) as usize;
+    if len > MAX_KEY_SIZE {
+        return Err(Error::CorruptedFile("key longer than its slot"));
+    }
+    Ok(Key(raw_key[KEY_LEN_SIZE..KEY_LEN_SIZE + len].to_vec()))
+}
+
+fn decode_value(raw_value: &[u8]) -> Result<StoredValue, Error> {
+    let Value(len) = Value::try_from(&raw_value[..VALUE_LEN_SIZE])?;
+    if len <= INLINE_VALUE_SIZE {
+        return Ok(StoredValue::Inline(raw_value[VALUE_LEN_SIZE..VALUE_LEN_SIZE + len].to_vec()));
+    }
+    let Value(first_page) = Value::try_from(&raw_value[VALUE_LEN_SIZE..VALUE_LEN_SIZE + PTR_SIZE])?;
+    Ok(StoredValue::Overflow { len, first_page: Offset(first_page) })
+}
@@ -15,43 +15,71 @@ impl TryFrom<[u8; PTR_SIZE]> for Offset {
     }
 }
 
-#[derive(Clone, Eq, PartialEq, PartialOrd, Ord, Debug)]
-pub struct Key(pub String);
+/// Comparator orders keys, a tree file must always be opened with the comparator it was built with.
+pub type Comparator = fn(&[u8], &[u8]) -> Ordering;
 
-#[derive(Clone, Eq, Debug)]
-pub struct KeyValuePair {
-    pub key: String,
-    pub value: String
+/// bytewise orders keys lexicographically by their bytes, the default comparator.
+pub fn bytewise(a: &[u8], b: &[u8]) -> Ordering {
+    a.cmp(b)
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct Key(pub Vec<u8>);
+
+impl From<Vec<u8>> for Key {
+    fn from(key: Vec<u8>) -> Self {
+        Key(key)
+    }
 }
 
-impl Ord for KeyValuePair {
-    fn cmp(&self, other: &Self) -> Ordering {
-        self.key.cmp(&other.key)
+impl From<&[u8]> for Key {
+    fn from(key: &[u8]) -> Self {
+        Key(key.to_vec())
     }
 }
 
-impl PartialOrd for KeyValuePair {
-    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
-        Some(self.cmp(other))
+impl From<String> for Key {
+    fn from(key: String) -> Self {
+        Key(key.into_bytes())
     }
 }
 
-impl PartialEq for KeyValuePair {
-    fn eq(&self, other: &Self) -> bool {
-        self.key == other.key && self.value == other.value
+impl From<&str> for Key {
+    fn from(key: &str) -> Self {
+        Key(key.as_bytes().to_vec())
     }
 }
 
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct KeyValuePair {
+    pub key: Vec<u8>,
+    pub value: Vec<u8>
+}
+
 impl KeyValuePair {
-    pub fn new(key: String, value: String) -> KeyValuePair {
-        KeyValuePair { key, value }
+    pub fn new(key: impl Into<Vec<u8>>, value: impl Into<Vec<u8>>) -> KeyValuePair {
+        KeyValuePair { key: key.into(), value: value.into() }
     }
 }
 
+/// StoredValue is a value as kept in a leaf: inline, or spilled onto a chain of overflow pages.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub enum StoredValue {
+    Inline(Vec<u8>),
+    Overflow { len: usize, first_page: Offset }
+}
+
+/// LeafEntry is a key and its stored value.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct LeafEntry {
+    pub key: Key,
+    pub value: StoredValue
+}
+
 #[derive(PartialEq, Eq, Clone, Debug)]
 pub enum NodeType {
     Internal(Vec<Offset>, Vec<Key>),
-    Leaf(Vec<KeyValuePair>),
+    Leaf(Vec<LeafEntry>),
     Unexpected
 }
 
@@ -59,7 +87,7 @@ impl From<u8> for NodeType {
     fn from(value: u8) -> Self {
         match value {
             0x01 => NodeType::Internal(Vec::<Offset>::new(), Vec::<Key>::new()),
-            0x02 => NodeType::Leaf(Vec::<KeyValuePair>::new()),
+            0x02 => NodeType::Leaf(Vec::<LeafEntry>::new()),
             _ => NodeType::Unexpected
         }
     }
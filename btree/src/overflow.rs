@@ -0,0 +1,31 @@
+use crate::{error::Error, node_type::Offset, page::Page, page_layout::{NODE_TYPE_OFFSET, NO_OVERFLOW_PAGE, OVERFLOW_PAGE_CAPACITY, OVERFLOW_PAGE_HEADER_SIZE, OVERFLOW_PAGE_LEN_OFFSET, OVERFLOW_PAGE_NEXT_OFFSET, OVERFLOW_PAGE_TYPE, PAGE_SIZE}};
+
+/// overflow_page encodes one chunk of a value too long to be stored in its leaf,
+/// linking to the page holding the next chunk.
+pub fn overflow_page(next: Option<&Offset>, chunk: &[u8]) -> Result<Page, Error> {
+    if chunk.len() > OVERFLOW_PAGE_CAPACITY {
+        return Err(Error::UnexpectedError);
+    }
+    let mut page = Page::new([0x00; PAGE_SIZE]);
+    page.write_bytes_at_offset(&[OVERFLOW_PAGE_TYPE], NODE_TYPE_OFFSET, 1)?;
+    page.write_value_at_offset(OVERFLOW_PAGE_NEXT_OFFSET, next.map_or(NO_OVERFLOW_PAGE, |offset| offset.0))?;
+    page.write_value_at_offset(OVERFLOW_PAGE_LEN_OFFSET, chunk.len())?;
+    page.write_bytes_at_offset(chunk, OVERFLOW_PAGE_HEADER_SIZE, chunk.len())?;
+    Ok(page)
+}
+
+/// read_overflow_page decodes the chunk stored in an overflow page and the link to the next one.
+pub fn read_overflow_page(page: &Page) -> Result<(Option<Offset>, &[u8]), Error> {
+    if page.get_data()[NODE_TYPE_OFFSET] != OVERFLOW_PAGE_TYPE {
+        return Err(Error::CorruptedFile("overflow chain links to a page that is not an overflow page"));
+    }
+    let len = page.get_value_from_offset(OVERFLOW_PAGE_LEN_OFFSET)?;
+    if len > OVERFLOW_PAGE_CAPACITY {
+        return Err(Error::CorruptedFile("overflow page longer than a page"));
+    }
+    let next = match page.get_value_from_offset(OVERFLOW_PAGE_NEXT_OFFSET)? {
+        NO_OVERFLOW_PAGE => None,
+        offset => Some(Offset(offset))
+    };
+    Ok((next, page.get_ptr_from_offset(OVERFLOW_PAGE_HEADER_SIZE, len)))
+}
@@ -1,4 +1,4 @@
-use crate::{error::Error, node::Node, node_type::{Key, NodeType, Offset}, page_layout::{ToByte, INTERNAL_NODE_HEADER_SIZE, INTERNAL_NODE_NUM_CHILDREN_OFFSET, INTERNAL_NODE_NUM_CHILDREN_SIZE, IS_ROOT_OFFSET, KEY_SIZE, LEAF_NODE_HEADER_SIZE, LEAF_NODE_NUM_PAIRS_OFFSET, LEAF_NODE_NUM_PAIRS_SIZE, NODE_TYPE_OFFSET, PAGE_SIZE, PARENT_POINTER_OFFSET, PARENT_POINTER_SIZE, PTR_SIZE, VALUE_SIZE}};
+use crate::{error::Error, node::Node, node_type::{Key, NodeType, Offset, StoredValue}, page_layout::{ToByte, FREE_PAGE_TYPE, INLINE_VALUE_SIZE, INTERNAL_NODE_HEADER_SIZE, INTERNAL_NODE_MAX_CHILDREN, INTERNAL_NODE_NUM_CHILDREN_OFFSET, INTERNAL_NODE_NUM_CHILDREN_SIZE, IS_ROOT_OFFSET, KEY_LEN_SIZE, KEY_SIZE, LEAF_NODE_HEADER_SIZE, LEAF_NODE_MAX_PAIRS, LEAF_NODE_NEXT_LEAF_OFFSET, LEAF_NODE_NUM_PAIRS_OFFSET, LEAF_NODE_NUM_PAIRS_SIZE, LEAF_NODE_PREV_LEAF_OFFSET, MAX_KEY_SIZE, NODE_TYPE_OFFSET, NO_SIBLING, OVERFLOW_PAGE_CAPACITY, OVERFLOW_PAGE_LEN_OFFSET, OVERFLOW_PAGE_TYPE, PAGE_SIZE, PARENT_POINTER_OFFSET, PARENT_POINTER_SIZE, PTR_SIZE, VALUE_LEN_SIZE, VALUE_SIZE}};
 
 
 /// Value is a wrapper for value in the page.
@@ -6,6 +6,7 @@ pub struct Value(pub usize);
 
 /// Page is a wrapper for a single page of memory
 /// providing some helpful helpers for quick access.
+#[derive(Clone)]
 pub struct Page {
     data: Box<[u8; PAGE_SIZE]>
 }
@@ -24,7 +25,7 @@ impl Page {
         }
 
         let bytes = value.to_be_bytes();
-        self.data[offset..offset+PAGE_SIZE].clone_from_slice(&bytes);
+        self.data[offset..offset+PTR_SIZE].clone_from_slice(&bytes);
         Ok(())
     }
 
@@ -71,6 +72,38 @@ impl Page {
         *self.data
     }
 
+    /// check_header validates the node header so that garbage pages are rejected
+    /// before their contents are decoded.
+    pub fn check_header(&self) -> Result<(), Error> {
+        if self.data[IS_ROOT_OFFSET] > 0x01 {
+            return Err(Error::CorruptedFile("invalid is_root flag in page header"));
+        }
+        match self.data[NODE_TYPE_OFFSET] {
+            FREE_PAGE_TYPE => return Ok(()),
+            OVERFLOW_PAGE_TYPE => {
+                if self.get_value_from_offset(OVERFLOW_PAGE_LEN_OFFSET)? > OVERFLOW_PAGE_CAPACITY {
+                    return Err(Error::CorruptedFile("overflow page longer than a page"));
+                }
+                return Ok(());
+            }
+            _ => {}
+        }
+        match NodeType::from(self.data[NODE_TYPE_OFFSET]) {
+            NodeType::Internal(_, _) => {
+                if self.get_value_from_offset(INTERNAL_NODE_NUM_CHILDREN_OFFSET)? > INTERNAL_NODE_MAX_CHILDREN {
+                    return Err(Error::CorruptedFile("too many children in internal page"));
+                }
+            }
+            NodeType::Leaf(_) => {
+                if self.get_value_from_offset(LEAF_NODE_NUM_PAIRS_OFFSET)? > LEAF_NODE_MAX_PAIRS {
+                    return Err(Error::CorruptedFile("too many key value pairs in leaf page"));
+                }
+            }
+            NodeType::Unexpected => return Err(Error::CorruptedFile("unknown node type in page header")),
+        }
+        Ok(())
+    }
+
 }
 
 
@@ -116,46 +149,22 @@ impl TryFrom<&Node> for Page {
                     page_offset += PTR_SIZE;
                 }
 
-                for Key(key) in keys {
-                    let key_bytes = key.as_bytes();
-                    let mut raw_key: [u8; KEY_SIZE] = [0x00; KEY_SIZE];
-                    if key_bytes.len() > KEY_SIZE {
-                        return Err(Error::KeyOverflowError);
-                    } else {
-                        for (i, byte) in key_bytes.iter().enumerate() {
-                            raw_key[i] = *byte;
-                        }
-                    }
-                    data[page_offset..page_offset + KEY_SIZE].clone_from_slice(&raw_key);
+                for key in keys {
+                    data[page_offset..page_offset + KEY_SIZE].clone_from_slice(&encode_key(key)?);
                     page_offset += KEY_SIZE;
                 }
             }
-            NodeType::Leaf(kv_pairs) => {
-                data[LEAF_NODE_NUM_PAIRS_OFFSET..LEAF_NODE_NUM_PAIRS_OFFSET + LEAF_NODE_NUM_PAIRS_SIZE].clone_from_slice(&kv_pairs.len().to_be_bytes());
+            NodeType::Leaf(entries) => {
+                data[LEAF_NODE_NUM_PAIRS_OFFSET..LEAF_NODE_NUM_PAIRS_OFFSET + LEAF_NODE_NUM_PAIRS_SIZE].clone_from_slice(&entries.len().to_be_bytes());
+                for (field_offset, sibling) in [(LEAF_NODE_PREV_LEAF_OFFSET, &node.prev_leaf), (LEAF_NODE_NEXT_LEAF_OFFSET, &node.next_leaf)] {
+                    let sibling_offset = sibling.as_ref().map_or(NO_SIBLING, |Offset(offset)| *offset);
+                    data[field_offset..field_offset + PTR_SIZE].clone_from_slice(&sibling_offset.to_be_bytes());
+                }
                 let mut page_offset = LEAF_NODE_HEADER_SIZE;
-                for pair in kv_pairs {
-                    let key_bytes = pair.key.as_bytes();
-                    let mut raw_key: [u8; KEY_SIZE] = [0x00; KEY_SIZE];
-                    if key_bytes.len() > KEY_SIZE {
-                        return Err(Error::KeyOverflowError);
-                    } else {
-                        for (i, bytes) in key_bytes.iter().enumerate() {
-                            raw_key[i] = *bytes;
-                        }
-                    }
-                    data[page_offset..page_offset+KEY_SIZE].clone_from_slice(&raw_key);
+                for entry in entries {
+                    data[page_offset..page_offset + KEY_SIZE].clone_from_slice(&encode_key(&entry.key)?);
                     page_offset += KEY_SIZE;
-
-                    let value_bytes = pair.value.as_bytes();
-                    let mut raw_value: [u8; VALUE_SIZE] = [0x00; VALUE_SIZE];
-                    if value_bytes.len() > VALUE_SIZE {
-                        return Err(Error::ValueOverflowError);
-                    } else {
-                        for (i, byte) in value_bytes.iter().enumerate() {
-                            raw_value[i] = *byte;
-                        }
-                    }
-                    data[page_offset..page_offset + VALUE_SIZE].clone_from_slice(&raw_value);
+                    data[page_offset..page_offset + VALUE_SIZE].clone_from_slice(&encode_value(&entry.value)?);
                     page_offset += VALUE_SIZE;
                 }
             }
@@ -164,4 +173,38 @@ impl TryFrom<&Node> for Page {
 
         Ok(Page::new(data))
     }
-}
\ No newline at end of file
+}
+
+/// encode_key lays a key out in its slot, prefixed by its length.
+fn encode_key(Key(key): &Key) -> Result<[u8; KEY_SIZE], Error> {
+    if key.len() > MAX_KEY_SIZE {
+        return Err(Error::KeyOverflowError);
+    }
+    let mut raw_key = [0x00; KEY_SIZE];
+    raw_key[..KEY_LEN_SIZE].clone_from_slice(&(key.len() as u16).to_be_bytes());
+    raw_key[KEY_LEN_SIZE..KEY_LEN_SIZE + key.len()].clone_from_slice(key);
+    Ok(raw_key)
+}
+
+/// encode_value lays a value out in its slot, prefixed by its length.
+/// Values longer than INLINE_VALUE_SIZE are replaced by the offset of their first overflow page.
+fn encode_value(value: &StoredValue) -> Result<[u8; VALUE_SIZE], Error> {
+    let mut raw_value = [0x00; VALUE_SIZE];
+    match value {
+        StoredValue::Inline(value) => {
+            if value.len() > INLINE_VALUE_SIZE {
+                return Err(Error::ValueOverflowError);
+            }
+            raw_value[..VALUE_LEN_SIZE].clone_from_slice(&value.len().to_be_bytes());
+            raw_value[VALUE_LEN_SIZE..VALUE_LEN_SIZE + value.len()].clone_from_slice(value);
+        }
+        StoredValue::Overflow { len, first_page: Offset(first_page) } => {
+            if *len <= INLINE_VALUE_SIZE {
+                return Err(Error::UnexpectedError);
+            }
+            raw_value[..VALUE_LEN_SIZE].clone_from_slice(&len.to_be_bytes());
+            raw_value[VALUE_LEN_SIZE..VALUE_LEN_SIZE + PTR_SIZE].clone_from_slice(&first_page.to_be_bytes());
+        }
+    }
+    Ok(raw_value)
+}
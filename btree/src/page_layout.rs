@@ -14,13 +14,20 @@ pub const PARENT_POINTER_OFFSET: usize = 2;
 pub const PARENT_POINTER_SIZE: usize = PTR_SIZE;
 pub const COMMON_NODE_HEADER_SIZE: usize = NODE_TYPE_SIZE + IS_ROOT_SIZE + PARENT_POINTER_SIZE;
 
-/// Leaf node header layout (18 bytes in total)
-/// 
-/// Space for keys and values: PAGE_SIZE - LEAF_NODE_HEADER_SIZE = 4096 - 18 = 4078 bytes.
-/// Which leaves 4076 / key_limit = 20 (ten for key and 10 for value).
+/// Leaf node header layout (34 bytes in total)
+///
+/// Leaves are chained in key order through their previous and next sibling offsets,
+/// NO_SIBLING marks either end of the chain.
+///
+/// Space for keys and values: PAGE_SIZE - LEAF_NODE_HEADER_SIZE = 4096 - 34 = 4062 bytes.
+/// Which leaves room for 4062 / (KEY_SIZE + VALUE_SIZE) = 42 pairs.
 pub const LEAF_NODE_NUM_PAIRS_OFFSET: usize = COMMON_NODE_HEADER_SIZE;
 pub const LEAF_NODE_NUM_PAIRS_SIZE: usize = PTR_SIZE;
-pub const LEAF_NODE_HEADER_SIZE: usize = COMMON_NODE_HEADER_SIZE + LEAF_NODE_NUM_PAIRS_SIZE;
+pub const LEAF_NODE_PREV_LEAF_OFFSET: usize = LEAF_NODE_NUM_PAIRS_OFFSET + LEAF_NODE_NUM_PAIRS_SIZE;
+pub const LEAF_NODE_NEXT_LEAF_OFFSET: usize = LEAF_NODE_PREV_LEAF_OFFSET + PTR_SIZE;
+pub const LEAF_NODE_HEADER_SIZE: usize = LEAF_NODE_NEXT_LEAF_OFFSET + PTR_SIZE;
+pub const NO_SIBLING: usize = usize::MAX;
+pub const LEAF_NODE_MAX_PAIRS: usize = (PAGE_SIZE - LEAF_NODE_HEADER_SIZE) / (KEY_SIZE + VALUE_SIZE);
 
 /// Internal header layout (18 bytes in total).
 /// 
@@ -28,14 +35,49 @@ pub const LEAF_NODE_HEADER_SIZE: usize = COMMON_NODE_HEADER_SIZE + LEAF_NODE_NUM
 pub const INTERNAL_NODE_NUM_CHILDREN_OFFSET: usize = COMMON_NODE_HEADER_SIZE;
 pub const INTERNAL_NODE_NUM_CHILDREN_SIZE: usize = PTR_SIZE;
 pub const INTERNAL_NODE_HEADER_SIZE: usize = COMMON_NODE_HEADER_SIZE + INTERNAL_NODE_NUM_CHILDREN_SIZE;
+pub const INTERNAL_NODE_MAX_CHILDREN: usize = (PAGE_SIZE - INTERNAL_NODE_HEADER_SIZE + KEY_SIZE) / (PTR_SIZE + KEY_SIZE);
+
+/// Page types stored at NODE_TYPE_OFFSET besides the node types.
+pub const FREE_PAGE_TYPE: u8 = 0x04;
+pub const HEADER_PAGE_TYPE: u8 = 0x05;
+pub const OVERFLOW_PAGE_TYPE: u8 = 0x06;
+
+/// Header page layout, the first page of the file.
+/// The free pages form a list through their next pointers, starting at the head stored here.
+pub const HEADER_PAGE_OFFSET: usize = 0;
+pub const HEADER_MAGIC: &[u8; 8] = b"btree\x00\x00\x03";
+pub const HEADER_MAGIC_OFFSET: usize = NODE_TYPE_OFFSET + NODE_TYPE_SIZE;
+pub const FREE_LIST_HEAD_OFFSET: usize = HEADER_MAGIC_OFFSET + HEADER_MAGIC.len();
+pub const BRANCHING_FACTOR_OFFSET: usize = FREE_LIST_HEAD_OFFSET + PTR_SIZE;
+
+/// Free page layout, the offset of the next free page follows the page type.
+pub const FREE_PAGE_NEXT_OFFSET: usize = NODE_TYPE_OFFSET + NODE_TYPE_SIZE;
+pub const NO_FREE_PAGE: usize = usize::MAX;
+
+/// Overflow page layout: the offset of the next page in the chain and the number of value
+/// bytes stored in this one, followed by the bytes.
+pub const OVERFLOW_PAGE_NEXT_OFFSET: usize = NODE_TYPE_OFFSET + NODE_TYPE_SIZE;
+pub const OVERFLOW_PAGE_LEN_OFFSET: usize = OVERFLOW_PAGE_NEXT_OFFSET + PTR_SIZE;
+pub const OVERFLOW_PAGE_HEADER_SIZE: usize = OVERFLOW_PAGE_LEN_OFFSET + PTR_SIZE;
+pub const OVERFLOW_PAGE_CAPACITY: usize = PAGE_SIZE - OVERFLOW_PAGE_HEADER_SIZE;
+pub const NO_OVERFLOW_PAGE: usize = usize::MAX;
 
-/// Key, Value sizes.
-pub const KEY_SIZE: usize = 10;
-pub const VALUE_SIZE: usize = 10;
+/// Key, Value slot sizes.
+///
+/// A key slot holds the key length followed by up to MAX_KEY_SIZE key bytes.
+/// A value slot holds the value length followed by the value itself when it is no longer than
+/// INLINE_VALUE_SIZE, or by the offset of its first overflow page otherwise.
+pub const KEY_LEN_SIZE: usize = 2;
+pub const MAX_KEY_SIZE: usize = 62;
+pub const KEY_SIZE: usize = KEY_LEN_SIZE + MAX_KEY_SIZE;
+pub const VALUE_LEN_SIZE: usize = PTR_SIZE;
+pub const INLINE_VALUE_SIZE: usize = 24;
+pub const VALUE_SIZE: usize = VALUE_LEN_SIZE + INLINE_VALUE_SIZE;
 
 /// Wrappers for converting byte to bool and back.
 /// The convention used throughout the index file is: one is true; otherwise - false.
 pub trait FromByte {
+    #[allow(clippy::wrong_self_convention)]
     fn from_byte(&self) -> bool;
 }
 
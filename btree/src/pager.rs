@@ -3,36 +3,82 @@ use std::{fs::{File, OpenOptions}, io::{Read, Seek, Write}, path::Path};
 use crate::{error::Error, node_type::Offset, page::Page, page_layout::PAGE_SIZE};
 
 
+/// Pages written by an operation, keyed by the offset they belong at.
+pub type DirtyPages = Vec<(Offset, Page)>;
 
+/// Pager reads and writes pages of the tree file.
 pub struct Pager {
     file: File,
-    curser: usize
+    /// End of the last page written to the file.
+    len: usize
 }
 
 impl Pager {
-    pub fn new(path: &Path) -> Result<Pager, Error> {
-        let fd = OpenOptions::new().create(true).read(true).write(true).truncate(true).open(path)?;
-        Ok(Pager { file: fd, curser: 0 })
+    /// Opens the file at `path`, creating it if needed.
+    pub fn new(path: &Path, truncate: bool) -> Result<Pager, Error> {
+        let fd = OpenOptions::new().create(true).read(true).write(true).truncate(truncate).open(path)?;
+        let file_len = fd.metadata()?.len() as usize;
+        Ok(Pager { file: fd, len: file_len })
+    }
+
+    /// check_length rejects a file that ends in a partially written page.
+    pub fn check_length(&self) -> Result<(), Error> {
+        if !self.len.is_multiple_of(PAGE_SIZE) {
+            return Err(Error::CorruptedFile("file length is not a multiple of the page size"));
+        }
+        Ok(())
+    }
+
+    /// is_empty reports whether no page has been written to the file yet.
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// page_offsets lists the offsets of every page in the file.
+    pub fn page_offsets(&self) -> impl Iterator<Item = Offset> + use<> {
+        (0..self.len).step_by(PAGE_SIZE).map(Offset)
     }
 
     pub fn get_page(&mut self, offset: &Offset) -> Result<Page, Error> {
+        if !offset.0.is_multiple_of(PAGE_SIZE) || offset.0 >= self.len {
+            return Err(Error::CorruptedFile("page offset is out of bounds"));
+        }
         let mut page: [u8; PAGE_SIZE] = [0x00; PAGE_SIZE];
         self.file.seek(std::io::SeekFrom::Start(offset.0 as u64))?;
         self.file.read_exact(&mut page)?;
         Ok(Page::new(page))
     }
 
-    pub fn write_page(&mut self, page: Page) -> Result<Offset, Error> {
-        self.file.seek(std::io::SeekFrom::Start(self.curser as u64))?;
-        self.file.write_all(&page.get_data())?;
-        let res = Offset(self.curser);
-        self.curser += PAGE_SIZE;
-        Ok(res)
+    /// apply writes logged pages to the file.
+    pub fn apply(&mut self, pages: DirtyPages) -> Result<(), Error> {
+        for (offset, page) in pages {
+            self.write_to_file(&offset, &page)?;
+        }
+        Ok(())
     }
 
-    pub fn write_page_at_offset(&mut self, page: Page, offset: &Offset) -> Result<(), Error> {
+    /// write_to_file writes a logged page straight to the file.
+    pub fn write_to_file(&mut self, offset: &Offset, page: &Page) -> Result<(), Error> {
         self.file.seek(std::io::SeekFrom::Start(offset.0 as u64))?;
         self.file.write_all(&page.get_data())?;
+        self.len = self.len.max(offset.0 + PAGE_SIZE);
         Ok(())
     }
-}
\ No newline at end of file
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// truncate cuts the file down to len bytes, dropping every page stored beyond it.
+    pub fn truncate(&mut self, len: usize) -> Result<(), Error> {
+        self.file.set_len(len as u64)?;
+        self.len = len;
+        self.sync()
+    }
+
+    /// sync flushes the file to disk.
+    pub fn sync(&mut self) -> Result<(), Error> {
+        self.file.sync_data()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,87 @@
+use crate::{error::Error, node::Node, node_type::{Comparator, Key, KeyValuePair, LeafEntry, NodeType, Offset, StoredValue}, overflow::read_overflow_page, page::Page};
+
+/// TreeReader is the read path shared by the writer, which sees the pages it has not committed
+/// yet, and by snapshots, which only see the last commit.
+pub(crate) trait TreeReader {
+    fn get_page(&mut self, offset: &Offset) -> Result<Page, Error>;
+
+    fn root_offset(&mut self) -> Result<Offset, Error>;
+
+    fn comparator(&self) -> Comparator;
+
+    /// load_node reads and decodes the node stored at offset.
+    fn load_node(&mut self, offset: &Offset) -> Result<Node, Error> {
+        Node::try_from(self.get_page(offset)?)
+    }
+
+    /// child_index is the index of the child of an internal node whose subtree holds key.
+    fn child_index(&self, keys: &[Key], key: &[u8]) -> usize {
+        let cmp = self.comparator();
+        keys.binary_search_by(|probe| cmp(&probe.0, key)).unwrap_or_else(|idx| idx)
+    }
+
+    /// entry_index finds key among the entries of a leaf, or the index it would be inserted at.
+    fn entry_index(&self, entries: &[LeafEntry], key: &[u8]) -> Result<usize, usize> {
+        let cmp = self.comparator();
+        entries.binary_search_by(|probe| cmp(&probe.key.0, key))
+    }
+
+    /// find_entry descends from the root to the leaf entry holding key.
+    fn find_entry(&mut self, key: &[u8]) -> Result<LeafEntry, Error> {
+        let mut offset = self.root_offset()?;
+        loop {
+            match self.load_node(&offset)?.node_type {
+                NodeType::Internal(children, keys) => {
+                    offset = children.get(self.child_index(&keys, key)).ok_or(Error::UnexpectedError)?.clone();
+                }
+                NodeType::Leaf(mut entries) => {
+                    return match self.entry_index(&entries, key) {
+                        Ok(idx) => Ok(entries.swap_remove(idx)),
+                        Err(_) => Err(Error::KeyNotFound)
+                    };
+                }
+                NodeType::Unexpected => return Err(Error::UnexpectedError)
+            }
+        }
+    }
+
+    /// load_pair reads the value of a leaf entry back, following its overflow chain if it has one.
+    fn load_pair(&mut self, entry: LeafEntry) -> Result<KeyValuePair, Error> {
+        let value = match entry.value {
+            StoredValue::Inline(value) => value,
+            StoredValue::Overflow { .. } => {
+                let pages = self.overflow_chain(&entry.value)?;
+                let mut value = Vec::new();
+                for (_, page) in &pages {
+                    value.extend_from_slice(read_overflow_page(page)?.1);
+                }
+                value
+            }
+        };
+        Ok(KeyValuePair::new(entry.key.0, value))
+    }
+
+    /// overflow_chain loads the overflow pages of a value in order, none for an inline value.
+    fn overflow_chain(&mut self, value: &StoredValue) -> Result<Vec<(Offset, Page)>, Error> {
+        let StoredValue::Overflow { len, first_page } = value else {
+            return Ok(vec![]);
+        };
+        let mut pages = Vec::new();
+        let mut stored = 0;
+        let mut next = Some(first_page.clone());
+        while let Some(offset) = next {
+            let page = self.get_page(&offset)?;
+            let (next_page, chunk) = read_overflow_page(&page)?;
+            stored += chunk.len();
+            if chunk.is_empty() || stored > *len {
+                return Err(Error::CorruptedFile("overflow chain longer than its value"));
+            }
+            next = next_page;
+            pages.push((offset, page));
+        }
+        if stored != *len {
+            return Err(Error::CorruptedFile("overflow chain shorter than its value"));
+        }
+        Ok(pages)
+    }
+}
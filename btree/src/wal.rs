@@ -1,34 +1,212 @@
-use std::{fs::{File, OpenOptions}, io::{Read, Seek, Write}, path::PathBuf};
-
-use crate::{error::Error, node_type::Offset, page_layout::PTR_SIZE};
+use std::{ffi::OsString, fs::{self, File, OpenOptions}, io::{Read, Seek, Write}, path::{Path, PathBuf}};
 
+use crate::{error::Error, node_type::Offset, page::Page, page_layout::{PAGE_SIZE, PTR_SIZE}, pager::DirtyPages};
 
+/// Record layout: kind (1 byte), offset (PTR_SIZE), payload, CRC-32 of everything before it (4 bytes).
+/// A page record carries the page image written at offset, a commit record the root offset
+/// and no payload.
+const RECORD_KIND_PAGE: u8 = 0x01;
+const RECORD_KIND_COMMIT: u8 = 0x02;
+const RECORD_HEADER_SIZE: usize = 1 + PTR_SIZE;
+const CHECKSUM_SIZE: usize = 4;
+const PAGE_RECORD_SIZE: usize = RECORD_HEADER_SIZE + PAGE_SIZE + CHECKSUM_SIZE;
+const COMMIT_RECORD_SIZE: usize = RECORD_HEADER_SIZE + CHECKSUM_SIZE;
 
+/// Wal is a redo log: every page written by an operation is appended together with a commit
+/// record naming the new root, and the log is synced before the pages reach the tree file.
 pub struct Wal {
-    file: File
+    file: File,
+    path: PathBuf,
+    root: Option<Offset>,
+    len: usize
 }
 
 impl Wal {
-    pub fn new(parent_directory: PathBuf) -> Result<Self, Error> {
-        let fd = OpenOptions::new().create(true).read(true).write(true).truncate(true).open(parent_directory.join("wal"))?;
-        Ok(Self { file: fd })
+    /// new opens the log of the tree file at tree_path, which lives next to it as `<tree_path>.wal`
+    /// so every tree has a log of its own.
+    pub fn new(tree_path: &Path, truncate: bool) -> Result<Self, Error> {
+        let path = wal_path(tree_path);
+        let fd = OpenOptions::new().create(true).read(true).write(true).truncate(truncate).open(&path)?;
+        Ok(Self { file: fd, path, root: None, len: 0 })
+    }
+
+    /// recover scans the log and returns the pages of every committed operation together with
+    /// the last committed root. Records after the last valid commit (a torn or uncommitted
+    /// tail) are cut off.
+    pub fn recover(&mut self) -> Result<(DirtyPages, Option<Offset>), Error> {
+        let mut log = Vec::new();
+        self.file.seek(std::io::SeekFrom::Start(0))?;
+        self.file.read_to_end(&mut log)?;
+
+        let mut committed = Vec::new();
+        let mut pending = Vec::new();
+        let mut valid_len = 0;
+        let mut pos = 0;
+        while let Some((kind, offset, payload, record_len)) = read_record(&log[pos..]) {
+            match kind {
+                RECORD_KIND_PAGE => {
+                    let mut data = [0x00; PAGE_SIZE];
+                    data.copy_from_slice(payload);
+                    pending.push((offset, Page::new(data)));
+                }
+                _ => {
+                    committed.append(&mut pending);
+                    self.root = Some(offset);
+                    valid_len = pos + record_len;
+                }
+            }
+            pos += record_len;
+        }
+
+        self.file.set_len(valid_len as u64)?;
+        self.len = valid_len;
+        Ok((committed, self.root.clone()))
     }
 
     pub fn get_root(&mut self) -> Result<Offset, Error> {
-        let mut buf: [u8; PTR_SIZE] = [0x00; PTR_SIZE];
-        let file_len = self.file.seek(std::io::SeekFrom::End(0))? as usize;
-        let mut root_offset: usize = 0;
-        if file_len > 0 {
-            root_offset = (file_len / PTR_SIZE - 1) * PTR_SIZE;
+        self.root.clone().ok_or(Error::UnexpectedError)
+    }
+
+    /// commit logs the given pages followed by a commit record for root and syncs the log.
+    pub fn commit(&mut self, pages: &[(Offset, Page)], root: Offset) -> Result<(), Error> {
+        let mut records = Vec::with_capacity(pages.len() * PAGE_RECORD_SIZE + COMMIT_RECORD_SIZE);
+        for (offset, page) in pages {
+            encode_record(&mut records, RECORD_KIND_PAGE, offset, &page.get_data());
         }
-        self.file.seek(std::io::SeekFrom::Start(root_offset as u64))?;
-        self.file.read_exact(&mut buf)?;
-        Offset::try_from(buf)
+        encode_record(&mut records, RECORD_KIND_COMMIT, &root, &[]);
+
+        self.file.seek(std::io::SeekFrom::Start(self.len as u64))?;
+        self.file.write_all(&records)?;
+        self.file.sync_data()?;
+        self.len += records.len();
+        self.root = Some(root);
+        Ok(())
     }
 
-    pub fn set_root(&mut self, offset: Offset) -> Result<(), Error> {
-        self.file.seek(std::io::SeekFrom::End(0))?;
-        self.file.write_all(&offset.0.to_be_bytes())?;
+    /// checkpoint empties the log once the tree file holds all committed pages,
+    /// keeping only the current root. The new log is synced aside and renamed over the old one,
+    /// so a crash leaves one of the two in place and never a log without a root.
+    pub fn checkpoint(&mut self) -> Result<(), Error> {
+        let root = self.get_root()?;
+        let mut records = Vec::with_capacity(COMMIT_RECORD_SIZE);
+        encode_record(&mut records, RECORD_KIND_COMMIT, &root, &[]);
+
+        let checkpoint_path = checkpoint_path(&self.path);
+        let mut file = OpenOptions::new().create(true).read(true).write(true).truncate(true).open(&checkpoint_path)?;
+        file.write_all(&records)?;
+        file.sync_data()?;
+        fs::rename(&checkpoint_path, &self.path)?;
+        sync_parent_directory(&self.path)?;
+        self.file = file;
+        self.len = records.len();
         Ok(())
     }
-}
\ No newline at end of file
+
+    /// len is the size of the log in bytes.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+}
+
+/// wal_path is where the log of the tree file at tree_path is kept.
+pub fn wal_path(tree_path: &Path) -> PathBuf {
+    let mut path = OsString::from(tree_path);
+    path.push(".wal");
+    PathBuf::from(path)
+}
+
+/// checkpoint_path is where checkpoint writes the log replacing the one at wal_path.
+pub fn checkpoint_path(wal_path: &Path) -> PathBuf {
+    let mut path = OsString::from(wal_path);
+    path.push(".tmp");
+    PathBuf::from(path)
+}
+
+/// sync_parent_directory makes a rename of the file at path durable, commits appended to the
+/// renamed log would otherwise be lost along with it.
+#[cfg(unix)]
+fn sync_parent_directory(path: &Path) -> Result<(), Error> {
+    let parent = path.parent().filter(|parent| !parent.as_os_str().is_empty()).unwrap_or(Path::new("."));
+    File::open(parent)?.sync_all()?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+fn sync_parent_directory(_path: &Path) -> Result<(), Error> {
+    Ok(())
+}
+
+fn encode_record(buf: &mut Vec<u8>, kind: u8, offset: &Offset, payload: &[u8]) {
+    let start = buf.len();
+    buf.push(kind);
+    buf.extend_from_slice(&offset.0.to_be_bytes());
+    buf.extend_from_slice(payload);
+    let crc = checksum(&buf[start..]);
+    buf.extend_from_slice(&crc.to_be_bytes());
+}
+
+/// read_record decodes the record at the start of bytes, returning None if it is torn or corrupted.
+fn read_record(bytes: &[u8]) -> Option<(u8, Offset, &[u8], usize)> {
+    let kind = *bytes.first()?;
+    let record_len = match kind {
+        RECORD_KIND_PAGE => PAGE_RECORD_SIZE,
+        RECORD_KIND_COMMIT => COMMIT_RECORD_SIZE,
+        _ => return None,
+    };
+    let record = bytes.get(..record_len)?;
+    let (body, crc) = record.split_at(record_len - CHECKSUM_SIZE);
+    if checksum(body).to_be_bytes() != crc {
+        return None;
+    }
+    let offset = usize::from_be_bytes(body[1..RECORD_HEADER_SIZE].try_into().ok()?);
+    Some((kind, Offset(offset), &body[RECORD_HEADER_SIZE..], record_len))
+}
+
+/// checksum computes the CRC-32 (IEEE) of bytes.
+fn checksum(bytes: &[u8]) -> u32 {
+    let mut crc = !0u32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = (crc >> 1) ^ (0xEDB8_8320 & (crc & 1).wrapping_neg());
+        }
+    }
+    !crc
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_checksum() {
+        assert_eq!(checksum(b"123456789"), 0xCBF4_3926);
+        assert_eq!(checksum(b""), 0);
+    }
+
+    #[test]
+    fn test_recover_drops_uncommitted_and_torn_records() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut wal = Wal::new(&dir.path().join("db"), false).unwrap();
+        wal.commit(&[(Offset(0), Page::new([0x01; PAGE_SIZE]))], Offset(0)).unwrap();
+        wal.commit(&[(Offset(PAGE_SIZE), Page::new([0x02; PAGE_SIZE]))], Offset(PAGE_SIZE)).unwrap();
+        let committed_len = wal.len();
+
+        // A page record without its commit record, then a commit record whose checksum is off.
+        let mut tail = Vec::new();
+        encode_record(&mut tail, RECORD_KIND_PAGE, &Offset(2 * PAGE_SIZE), &[0x03; PAGE_SIZE]);
+        encode_record(&mut tail, RECORD_KIND_COMMIT, &Offset(2 * PAGE_SIZE), &[]);
+        let last = tail.len() - 1;
+        tail[last] ^= 0xff;
+        wal.file.write_all(&tail).unwrap();
+        drop(wal);
+
+        let mut wal = Wal::new(&dir.path().join("db"), false).unwrap();
+        let (pages, root) = wal.recover().unwrap();
+        assert_eq!(root, Some(Offset(PAGE_SIZE)));
+        assert_eq!(pages.len(), 2);
+        assert_eq!(pages[1].1.get_data(), [0x02; PAGE_SIZE]);
+        assert_eq!(wal.len(), committed_len);
+        assert_eq!(wal.file.metadata().unwrap().len() as usize, committed_len);
+    }
+}
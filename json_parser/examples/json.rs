@@ -1,5 +1,3 @@
-// examples/json.rs
-
 use std::fs::File;
 use sv2_serde_json::parser::JsonParser;
 
@@ -0,0 +1,68 @@
+use std::fmt;
+
+/// Position is a place in the input, lines and columns start at 1 and columns count characters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Position {
+    pub offset: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Position { offset: 0, line: 1, column: 1 }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ErrorKind {
+    Io(std::io::ErrorKind),
+    UnexpectedByte { found: u8, expected: &'static str },
+    UnexpectedEof { expected: &'static str },
+    InvalidLiteral,
+    InvalidNumber,
+    NumberOutOfRange,
+    InvalidEscape,
+    LoneSurrogate,
+    ControlCharacter,
+    InvalidUtf8,
+    TrailingCharacters,
+    DepthLimitExceeded,
+}
+
+/// JsonError is what went wrong while reading JSON and where.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonError {
+    pub kind: ErrorKind,
+    pub position: Position,
+}
+
+impl fmt::Display for ErrorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ErrorKind::Io(kind) => write!(f, "failed to read input: {kind}"),
+            ErrorKind::UnexpectedByte { found, expected } => {
+                write!(f, "expected {expected}, found '{}'", found.escape_ascii())
+            }
+            ErrorKind::UnexpectedEof { expected } => write!(f, "expected {expected}, found end of input"),
+            ErrorKind::InvalidLiteral => write!(f, "invalid literal, expected true, false or null"),
+            ErrorKind::InvalidNumber => write!(f, "invalid number"),
+            ErrorKind::NumberOutOfRange => write!(f, "number out of range"),
+            ErrorKind::InvalidEscape => write!(f, "invalid escape sequence"),
+            ErrorKind::LoneSurrogate => write!(f, "unpaired UTF-16 surrogate in escape sequence"),
+            ErrorKind::ControlCharacter => write!(f, "unescaped control character in string"),
+            ErrorKind::InvalidUtf8 => write!(f, "invalid UTF-8"),
+            ErrorKind::TrailingCharacters => write!(f, "trailing characters after the JSON value"),
+            ErrorKind::DepthLimitExceeded => write!(f, "arrays and objects nested too deeply"),
+        }
+    }
+}
+
+impl fmt::Display for JsonError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let Position { offset, line, column } = self.position;
+        write!(f, "{} at line {line}, column {column} (byte {offset})", self.kind)
+    }
+}
+
+impl std::error::Error for JsonError {}
@@ -0,0 +1,500 @@
+use std::io::Read;
+
+use crate::{
+    error::{ErrorKind, JsonError, Position},
+    reader::JsonReader,
+    value::{Number, Value},
+};
+
+/// Arrays and objects nested deeper than this are rejected unless configured otherwise.
+pub const DEFAULT_MAX_DEPTH: usize = 512;
+
+/// Event is a single step through a JSON document. Values are only ever strings, numbers,
+/// booleans or null, arrays and objects are reported by their start and end.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Event {
+    StartObject,
+    EndObject,
+    StartArray,
+    EndArray,
+    Key(String),
+    Value(Value),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Container {
+    Object,
+    Array,
+}
+
+/// State is what the parser expects to read next.
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum State {
+    Value,
+    FirstValueOrEnd,
+    FirstKeyOrEnd,
+    Key,
+    Colon,
+    CommaOrEnd,
+    EndOfInput,
+    Finished,
+    Failed,
+}
+
+/// PullParser reads JSON incrementally from any reader, producing events on demand.
+///
+/// It never panics on malformed input: the first error is returned with its position and
+/// every call after it returns None.
+pub struct PullParser<T>
+where
+    T: Read,
+{
+    reader: JsonReader<T>,
+    stack: Vec<Container>,
+    state: State,
+    max_depth: usize,
+}
+
+impl<T> PullParser<T>
+where
+    T: Read,
+{
+    pub fn new(reader: T) -> Self {
+        PullParser { reader: JsonReader::new(reader), stack: Vec::new(), state: State::Value, max_depth: DEFAULT_MAX_DEPTH }
+    }
+
+    #[must_use]
+    pub fn from_bytes(bytes: &[u8]) -> PullParser<&[u8]> {
+        PullParser::new(bytes)
+    }
+
+    /// max_depth limits how deeply arrays and objects may nest.
+    #[must_use]
+    pub fn max_depth(mut self, max_depth: usize) -> Self {
+        self.max_depth = max_depth;
+        self
+    }
+
+    /// position is where the next event will be read from.
+    pub fn position(&self) -> Position {
+        self.reader.position()
+    }
+
+    /// next_event returns the next event, or None once the whole document has been read.
+    /// Anything but whitespace after the document is an error.
+    pub fn next_event(&mut self) -> Result<Option<Event>, JsonError> {
+        if matches!(self.state, State::Finished | State::Failed) {
+            return Ok(None);
+        }
+        let result = self.advance();
+        if result.is_err() {
+            self.state = State::Failed;
+        }
+        result
+    }
+
+    fn advance(&mut self) -> Result<Option<Event>, JsonError> {
+        loop {
+            let byte = self.reader.skip_whitespace()?;
+            match (self.state, byte) {
+                (State::EndOfInput, None) => {
+                    self.state = State::Finished;
+                    return Ok(None);
+                }
+                (State::EndOfInput, Some(_)) => return Err(self.reader.error(ErrorKind::TrailingCharacters)),
+                (State::FirstValueOrEnd, Some(b']')) | (State::FirstKeyOrEnd, Some(b'}')) => return self.end_container(),
+                (State::Value | State::FirstValueOrEnd, Some(byte)) => return self.parse_value(byte).map(Some),
+                (State::Key | State::FirstKeyOrEnd, Some(b'"')) => {
+                    let key = self.parse_string()?;
+                    self.state = State::Colon;
+                    return Ok(Some(Event::Key(key)));
+                }
+                (State::Colon, Some(b':')) => {
+                    self.reader.next_byte()?;
+                    self.state = State::Value;
+                }
+                (State::CommaOrEnd, Some(byte)) => match (byte, self.stack.last()) {
+                    (b',', Some(Container::Object)) => {
+                        self.reader.next_byte()?;
+                        self.state = State::Key;
+                    }
+                    (b',', Some(Container::Array)) => {
+                        self.reader.next_byte()?;
+                        self.state = State::Value;
+                    }
+                    (b'}', Some(Container::Object)) | (b']', Some(Container::Array)) => return self.end_container(),
+                    _ => return Err(self.unexpected(Some(byte))),
+                },
+                (State::Finished | State::Failed, _) => return Ok(None),
+                (_, byte) => return Err(self.unexpected(byte)),
+            }
+        }
+    }
+
+    /// unexpected reports byte as not being what the current state expects.
+    fn unexpected(&self, byte: Option<u8>) -> JsonError {
+        let expected = match (self.state, self.stack.last()) {
+            (State::Value, _) => "a value",
+            (State::FirstValueOrEnd, _) => "a value or ']'",
+            (State::FirstKeyOrEnd, _) => "a string key or '}'",
+            (State::Key, _) => "a string key",
+            (State::Colon, _) => "':'",
+            (State::CommaOrEnd, Some(Container::Object)) => "',' or '}'",
+            (State::CommaOrEnd, _) => "',' or ']'",
+            (State::EndOfInput | State::Finished | State::Failed, _) => "end of input",
+        };
+        match byte {
+            Some(found) => self.reader.error(ErrorKind::UnexpectedByte { found, expected }),
+            None => self.reader.error(ErrorKind::UnexpectedEof { expected }),
+        }
+    }
+
+    fn parse_value(&mut self, byte: u8) -> Result<Event, JsonError> {
+        let event = match byte {
+            b'{' | b'[' => {
+                if self.stack.len() >= self.max_depth {
+                    return Err(self.reader.error(ErrorKind::DepthLimitExceeded));
+                }
+                self.reader.next_byte()?;
+                return Ok(match byte {
+                    b'{' => {
+                        self.stack.push(Container::Object);
+                        self.state = State::FirstKeyOrEnd;
+                        Event::StartObject
+                    }
+                    _ => {
+                        self.stack.push(Container::Array);
+                        self.state = State::FirstValueOrEnd;
+                        Event::StartArray
+                    }
+                });
+            }
+            b'"' => Event::Value(Value::String(self.parse_string()?)),
+            b'-' | b'0'..=b'9' => Event::Value(Value::Number(self.parse_number()?)),
+            b't' => Event::Value(self.parse_literal(b"true", Value::Boolean(true))?),
+            b'f' => Event::Value(self.parse_literal(b"false", Value::Boolean(false))?),
+            b'n' => Event::Value(self.parse_literal(b"null", Value::Null)?),
+            _ => return Err(self.unexpected(Some(byte))),
+        };
+        self.value_done();
+        Ok(event)
+    }
+
+    fn end_container(&mut self) -> Result<Option<Event>, JsonError> {
+        self.reader.next_byte()?;
+        let event = match self.stack.pop() {
+            Some(Container::Object) => Event::EndObject,
+            _ => Event::EndArray,
+        };
+        self.value_done();
+        Ok(Some(event))
+    }
+
+    /// value_done moves on past a complete value, to its container or the end of the input.
+    fn value_done(&mut self) {
+        self.state = if self.stack.is_empty() { State::EndOfInput } else { State::CommaOrEnd };
+    }
+
+    fn parse_literal(&mut self, literal: &[u8], value: Value) -> Result<Value, JsonError> {
+        let start = self.reader.position();
+        for expected in literal {
+            if self.reader.next_byte()? != Some(*expected) {
+                return Err(JsonError { kind: ErrorKind::InvalidLiteral, position: start });
+            }
+        }
+        Ok(value)
+    }
+
+    /// parse_string reads a string up to its closing quote, decoding its escape sequences.
+    fn parse_string(&mut self) -> Result<String, JsonError> {
+        let start = self.reader.position();
+        self.reader.next_byte()?;
+        let mut bytes = Vec::new();
+        loop {
+            let position = self.reader.position();
+            match self.reader.next_byte()? {
+                Some(b'"') => break,
+                Some(b'\\') => {
+                    let character = self.parse_escape(position)?;
+                    bytes.extend_from_slice(character.encode_utf8(&mut [0; 4]).as_bytes());
+                }
+                Some(0x00..=0x1F) => return Err(JsonError { kind: ErrorKind::ControlCharacter, position }),
+                Some(byte) => bytes.push(byte),
+                None => return Err(self.unexpected_eof("a closing quote")),
+            }
+        }
+        // Validating once at the end also accepts characters split across reads.
+        String::from_utf8(bytes).map_err(|_| JsonError { kind: ErrorKind::InvalidUtf8, position: start })
+    }
+
+    /// parse_escape decodes the escape sequence starting at the backslash at start,
+    /// joining UTF-16 surrogate pairs.
+    fn parse_escape(&mut self, start: Position) -> Result<char, JsonError> {
+        let error = |kind| JsonError { kind, position: start };
+        let character = match self.reader.next_byte()? {
+            Some(b'"') => '"',
+            Some(b'\\') => '\\',
+            Some(b'/') => '/',
+            Some(b'b') => '\u{08}',
+            Some(b'f') => '\u{0C}',
+            Some(b'n') => '\n',
+            Some(b'r') => '\r',
+            Some(b't') => '\t',
+            Some(b'u') => {
+                let high = self.parse_hex4(start)?;
+                if !(0xD800..=0xDBFF).contains(&high) {
+                    return char::from_u32(high).ok_or(error(ErrorKind::LoneSurrogate));
+                }
+                if self.reader.peek_byte()? != Some(b'\\') {
+                    return Err(error(ErrorKind::LoneSurrogate));
+                }
+                self.reader.next_byte()?;
+                if self.reader.next_byte()? != Some(b'u') {
+                    return Err(error(ErrorKind::LoneSurrogate));
+                }
+                let low = self.parse_hex4(start)?;
+                if !(0xDC00..=0xDFFF).contains(&low) {
+                    return Err(error(ErrorKind::LoneSurrogate));
+                }
+                return char::from_u32(0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00)).ok_or(error(ErrorKind::LoneSurrogate));
+            }
+            _ => return Err(error(ErrorKind::InvalidEscape)),
+        };
+        Ok(character)
+    }
+
+    /// parse_hex4 reads the four hex digits of the \u escape starting at start.
+    fn parse_hex4(&mut self, start: Position) -> Result<u32, JsonError> {
+        let mut code = 0;
+        for _ in 0..4 {
+            match self.reader.next_byte()?.and_then(|byte| char::from(byte).to_digit(16)) {
+                Some(digit) => code = code * 16 + digit,
+                None => return Err(JsonError { kind: ErrorKind::InvalidEscape, position: start }),
+            }
+        }
+        Ok(code)
+    }
+
+    /// parse_number reads a number following the grammar of RFC 8259:
+    /// an optional minus, an integer part without leading zeros, an optional fraction and an optional exponent.
+    fn parse_number(&mut self) -> Result<Number, JsonError> {
+        let start = self.reader.position();
+        let invalid = JsonError { kind: ErrorKind::InvalidNumber, position: start };
+        let mut number = String::new();
+        if self.reader.peek_byte()? == Some(b'-') {
+            self.push_byte(&mut number)?;
+        }
+        match self.reader.peek_byte()? {
+            Some(b'0') => {
+                self.push_byte(&mut number)?;
+                if let Some(b'0'..=b'9') = self.reader.peek_byte()? {
+                    return Err(invalid);
+                }
+            }
+            Some(b'1'..=b'9') => {
+                self.push_digits(&mut number)?;
+            }
+            _ => return Err(invalid),
+        }
+        let mut is_integer = true;
+        if self.reader.peek_byte()? == Some(b'.') {
+            is_integer = false;
+            self.push_byte(&mut number)?;
+            if self.push_digits(&mut number)? == 0 {
+                return Err(invalid);
+            }
+        }
+        if let Some(b'e' | b'E') = self.reader.peek_byte()? {
+            is_integer = false;
+            self.push_byte(&mut number)?;
+            if let Some(b'+' | b'-') = self.reader.peek_byte()? {
+                self.push_byte(&mut number)?;
+            }
+            if self.push_digits(&mut number)? == 0 {
+                return Err(invalid);
+            }
+        }
+
+        // Integers too large for an i64 are kept as floats.
+        if let Some(integer) = is_integer.then(|| number.parse().ok()).flatten() {
+            return Ok(Number::I64(integer));
+        }
+        match number.parse::<f64>() {
+            Ok(float) if float.is_finite() => Ok(Number::F64(float)),
+            Ok(_) => Err(JsonError { kind: ErrorKind::NumberOutOfRange, position: start }),
+            Err(_) => Err(invalid),
+        }
+    }
+
+    fn push_byte(&mut self, number: &mut String) -> Result<(), JsonError> {
+        if let Some(byte) = self.reader.next_byte()? {
+            number.push(char::from(byte));
+        }
+        Ok(())
+    }
+
+    /// push_digits moves a run of digits into number, returning how many there were.
+    fn push_digits(&mut self, number: &mut String) -> Result<usize, JsonError> {
+        let mut count = 0;
+        while let Some(b'0'..=b'9') = self.reader.peek_byte()? {
+            self.push_byte(number)?;
+            count += 1;
+        }
+        Ok(count)
+    }
+
+    fn unexpected_eof(&self, expected: &'static str) -> JsonError {
+        self.reader.error(ErrorKind::UnexpectedEof { expected })
+    }
+}
+
+impl<T> Iterator for PullParser<T>
+where
+    T: Read,
+{
+    type Item = Result<Event, JsonError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.next_event().transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// OneByteReader hands out its input a byte per read, splitting every multi-byte character.
+    struct OneByteReader<'a>(&'a [u8]);
+
+    impl Read for OneByteReader<'_> {
+        fn read(&mut self, buf: &mut [u8]) -> std::io::Result<usize> {
+            match (self.0.split_first(), buf.first_mut()) {
+                (Some((byte, rest)), Some(slot)) => {
+                    *slot = *byte;
+                    self.0 = rest;
+                    Ok(1)
+                }
+                _ => Ok(0),
+            }
+        }
+    }
+
+    fn events(input: &[u8]) -> Result<Vec<Event>, JsonError> {
+        PullParser::<&[u8]>::from_bytes(input).collect()
+    }
+
+    #[test]
+    fn test_events_of_nested_document() {
+        let input = " {\"a\": [1, -2.5e1, true, null], \"b\": {}, \"c\": \"x\"}\n";
+        let expected = vec![
+            Event::StartObject,
+            Event::Key("a".to_string()),
+            Event::StartArray,
+            Event::Value(Value::Number(Number::I64(1))),
+            Event::Value(Value::Number(Number::F64(-25.0))),
+            Event::Value(Value::Boolean(true)),
+            Event::Value(Value::Null),
+            Event::EndArray,
+            Event::Key("b".to_string()),
+            Event::StartObject,
+            Event::EndObject,
+            Event::Key("c".to_string()),
+            Event::Value(Value::String("x".to_string())),
+            Event::EndObject,
+        ];
+        assert_eq!(events(input.as_bytes()).unwrap(), expected);
+
+        let incremental: Result<Vec<Event>, JsonError> = PullParser::new(OneByteReader(input.as_bytes())).collect();
+        assert_eq!(incremental.unwrap(), expected);
+        let unicode = PullParser::new(OneByteReader("[\"héllo ✓\"]".as_bytes())).nth(1).unwrap();
+        assert_eq!(unicode.unwrap(), Event::Value(Value::String("héllo ✓".to_string())));
+    }
+
+    #[test]
+    fn test_errors_report_their_position() {
+        let cases: [(&[u8], ErrorKind, usize, usize); 12] = [
+            (b"", ErrorKind::UnexpectedEof { expected: "a value" }, 1, 1),
+            (b"[1, 2", ErrorKind::UnexpectedEof { expected: "',' or ']'" }, 1, 6),
+            (b"{\"a\" 1}", ErrorKind::UnexpectedByte { found: b'1', expected: "':'" }, 1, 6),
+            (b"{\"a\": 1,}", ErrorKind::UnexpectedByte { found: b'}', expected: "a string key" }, 1, 9),
+            (b"[1,\n  ]", ErrorKind::UnexpectedByte { found: b']', expected: "a value" }, 2, 3),
+            (b"[1 2]", ErrorKind::UnexpectedByte { found: b'2', expected: "',' or ']'" }, 1, 4),
+            (b"{\"a\": [}", ErrorKind::UnexpectedByte { found: b'}', expected: "a value or ']'" }, 1, 8),
+            (b"[tru]", ErrorKind::InvalidLiteral, 1, 2),
+            (b"[\"\xc3\xa9\", -]", ErrorKind::InvalidNumber, 1, 7),
+            (b"\"\xff\"", ErrorKind::InvalidUtf8, 1, 1),
+            (b"\"abc", ErrorKind::UnexpectedEof { expected: "a closing quote" }, 1, 5),
+            (b"[1]\n x", ErrorKind::TrailingCharacters, 2, 2),
+        ];
+        for (input, kind, line, column) in cases {
+            let error = events(input).unwrap_err();
+            assert_eq!((&error.kind, error.position.line, error.position.column), (&kind, line, column), "{}", input.escape_ascii());
+        }
+
+        let error = events(b"\n[1, x]").unwrap_err();
+        assert_eq!(error.to_string(), "expected a value, found 'x' at line 2, column 5 (byte 5)");
+    }
+
+    #[test]
+    fn test_strings_decode_escapes() {
+        let string = |input: &[u8]| match events(input).map(|mut events| events.pop()) {
+            Ok(Some(Event::Value(Value::String(string)))) => Ok(string),
+            other => Err(other.unwrap_err().kind),
+        };
+        assert_eq!(string(b"\"\\\"\\\\\\/\\b\\f\\n\\r\\t\"").unwrap(), "\"\\/\u{08}\u{0C}\n\r\t");
+        assert_eq!(string(b"\"\\u00e9\\u2713\\uD834\\uDD1E\"").unwrap(), "\u{e9}\u{2713}\u{1D11E}");
+        assert_eq!(string(b"\"\\u0000\"").unwrap(), "\0");
+        assert_eq!(string(b"\"\\uD834\""), Err(ErrorKind::LoneSurrogate));
+        assert_eq!(string(b"\"\\uDD1E\\uD834\""), Err(ErrorKind::LoneSurrogate));
+        assert_eq!(string(b"\"\\uD834\\n\""), Err(ErrorKind::LoneSurrogate));
+        assert_eq!(string(b"\"\\u12G4\""), Err(ErrorKind::InvalidEscape));
+        assert_eq!(string(b"\"\\x41\""), Err(ErrorKind::InvalidEscape));
+        assert_eq!(string(b"\"a\tb\""), Err(ErrorKind::ControlCharacter));
+
+        let error = events(b"[\"ab\\q\"]").unwrap_err();
+        assert_eq!((error.kind, error.position.column), (ErrorKind::InvalidEscape, 5));
+
+        // A character split across the reader's 8 KiB buffer still decodes.
+        let long = format!("\"{}\u{e9}\u{1D11E}\"", "a".repeat(8190));
+        assert_eq!(string(long.as_bytes()).unwrap(), format!("{}\u{e9}\u{1D11E}", "a".repeat(8190)));
+    }
+
+    #[test]
+    fn test_numbers_follow_the_grammar() {
+        let number = |input: &str| match events(input.as_bytes()).map(|mut events| events.pop()) {
+            Ok(Some(Event::Value(Value::Number(number)))) => Ok(number),
+            other => Err(other.unwrap_err().kind),
+        };
+        assert_eq!(number("0"), Ok(Number::I64(0)));
+        assert_eq!(number("-0"), Ok(Number::I64(0)));
+        assert_eq!(number("-9223372036854775808"), Ok(Number::I64(i64::MIN)));
+        assert_eq!(number("9223372036854775808"), Ok(Number::F64(9223372036854775808.0)));
+        assert_eq!(number("1.5e3"), Ok(Number::F64(1500.0)));
+        assert_eq!(number("2E-2"), Ok(Number::F64(0.02)));
+        assert_eq!(number("1e+2"), Ok(Number::F64(100.0)));
+        assert_eq!(number("0.0"), Ok(Number::F64(0.0)));
+        assert_eq!(number("1e-400"), Ok(Number::F64(0.0)));
+        assert_eq!(number("1e400"), Err(ErrorKind::NumberOutOfRange));
+        assert_eq!(number("-1e400"), Err(ErrorKind::NumberOutOfRange));
+        for invalid in ["01", "-01", "1.", "1.e2", "1e", "1e+", "-", "-.5", "-a"] {
+            assert_eq!(number(invalid), Err(ErrorKind::InvalidNumber), "{invalid}");
+        }
+        assert_eq!(number(".5"), Err(ErrorKind::UnexpectedByte { found: b'.', expected: "a value" }));
+        assert_eq!(number("0x1"), Err(ErrorKind::TrailingCharacters));
+    }
+
+    #[test]
+    fn test_stops_after_the_first_error() {
+        let mut parser = PullParser::<&[u8]>::from_bytes(b"[1, ?, 2]");
+        assert_eq!(parser.next_event().unwrap(), Some(Event::StartArray));
+        assert_eq!(parser.next_event().unwrap(), Some(Event::Value(Value::Number(Number::I64(1)))));
+        assert!(parser.next_event().is_err());
+        assert_eq!(parser.next_event().unwrap(), None);
+
+        let deep = "[".repeat(100_000);
+        let error = events(deep.as_bytes()).unwrap_err();
+        assert_eq!((error.kind, error.position.offset), (ErrorKind::DepthLimitExceeded, DEFAULT_MAX_DEPTH));
+        let nested = format!("{}{}", "[".repeat(3), "]".repeat(3));
+        assert!(PullParser::<&[u8]>::from_bytes(nested.as_bytes()).max_depth(2).any(|event| event.is_err()));
+    }
+}
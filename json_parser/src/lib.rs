@@ -1,5 +1,10 @@
-
+pub mod error;
+pub mod event;
 pub mod reader;
 pub mod value;
-pub mod token;
+pub mod map;
 pub mod parser;
+pub mod path;
+mod pattern;
+pub mod schema;
+pub mod printer;
@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use crate::value::Value;
+
+/// Map holds the members of an object. It is a HashMap unless the preserve_order feature
+/// is enabled, which makes it an OrderedMap so members keep the order they were read in.
+#[cfg(not(feature = "preserve_order"))]
+pub type Map = HashMap<String, Value>;
+#[cfg(feature = "preserve_order")]
+pub type Map = OrderedMap;
+
+type Entry = (String, Value);
+
+/// Iter is what iterating over an OrderedMap by reference yields.
+pub type Iter<'a> = std::iter::Map<std::slice::Iter<'a, Entry>, fn(&Entry) -> (&String, &Value)>;
+
+/// OrderedMap is a map iterating in insertion order, offering the same methods as a HashMap
+/// so either can back Map. Replacing the value of a key keeps its position.
+#[derive(Debug, Clone, Default)]
+pub struct OrderedMap {
+    entries: Vec<Entry>,
+    index: HashMap<String, usize>,
+}
+
+impl OrderedMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    pub fn contains_key(&self, key: &str) -> bool {
+        self.index.contains_key(key)
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Value> {
+        self.index.get(key).map(|&position| &self.entries[position].1)
+    }
+
+    pub fn get_mut(&mut self, key: &str) -> Option<&mut Value> {
+        self.index.get(key).map(|&position| &mut self.entries[position].1)
+    }
+
+    /// insert adds a member at the end, or replaces the value of an existing one in place
+    /// and returns the old value.
+    pub fn insert(&mut self, key: String, value: Value) -> Option<Value> {
+        if let Some(&position) = self.index.get(&key) {
+            return Some(std::mem::replace(&mut self.entries[position].1, value));
+        }
+        self.index.insert(key.clone(), self.entries.len());
+        self.entries.push((key, value));
+        None
+    }
+
+    /// remove takes a member out, shifting the members after it back to keep their order.
+    pub fn remove(&mut self, key: &str) -> Option<Value> {
+        let position = self.index.remove(key)?;
+        let (_, value) = self.entries.remove(position);
+        for later in self.index.values_mut().filter(|later| **later > position) {
+            *later -= 1;
+        }
+        Some(value)
+    }
+
+    pub fn iter(&self) -> Iter<'_> {
+        self.entries.iter().map(|(key, value)| (key, value))
+    }
+
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&String, &mut Value)> {
+        self.entries.iter_mut().map(|(key, value)| (&*key, value))
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.entries.iter().map(|(key, _)| key)
+    }
+
+    pub fn values(&self) -> impl Iterator<Item = &Value> {
+        self.entries.iter().map(|(_, value)| value)
+    }
+
+    pub fn values_mut(&mut self) -> impl Iterator<Item = &mut Value> {
+        self.entries.iter_mut().map(|(_, value)| value)
+    }
+}
+
+/// Maps are equal when they have the same members, whatever their order, as with a HashMap.
+impl PartialEq for OrderedMap {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().all(|(key, value)| other.get(key) == Some(value))
+    }
+}
+
+impl FromIterator<Entry> for OrderedMap {
+    fn from_iter<I: IntoIterator<Item = Entry>>(entries: I) -> Self {
+        let mut map = OrderedMap::new();
+        map.extend(entries);
+        map
+    }
+}
+
+impl Extend<Entry> for OrderedMap {
+    fn extend<I: IntoIterator<Item = Entry>>(&mut self, entries: I) {
+        for (key, value) in entries {
+            self.insert(key, value);
+        }
+    }
+}
+
+impl<const N: usize> From<[Entry; N]> for OrderedMap {
+    fn from(entries: [Entry; N]) -> Self {
+        entries.into_iter().collect()
+    }
+}
+
+impl IntoIterator for OrderedMap {
+    type Item = Entry;
+    type IntoIter = std::vec::IntoIter<Entry>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.entries.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a OrderedMap {
+    type Item = (&'a String, &'a Value);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keeps_insertion_order() {
+        let mut map = OrderedMap::from([("b".to_string(), Value::Null), ("a".to_string(), Value::Boolean(true))]);
+        assert_eq!(map.insert("c".to_string(), Value::Null), None);
+        assert_eq!(map.insert("b".to_string(), Value::Boolean(false)), Some(Value::Null));
+        assert_eq!(map.keys().collect::<Vec<_>>(), ["b", "a", "c"]);
+        assert_eq!(map.get("b"), Some(&Value::Boolean(false)));
+
+        assert_eq!(map.remove("b"), Some(Value::Boolean(false)));
+        assert_eq!(map.remove("b"), None);
+        *map.get_mut("c").unwrap() = Value::Boolean(true);
+        assert_eq!(map.iter().collect::<Vec<_>>(), [(&"a".to_string(), &Value::Boolean(true)), (&"c".to_string(), &Value::Boolean(true))]);
+        assert!(map.contains_key("c") && !map.contains_key("b"));
+
+        let reversed: OrderedMap = map.clone().into_iter().rev().collect();
+        assert_eq!(reversed, map);
+        assert_ne!(reversed, OrderedMap::new());
+    }
+}
@@ -1,128 +1,101 @@
-use std::{collections::HashMap, fs::File, io::{BufReader, Cursor}, iter::Peekable, slice::Iter};
+use std::io::Read;
 
-use crate::{token::{Jsontokensizer, Token}, value::Value};
+use crate::{
+    error::{ErrorKind, JsonError},
+    event::{Event, PullParser},
+    map::Map,
+    value::Value,
+};
 
 /// Main parser which is the entrypoint for parsing JSON
 pub struct JsonParser;
 
-impl JsonParser {
-    
+/// Partial is an array or object whose end has not been read yet.
+enum Partial {
+    Array(Vec<Value>),
+    Object(Map, Option<String>),
+}
 
-    pub fn parse_from_bytes<'a>(input: &'a[u8]) -> Result<Value, ()> {
-        let mut json_tokenizer = Jsontokensizer::<BufReader<Cursor<&[u8]>>>::from_bytes(input);
-        let tokens = json_tokenizer.tokensize_json()?;
-        Ok(Self::tokens_to_value(tokens))
+impl JsonParser {
+    pub fn parse_from_bytes(input: &[u8]) -> Result<Value, JsonError> {
+        Self::parse_events(PullParser::<&[u8]>::from_bytes(input))
     }
 
-    pub fn parse(reader: File) -> Result<Value, ()> {
-        let mut json_tokenizer = Jsontokensizer::<BufReader<File>>::new(reader);
-        let tokens = json_tokenizer.tokensize_json()?;
-        Ok(Self::tokens_to_value(tokens))
+    pub fn parse<T: Read>(reader: T) -> Result<Value, JsonError> {
+        Self::parse_events(PullParser::new(reader))
     }
 
-    fn tokens_to_value(tokens: &[Token]) -> Value {
-        let mut iterator = tokens.iter().peekable();
-        let mut value = Value::Null;
-        while let Some(token) = iterator.next() {
-            match token {
-                Token::CurlyOpen => {
-                    value = Value::Object(Self::process_object(&mut iterator));
-                }
-                Token::String(string) => {
-                    value = Value::String(string.clone());
-                }
-                Token::Number(number) => {
-                    value = Value::Number(*number);
+    /// parse_events builds a Value from the events of a configured parser.
+    pub fn parse_events<T: Read>(mut events: PullParser<T>) -> Result<Value, JsonError> {
+        let mut stack = Vec::<Partial>::new();
+        let mut document = None;
+        while let Some(event) = events.next_event()? {
+            let value = match event {
+                Event::StartObject => {
+                    stack.push(Partial::Object(Map::new(), None));
+                    continue;
                 }
-                Token::ArrayOpen => {
-                    value = Value::Array(Self::process_array(&mut iterator))
+                Event::StartArray => {
+                    stack.push(Partial::Array(Vec::new()));
+                    continue;
                 }
-                Token::Boolean(boolean) => value = Value::Boolean(*boolean),
-                Token::Null => value = Value::Null,
-                Token::Comma | Token::CurlyClose | Token::Quotes | Token::Colon | Token::ArrayClose => {}
-            }
-        }
-        value
-    }
-
-    fn process_object(iterator: &mut Peekable<Iter<Token>>) -> HashMap<String, Value> {
-        let mut is_key = true;
-        let mut current_key : Option<&str> = None;
-        let mut value = HashMap::<String, Value>::new();
-        while let Some(token) = iterator.next() {
-            match token {
-                Token::CurlyOpen => {
-                    if let Some(current_key) = current_key {
-                        value.insert(current_key.to_string(), Value::Object(Self::process_object(iterator)));
-                    }
-                }
-                Token::CurlyClose => {
-                    break;
-                }
-                Token::Quotes | Token::ArrayClose => {},
-                Token::Colon => {
-                    is_key = false;
-                }
-                Token::String(string) => {
-                    if is_key {
-                        current_key = Some(string);
-                    } else if let Some(key) = current_key {
-                        value.insert(key.to_string(), Value::String(string.clone()));
+                Event::Key(key) => {
+                    if let Some(Partial::Object(_, pending_key)) = stack.last_mut() {
+                        *pending_key = Some(key);
                     }
+                    continue;
                 }
-                Token::Number(number) => {
-                    if let Some(key) = current_key {
-                        value.insert(key.to_string(), Value::Number(*number));
-                        current_key = None;
-                    }
-                }
-                Token::ArrayOpen => {
-                    if let Some(key) = current_key {
-                        value.insert(key.to_string(), Value::Array(Self::process_array(iterator)));
-                    }
-                }
-                Token::Comma => is_key = true,
-                Token::Boolean(boolean) => {
-                    if let Some(key) = current_key {
-                        value.insert(key.to_string(), Value::Boolean(*boolean));
-                        current_key = None;
-                    }
-                }
-                Token::Null => {
-                    if let Some(key) = current_key {
-                        value.insert(key.to_string(), Value::Null);
-                        current_key = None;
+                Event::Value(value) => value,
+                Event::EndObject | Event::EndArray => match stack.pop() {
+                    Some(Partial::Array(values)) => Value::Array(values),
+                    Some(Partial::Object(members, _)) => Value::Object(members),
+                    None => continue,
+                },
+            };
+            match stack.last_mut() {
+                Some(Partial::Array(values)) => values.push(value),
+                Some(Partial::Object(members, pending_key)) => {
+                    if let Some(key) = pending_key.take() {
+                        members.insert(key, value);
                     }
                 }
+                None => document = Some(value),
             }
         }
-        value
+        document.ok_or_else(|| JsonError { kind: ErrorKind::UnexpectedEof { expected: "a value" }, position: events.position() })
     }
+}
 
-    fn process_array(iterator: &mut Peekable<Iter<Token>>) -> Vec<Value> {
-        let mut internal_value = Vec::<Value>::new();
-
-        while let Some(token) = iterator.next() {
-            match token {
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value::Number;
 
-                Token::CurlyOpen => {
-                    internal_value.push(Value::Object(Self::process_object(iterator)));
-                }
-                Token::String(string) => internal_value.push(Value::String(string.clone())),
-                Token::Number(number) => internal_value.push(Value::Number(*number)),
-                Token::ArrayOpen => {
-                    internal_value.push(Value::Array(Self::process_array(iterator)));
-                }
-                Token::ArrayClose => {
-                    break;
-                }
-                Token::Boolean(boolean) => internal_value.push(Value::Boolean(*boolean)),
-                Token::Null => internal_value.push(Value::Null),
-                Token::Comma | Token::CurlyClose | Token::Quotes | Token::Colon => {}
+    #[test]
+    fn test_parse_builds_values() {
+        let value = JsonParser::parse_from_bytes(b"{\"a\": [1, {\"b\": null}], \"c\": false}").unwrap();
+        let expected = Value::Object(Map::from([
+            (
+                "a".to_string(),
+                Value::Array(vec![Value::Number(Number::I64(1)), Value::Object(Map::from([("b".to_string(), Value::Null)]))]),
+            ),
+            ("c".to_string(), Value::Boolean(false)),
+        ]));
+        assert_eq!(value, expected);
+        assert_eq!(JsonParser::parse_from_bytes(b" 12 ").unwrap(), Value::Number(Number::I64(12)));
+        assert!(JsonParser::parse_from_bytes(b"{\"a\": 1} {}").is_err());
 
-            }
-        }
+        let document = JsonParser::parse(&include_bytes!("../examples/test.json")[..]).unwrap();
+        let Value::Object(members) = document else {
+            panic!("expected an object");
+        };
+        assert_eq!(members.get("version"), Some(&Value::String("2".to_string())));
+    }
 
-        internal_value
+    #[cfg(feature = "preserve_order")]
+    #[test]
+    fn test_parse_preserves_member_order() {
+        let input = "{\"z\":1,\"a\":{\"y\":null,\"b\":[]},\"m\":true}";
+        assert_eq!(JsonParser::parse_from_bytes(input.as_bytes()).unwrap().to_json_string(), input);
     }
-}
\ No newline at end of file
+}
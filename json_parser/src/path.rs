@@ -0,0 +1,594 @@
+use std::{cmp::Ordering, fmt};
+
+use crate::{
+    parser::JsonParser,
+    value::{Number, Value},
+};
+
+/// Filters, negations and parentheses nested deeper than this are rejected. Each level
+/// takes several stack frames to parse, hence the limit well below the
+/// DEFAULT_MAX_DEPTH documents may nest to.
+pub const MAX_FILTER_DEPTH: usize = 64;
+
+/// JsonPath is a parsed JSONPath expression. The supported subset is:
+///
+/// - `$` the root and `@` the current value inside filters,
+/// - `.name`, `['name']` and `["name"]` object members,
+/// - `.*` and `[*]` every child,
+/// - `..` recursive descent, as in `$..name` or `$..[0]`,
+/// - `[1]`, `[-1]` and unions such as `[0,'a']`,
+/// - `[start:end:step]` array slices,
+/// - `[?(@.price < 10 && @.isbn)]` filters, comparing with `==`, `!=`, `<`, `<=`, `>` and `>=`
+///   and combining with `&&`, `||`, `!` and parentheses.
+#[derive(Debug, Clone, PartialEq)]
+pub struct JsonPath {
+    query: Query,
+}
+
+/// PathError is a JSONPath expression that could not be parsed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PathError {
+    pub offset: usize,
+    pub expected: &'static str,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+struct Query {
+    relative: bool,
+    segments: Vec<Segment>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Child(Vec<Selector>),
+    Descendant(Vec<Selector>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Selector {
+    Name(String),
+    Wildcard,
+    Index(i64),
+    Slice(Option<i64>, Option<i64>, i64),
+    Filter(Filter),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Filter {
+    Exists(Query),
+    Compare(Operand, Comparison, Operand),
+    Not(Box<Filter>),
+    And(Box<Filter>, Box<Filter>),
+    Or(Box<Filter>, Box<Filter>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Operand {
+    Literal(Value),
+    Query(Query),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Comparison {
+    Equal,
+    NotEqual,
+    Less,
+    LessOrEqual,
+    Greater,
+    GreaterOrEqual,
+}
+
+impl JsonPath {
+    pub fn parse(path: &str) -> Result<JsonPath, PathError> {
+        let mut parser = Parser { input: path.as_bytes(), offset: 0, depth: 0 };
+        if !parser.eat(b'$') {
+            return Err(parser.error("'$'"));
+        }
+        let query = parser.parse_query(false)?;
+        if parser.offset < parser.input.len() {
+            return Err(parser.error("'.' or '['"));
+        }
+        Ok(JsonPath { query })
+    }
+
+    /// query returns the values the path selects, in document order for arrays.
+    pub fn query<'a>(&self, value: &'a Value) -> Vec<&'a Value> {
+        self.query.select(value, value)
+    }
+}
+
+impl fmt::Display for PathError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid JSONPath, expected {} at byte {}", self.expected, self.offset)
+    }
+}
+
+impl std::error::Error for PathError {}
+
+impl Query {
+    fn select<'a>(&self, root: &'a Value, current: &'a Value) -> Vec<&'a Value> {
+        let mut nodes = vec![if self.relative { current } else { root }];
+        for segment in &self.segments {
+            let mut selected = Vec::new();
+            for node in nodes {
+                match segment {
+                    Segment::Child(selectors) => select_children(selectors, root, node, &mut selected),
+                    Segment::Descendant(selectors) => {
+                        for descendant in descendants(node) {
+                            select_children(selectors, root, descendant, &mut selected);
+                        }
+                    }
+                }
+            }
+            nodes = selected;
+        }
+        nodes
+    }
+}
+
+/// select_children applies each selector to node, appending what they select to selected.
+fn select_children<'a>(selectors: &[Selector], root: &'a Value, node: &'a Value, selected: &mut Vec<&'a Value>) {
+    for selector in selectors {
+        match (selector, node) {
+            (Selector::Name(name), Value::Object(members)) => selected.extend(members.get(name)),
+            (Selector::Wildcard, _) => selected.extend(children(node)),
+            (Selector::Index(index), Value::Array(values)) => {
+                selected.extend(normalize_index(*index, values.len()).map(|index| &values[index]));
+            }
+            (Selector::Slice(start, end, step), Value::Array(values)) => {
+                selected.extend(slice_indices(*start, *end, *step, values.len()).into_iter().map(|index| &values[index]));
+            }
+            (Selector::Filter(filter), _) => selected.extend(children(node).filter(|child| filter.matches(root, child))),
+            _ => {}
+        }
+    }
+}
+
+fn children(value: &Value) -> Box<dyn Iterator<Item = &Value> + '_> {
+    match value {
+        Value::Array(values) => Box::new(values.iter()),
+        Value::Object(members) => Box::new(members.values()),
+        _ => Box::new(std::iter::empty()),
+    }
+}
+
+/// descendants lists value and everything nested in it, parents before their children.
+fn descendants(value: &Value) -> Vec<&Value> {
+    let mut found = Vec::new();
+    let mut stack = vec![value];
+    while let Some(value) = stack.pop() {
+        found.push(value);
+        let start = stack.len();
+        stack.extend(children(value));
+        stack[start..].reverse();
+    }
+    found
+}
+
+/// normalize_index turns a possibly negative index, counting from the end, into a position in an array of len.
+fn normalize_index(index: i64, len: usize) -> Option<usize> {
+    let index = if index < 0 { index + len as i64 } else { index };
+    usize::try_from(index).ok().filter(|&index| index < len)
+}
+
+/// slice_indices lists the positions a slice selects with the bounds clamped like Python's.
+fn slice_indices(start: Option<i64>, end: Option<i64>, step: i64, len: usize) -> Vec<usize> {
+    let len = len as i64;
+    let bound = |index: i64, low: i64, high: i64| if index < 0 { index + len } else { index }.clamp(low, high);
+    let mut indices = Vec::new();
+    if step > 0 {
+        let mut index = start.map_or(0, |start| bound(start, 0, len));
+        let end = end.map_or(len, |end| bound(end, 0, len));
+        while index < end {
+            indices.push(index as usize);
+            // A step past i64::MAX ends the slice like any step past its end.
+            let Some(next) = index.checked_add(step) else { break };
+            index = next;
+        }
+    } else if step < 0 {
+        let mut index = start.map_or(len - 1, |start| bound(start, -1, len - 1));
+        let end = end.map_or(-1, |end| bound(end, -1, len - 1));
+        while index > end {
+            indices.push(index as usize);
+            let Some(next) = index.checked_add(step) else { break };
+            index = next;
+        }
+    }
+    indices
+}
+
+impl Filter {
+    fn matches(&self, root: &Value, current: &Value) -> bool {
+        match self {
+            Filter::Exists(query) => !query.select(root, current).is_empty(),
+            Filter::Compare(left, comparison, right) => {
+                compare(left.evaluate(root, current), *comparison, right.evaluate(root, current))
+            }
+            Filter::Not(filter) => !filter.matches(root, current),
+            Filter::And(left, right) => left.matches(root, current) && right.matches(root, current),
+            Filter::Or(left, right) => left.matches(root, current) || right.matches(root, current),
+        }
+    }
+}
+
+impl Operand {
+    /// evaluate is the single value an operand stands for, a query selecting no or several values has none.
+    fn evaluate<'a>(&'a self, root: &'a Value, current: &'a Value) -> Option<&'a Value> {
+        match self {
+            Operand::Literal(value) => Some(value),
+            Operand::Query(query) => match query.select(root, current)[..] {
+                [value] => Some(value),
+                _ => None,
+            },
+        }
+    }
+}
+
+/// compare applies comparison, numbers compare by value whichever way they are stored
+/// and two missing values are equal.
+fn compare(left: Option<&Value>, comparison: Comparison, right: Option<&Value>) -> bool {
+    let ordering = match (left, right) {
+        (Some(Value::Number(left)), Some(Value::Number(right))) => as_f64(*left).partial_cmp(&as_f64(*right)),
+        (Some(Value::String(left)), Some(Value::String(right))) => Some(left.cmp(right)),
+        (left, right) if left == right => Some(Ordering::Equal),
+        _ => None,
+    };
+    match comparison {
+        Comparison::Equal => ordering == Some(Ordering::Equal),
+        Comparison::NotEqual => ordering != Some(Ordering::Equal),
+        Comparison::Less => ordering == Some(Ordering::Less),
+        Comparison::LessOrEqual => matches!(ordering, Some(Ordering::Less | Ordering::Equal)),
+        Comparison::Greater => ordering == Some(Ordering::Greater),
+        Comparison::GreaterOrEqual => matches!(ordering, Some(Ordering::Greater | Ordering::Equal)),
+    }
+}
+
+fn as_f64(number: Number) -> f64 {
+    match number {
+        Number::I64(number) => number as f64,
+        Number::F64(number) => number,
+    }
+}
+
+struct Parser<'a> {
+    input: &'a [u8],
+    offset: usize,
+    /// depth counts the filters, negations and parentheses being parsed, so nesting them
+    /// cannot overflow the stack.
+    depth: usize,
+}
+
+impl Parser<'_> {
+    fn peek(&self) -> Option<u8> {
+        self.input.get(self.offset).copied()
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        let matched = self.peek() == Some(byte);
+        if matched {
+            self.offset += 1;
+        }
+        matched
+    }
+
+    fn eat_str(&mut self, text: &str) -> bool {
+        let matched = self.input[self.offset..].starts_with(text.as_bytes());
+        if matched {
+            self.offset += text.len();
+        }
+        matched
+    }
+
+    fn expect(&mut self, byte: u8, expected: &'static str) -> Result<(), PathError> {
+        if self.eat(byte) { Ok(()) } else { Err(self.error(expected)) }
+    }
+
+    fn skip_spaces(&mut self) {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek() {
+            self.offset += 1;
+        }
+    }
+
+    fn error(&self, expected: &'static str) -> PathError {
+        PathError { offset: self.offset, expected }
+    }
+
+    /// nested runs parse one level of filter nesting deeper, failing beyond MAX_FILTER_DEPTH levels.
+    fn nested<T>(&mut self, parse: impl FnOnce(&mut Self) -> Result<T, PathError>) -> Result<T, PathError> {
+        if self.depth == MAX_FILTER_DEPTH {
+            return Err(self.error("filters nested less deeply"));
+        }
+        self.depth += 1;
+        let result = parse(self);
+        self.depth -= 1;
+        result
+    }
+
+    /// parse_query reads the segments after `$` or `@`.
+    fn parse_query(&mut self, relative: bool) -> Result<Query, PathError> {
+        let mut segments = Vec::new();
+        loop {
+            if self.eat_str("..") {
+                let selectors = match self.peek() {
+                    Some(b'[') => self.parse_bracket()?,
+                    _ => vec![self.parse_shorthand()?],
+                };
+                segments.push(Segment::Descendant(selectors));
+            } else if self.eat(b'.') {
+                segments.push(Segment::Child(vec![self.parse_shorthand()?]));
+            } else if self.peek() == Some(b'[') {
+                segments.push(Segment::Child(self.parse_bracket()?));
+            } else {
+                return Ok(Query { relative, segments });
+            }
+        }
+    }
+
+    /// parse_shorthand reads the `*` or member name following a dot.
+    fn parse_shorthand(&mut self) -> Result<Selector, PathError> {
+        if self.eat(b'*') {
+            return Ok(Selector::Wildcard);
+        }
+        let start = self.offset;
+        while let Some(b'a'..=b'z' | b'A'..=b'Z' | b'0'..=b'9' | b'_' | 0x80..) = self.peek() {
+            self.offset += 1;
+        }
+        if start == self.offset {
+            return Err(self.error("a member name or '*'"));
+        }
+        Ok(Selector::Name(String::from_utf8_lossy(&self.input[start..self.offset]).into_owned()))
+    }
+
+    /// parse_bracket reads a comma separated list of selectors between brackets.
+    fn parse_bracket(&mut self) -> Result<Vec<Selector>, PathError> {
+        self.expect(b'[', "'['")?;
+        let mut selectors = Vec::new();
+        loop {
+            self.skip_spaces();
+            selectors.push(self.parse_selector()?);
+            self.skip_spaces();
+            if self.eat(b']') {
+                return Ok(selectors);
+            }
+            self.expect(b',', "',' or ']'")?;
+        }
+    }
+
+    fn parse_selector(&mut self) -> Result<Selector, PathError> {
+        match self.peek() {
+            Some(b'*') => {
+                self.offset += 1;
+                Ok(Selector::Wildcard)
+            }
+            Some(b'\'' | b'"') => Ok(Selector::Name(self.parse_string()?)),
+            Some(b'?') => {
+                self.offset += 1;
+                self.skip_spaces();
+                Ok(Selector::Filter(self.nested(Self::parse_or)?))
+            }
+            Some(b'-' | b'0'..=b'9' | b':') => {
+                let start = self.parse_integer()?;
+                self.skip_spaces();
+                if !self.eat(b':') {
+                    return start.map(Selector::Index).ok_or_else(|| self.error("an index"));
+                }
+                self.skip_spaces();
+                let end = self.parse_integer()?;
+                self.skip_spaces();
+                let step = if self.eat(b':') {
+                    self.skip_spaces();
+                    self.parse_integer()?.unwrap_or(1)
+                } else {
+                    1
+                };
+                Ok(Selector::Slice(start, end, step))
+            }
+            _ => Err(self.error("a selector")),
+        }
+    }
+
+    /// parse_integer reads an optional integer, as in the bounds of a slice.
+    fn parse_integer(&mut self) -> Result<Option<i64>, PathError> {
+        let start = self.offset;
+        self.eat(b'-');
+        while let Some(b'0'..=b'9') = self.peek() {
+            self.offset += 1;
+        }
+        if start == self.offset {
+            return Ok(None);
+        }
+        let digits = std::str::from_utf8(&self.input[start..self.offset]).unwrap_or_default();
+        digits.parse().map(Some).map_err(|_| PathError { offset: start, expected: "an integer" })
+    }
+
+    /// parse_string reads a string in single or double quotes where a backslash escapes the next character.
+    fn parse_string(&mut self) -> Result<String, PathError> {
+        let Some(quote) = self.peek() else {
+            return Err(self.error("a string"));
+        };
+        self.offset += 1;
+        let mut bytes = Vec::new();
+        loop {
+            match self.peek() {
+                Some(byte) if byte == quote => break,
+                Some(b'\\') => {
+                    self.offset += 1;
+                    bytes.extend(self.peek());
+                }
+                Some(byte) => bytes.push(byte),
+                None => return Err(self.error("a closing quote")),
+            }
+            self.offset += 1;
+        }
+        self.offset += 1;
+        Ok(String::from_utf8_lossy(&bytes).into_owned())
+    }
+
+    fn parse_or(&mut self) -> Result<Filter, PathError> {
+        let mut filter = self.parse_and()?;
+        while self.eat_str("||") {
+            self.skip_spaces();
+            filter = Filter::Or(Box::new(filter), Box::new(self.parse_and()?));
+        }
+        Ok(filter)
+    }
+
+    fn parse_and(&mut self) -> Result<Filter, PathError> {
+        let mut filter = self.parse_unary()?;
+        while self.eat_str("&&") {
+            self.skip_spaces();
+            filter = Filter::And(Box::new(filter), Box::new(self.parse_unary()?));
+        }
+        Ok(filter)
+    }
+
+    /// parse_unary reads a negation, a parenthesized filter, an existence test or a comparison.
+    fn parse_unary(&mut self) -> Result<Filter, PathError> {
+        let filter = if self.eat(b'!') {
+            self.skip_spaces();
+            Filter::Not(Box::new(self.nested(Self::parse_unary)?))
+        } else if self.eat(b'(') {
+            self.skip_spaces();
+            let filter = self.nested(Self::parse_or)?;
+            self.expect(b')', "')'")?;
+            filter
+        } else {
+            let start = self.offset;
+            let left = self.parse_operand()?;
+            self.skip_spaces();
+            match (self.parse_comparison(), left) {
+                (Some(comparison), left) => {
+                    self.skip_spaces();
+                    Filter::Compare(left, comparison, self.parse_operand()?)
+                }
+                (None, Operand::Query(query)) => Filter::Exists(query),
+                (None, Operand::Literal(_)) => return Err(PathError { offset: start, expected: "a query or a comparison" }),
+            }
+        };
+        self.skip_spaces();
+        Ok(filter)
+    }
+
+    fn parse_comparison(&mut self) -> Option<Comparison> {
+        let comparisons = [
+            ("==", Comparison::Equal),
+            ("!=", Comparison::NotEqual),
+            ("<=", Comparison::LessOrEqual),
+            (">=", Comparison::GreaterOrEqual),
+            ("<", Comparison::Less),
+            (">", Comparison::Greater),
+        ];
+        comparisons.into_iter().find(|(text, _)| self.eat_str(text)).map(|(_, comparison)| comparison)
+    }
+
+    /// parse_operand reads a query starting at `@` or `$`, or a string, number, boolean or null literal.
+    fn parse_operand(&mut self) -> Result<Operand, PathError> {
+        let start = self.offset;
+        match self.peek() {
+            Some(b'@') | Some(b'$') => {
+                let relative = self.eat(b'@');
+                self.eat(b'$');
+                Ok(Operand::Query(self.parse_query(relative)?))
+            }
+            Some(b'\'' | b'"') => Ok(Operand::Literal(Value::String(self.parse_string()?))),
+            Some(b'-' | b'0'..=b'9') => {
+                while let Some(b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9') = self.peek() {
+                    self.offset += 1;
+                }
+                match JsonParser::parse_from_bytes(&self.input[start..self.offset]) {
+                    Ok(number @ Value::Number(_)) => Ok(Operand::Literal(number)),
+                    _ => Err(PathError { offset: start, expected: "a number" }),
+                }
+            }
+            _ if self.eat_str("true") => Ok(Operand::Literal(Value::Boolean(true))),
+            _ if self.eat_str("false") => Ok(Operand::Literal(Value::Boolean(false))),
+            _ if self.eat_str("null") => Ok(Operand::Literal(Value::Null)),
+            _ => Err(self.error("a query or a literal")),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn store() -> Value {
+        let document = br#"{"store": {
+            "book": [
+                {"category": "reference", "author": "Rees", "title": "Sayings", "price": 8.95},
+                {"category": "fiction", "author": "Waugh", "title": "Sword", "price": 12.99},
+                {"category": "fiction", "author": "Melville", "title": "Moby Dick", "isbn": "0-553", "price": 8},
+                {"category": "fiction", "author": "Tolkien", "title": "The Lord", "isbn": "0-395", "price": 22.99}
+            ],
+            "bicycle": {"color": "red", "price": 19.95}
+        }}"#;
+        JsonParser::parse_from_bytes(document).unwrap()
+    }
+
+    fn titles(value: &Value, path: &str) -> Vec<String> {
+        let selected = value.query(path).unwrap_or_else(|error| panic!("{path}: {error}"));
+        selected.into_iter().map(|book| String::try_from(book.pointer("/title").unwrap_or(book)).unwrap()).collect()
+    }
+
+    #[test]
+    fn test_query_selects_values() {
+        let store = store();
+        let cases: [(&str, &[&str]); 14] = [
+            ("$.store.book[0]", &["Sayings"]),
+            ("$['store'][\"book\"][-1]", &["The Lord"]),
+            ("$.store.book[0,2]", &["Sayings", "Moby Dick"]),
+            ("$.store.book[1:3]", &["Sword", "Moby Dick"]),
+            ("$.store.book[::-2]", &["The Lord", "Sword"]),
+            ("$.store.book[-2:]", &["Moby Dick", "The Lord"]),
+            ("$.store.book[:2:0]", &[]),
+            ("$.store.book[*].title", &["Sayings", "Sword", "Moby Dick", "The Lord"]),
+            ("$..book[?(@.isbn)]", &["Moby Dick", "The Lord"]),
+            ("$..book[?(@.price < 10)]", &["Sayings", "Moby Dick"]),
+            ("$..book[?(@.price >= 8 && @.category == 'fiction' && !@.isbn)]", &["Sword"]),
+            ("$..book[?(@.author == \"Rees\" || (@.price > 20))]", &["Sayings", "The Lord"]),
+            ("$.store.book[?(@.price == 8.0)]", &["Moby Dick"]),
+            ("$.store.book[?@.missing == null]", &[]),
+        ];
+        for (path, expected) in cases {
+            assert_eq!(titles(&store, path), expected, "{path}");
+        }
+
+        let mut prices: Vec<f64> = store.query("$..price").unwrap().into_iter().map(|price| f64::try_from(price).unwrap()).collect();
+        prices.sort_by(f64::total_cmp);
+        assert_eq!(prices, [8.0, 8.95, 12.99, 19.95, 22.99]);
+        assert_eq!(store.query("$.store.*").unwrap().len(), 2);
+        assert_eq!(store.query("$..*").unwrap().len(), 27);
+        assert_eq!(store.query("$").unwrap(), [&store]);
+        assert!(store.query("$.nothing[0]").unwrap().is_empty());
+
+        let numbers = JsonParser::parse_from_bytes(b"[1, 2, 3]").unwrap();
+        assert_eq!(numbers.query("$[1::9223372036854775807]").unwrap(), [&Value::Number(Number::I64(2))]);
+        assert_eq!(numbers.query("$[1::-9223372036854775808]").unwrap(), [&Value::Number(Number::I64(2))]);
+    }
+
+    #[test]
+    fn test_parse_reports_errors() {
+        let cases = [
+            ("", 0, "'$'"),
+            ("$.", 2, "a member name or '*'"),
+            ("$[1", 3, "',' or ']'"),
+            ("$['a", 4, "a closing quote"),
+            ("$[?(@.a == )]", 11, "a query or a literal"),
+            ("$[?(1)]", 4, "a query or a comparison"),
+            ("$[?(@.a == 1]", 12, "')'"),
+            ("$ x", 1, "'.' or '['"),
+        ];
+        for (path, offset, expected) in cases {
+            assert_eq!(JsonPath::parse(path), Err(PathError { offset, expected }), "{path}");
+        }
+
+        let negations = format!("$[?{}@.a]", "!".repeat(200_000));
+        assert_eq!(JsonPath::parse(&negations).unwrap_err().expected, "filters nested less deeply");
+        let parentheses = format!("$[?{}@.a{}]", "(".repeat(200_000), ")".repeat(200_000));
+        assert_eq!(JsonPath::parse(&parentheses).unwrap_err().expected, "filters nested less deeply");
+        let filters = format!("${}{}", "[?@".repeat(100_000), "]".repeat(100_000));
+        assert_eq!(JsonPath::parse(&filters).unwrap_err().expected, "filters nested less deeply");
+        let deepest = format!("${}{}", "[?@".repeat(MAX_FILTER_DEPTH), "]".repeat(MAX_FILTER_DEPTH));
+        assert!(JsonPath::parse(&deepest).is_ok());
+    }
+}
@@ -0,0 +1,480 @@
+/// Pattern is a regular expression in the ECMAScript syntax JSON Schema uses, limited to
+/// literals, `.`, classes such as `[^a-z\d]`, the escapes `\d \w \s` and their negations,
+/// `^` and `$`, groups with `|`, and the quantifiers `* + ? {n} {n,} {n,m}`, greedy or lazy.
+/// It finds a match anywhere in the text unless anchored. Matching follows every way through
+/// the pattern at once instead of backtracking, so it takes time linear in the length of the text.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Pattern {
+    program: Vec<Inst>,
+}
+
+/// MAX_PROGRAM_LEN bounds the instructions a pattern compiles to, as counted repetitions
+/// such as `(a{1000}){1000}` copy what they repeat.
+const MAX_PROGRAM_LEN: usize = 10_000;
+
+/// MAX_GROUP_DEPTH bounds how deeply groups nest, as parsing and compiling them recurse.
+const MAX_GROUP_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Char(char),
+    Any,
+    Class(Vec<ClassItem>, bool),
+    Start,
+    End,
+    Group(Vec<Vec<Node>>),
+    Repeat { node: Box<Node>, min: usize, max: Option<usize>, greedy: bool },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ClassItem {
+    Range(char, char),
+    Digit(bool),
+    Word(bool),
+    Space(bool),
+}
+
+/// Inst is an instruction of the compiled pattern. Step consumes a character its Char, Any
+/// or Class node accepts, Split continues at both targets, and Accept means the text matched.
+#[derive(Debug, Clone, PartialEq)]
+enum Inst {
+    Step(Node),
+    Split(usize, usize),
+    Jump(usize),
+    Start,
+    End,
+    Accept,
+}
+
+impl Pattern {
+    pub(crate) fn new(pattern: &str) -> Result<Pattern, &'static str> {
+        let mut parser = Parser { chars: pattern.chars().collect(), position: 0, depth: 0 };
+        let alternatives = parser.parse_alternatives()?;
+        if parser.position < parser.chars.len() {
+            return Err("unmatched ')'");
+        }
+        let mut compiler = Compiler { program: Vec::new() };
+        compiler.compile_alternatives(&alternatives)?;
+        compiler.push(Inst::Accept)?;
+        Ok(Pattern { program: compiler.program })
+    }
+
+    /// is_match reports whether the pattern matches anywhere in text.
+    pub(crate) fn is_match(&self, text: &str) -> bool {
+        let text: Vec<char> = text.chars().collect();
+        let mut current = Threads::new(self.program.len());
+        let mut next = Threads::new(self.program.len());
+        for position in 0..=text.len() {
+            // A match may start anywhere, so every position starts over at the beginning too.
+            if self.add_thread(&mut current, 0, position, text.len()) {
+                return true;
+            }
+            let Some(&character) = text.get(position) else {
+                break;
+            };
+            for &pc in &current.list {
+                if let Inst::Step(node) = &self.program[pc] {
+                    if matches_char(node, character) && self.add_thread(&mut next, pc + 1, position + 1, text.len()) {
+                        return true;
+                    }
+                }
+            }
+            std::mem::swap(&mut current, &mut next);
+            next.clear();
+        }
+        false
+    }
+
+    /// add_thread adds pc and every instruction reachable from it without consuming a
+    /// character to threads, reporting whether one of them accepts.
+    fn add_thread(&self, threads: &mut Threads, pc: usize, position: usize, len: usize) -> bool {
+        let mut pending = vec![pc];
+        while let Some(pc) = pending.pop() {
+            if threads.seen[pc] {
+                continue;
+            }
+            threads.seen[pc] = true;
+            threads.list.push(pc);
+            match self.program[pc] {
+                Inst::Step(_) => {}
+                Inst::Split(first, second) => pending.extend([second, first]),
+                Inst::Jump(target) => pending.push(target),
+                Inst::Start if position == 0 => pending.push(pc + 1),
+                Inst::End if position == len => pending.push(pc + 1),
+                Inst::Start | Inst::End => {}
+                Inst::Accept => return true,
+            }
+        }
+        false
+    }
+}
+
+/// Threads is the set of instructions the simulation has reached at one position.
+struct Threads {
+    list: Vec<usize>,
+    seen: Vec<bool>,
+}
+
+impl Threads {
+    fn new(len: usize) -> Self {
+        Threads { list: Vec::new(), seen: vec![false; len] }
+    }
+
+    fn clear(&mut self) {
+        for pc in self.list.drain(..) {
+            self.seen[pc] = false;
+        }
+    }
+}
+
+struct Compiler {
+    program: Vec<Inst>,
+}
+
+impl Compiler {
+    fn push(&mut self, inst: Inst) -> Result<usize, &'static str> {
+        if self.program.len() == MAX_PROGRAM_LEN {
+            return Err("pattern too large");
+        }
+        self.program.push(inst);
+        Ok(self.program.len() - 1)
+    }
+
+    /// compile_alternatives tries each alternative in turn, each jumping past the rest once it matched.
+    fn compile_alternatives(&mut self, alternatives: &[Vec<Node>]) -> Result<(), &'static str> {
+        let mut jumps = Vec::new();
+        for (index, alternative) in alternatives.iter().enumerate() {
+            if index + 1 == alternatives.len() {
+                self.compile_sequence(alternative)?;
+                break;
+            }
+            let split = self.push(Inst::Split(0, 0))?;
+            self.compile_sequence(alternative)?;
+            jumps.push(self.push(Inst::Jump(0))?);
+            self.program[split] = Inst::Split(split + 1, self.program.len());
+        }
+        let end = self.program.len();
+        for jump in jumps {
+            self.program[jump] = Inst::Jump(end);
+        }
+        Ok(())
+    }
+
+    fn compile_sequence(&mut self, nodes: &[Node]) -> Result<(), &'static str> {
+        nodes.iter().try_for_each(|node| self.compile_node(node))
+    }
+
+    /// compile_node writes out a repetition as min copies of its node followed by a loop, or
+    /// by max - min optional copies. Greediness only decides which match is found, so it is dropped.
+    /// A node that compiles to nothing, such as `(?:)`, is left out however often it repeats.
+    fn compile_node(&mut self, node: &Node) -> Result<(), &'static str> {
+        match node {
+            Node::Start => self.push(Inst::Start).map(drop),
+            Node::End => self.push(Inst::End).map(drop),
+            Node::Group(alternatives) => self.compile_alternatives(alternatives),
+            Node::Repeat { node, min, max, .. } => {
+                let start = self.program.len();
+                self.compile_node(node)?;
+                if self.program.len() == start {
+                    return Ok(());
+                }
+                self.program.truncate(start);
+                for _ in 0..*min {
+                    self.compile_node(node)?;
+                }
+                match max {
+                    None => {
+                        let split = self.push(Inst::Split(0, 0))?;
+                        self.compile_node(node)?;
+                        self.push(Inst::Jump(split))?;
+                        self.program[split] = Inst::Split(split + 1, self.program.len());
+                    }
+                    Some(max) => {
+                        let mut splits = Vec::new();
+                        for _ in *min..*max {
+                            splits.push(self.push(Inst::Split(0, 0))?);
+                            self.compile_node(node)?;
+                        }
+                        let end = self.program.len();
+                        for split in splits {
+                            self.program[split] = Inst::Split(split + 1, end);
+                        }
+                    }
+                }
+                Ok(())
+            }
+            single => self.push(Inst::Step(single.clone())).map(drop),
+        }
+    }
+}
+
+fn matches_char(node: &Node, character: char) -> bool {
+    match node {
+        Node::Char(expected) => *expected == character,
+        Node::Any => !matches!(character, '\n' | '\r' | '\u{2028}' | '\u{2029}'),
+        Node::Class(items, negated) => items.iter().any(|item| item.contains(character)) != *negated,
+        _ => false,
+    }
+}
+
+impl ClassItem {
+    fn contains(self, character: char) -> bool {
+        match self {
+            ClassItem::Range(low, high) => (low..=high).contains(&character),
+            ClassItem::Digit(negated) => character.is_ascii_digit() != negated,
+            ClassItem::Word(negated) => (character.is_ascii_alphanumeric() || character == '_') != negated,
+            ClassItem::Space(negated) => character.is_whitespace() != negated,
+        }
+    }
+}
+
+struct Parser {
+    chars: Vec<char>,
+    position: usize,
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<char> {
+        self.chars.get(self.position).copied()
+    }
+
+    fn eat(&mut self, expected: char) -> bool {
+        let matched = self.peek() == Some(expected);
+        if matched {
+            self.position += 1;
+        }
+        matched
+    }
+
+    fn parse_alternatives(&mut self) -> Result<Vec<Vec<Node>>, &'static str> {
+        let mut alternatives = vec![self.parse_sequence()?];
+        while self.eat('|') {
+            alternatives.push(self.parse_sequence()?);
+        }
+        Ok(alternatives)
+    }
+
+    fn parse_sequence(&mut self) -> Result<Vec<Node>, &'static str> {
+        let mut nodes = Vec::new();
+        while let Some(character) = self.peek() {
+            if character == '|' || character == ')' {
+                break;
+            }
+            let atom = self.parse_atom()?;
+            nodes.push(self.parse_quantifier(atom)?);
+        }
+        Ok(nodes)
+    }
+
+    fn parse_atom(&mut self) -> Result<Node, &'static str> {
+        let Some(character) = self.peek() else {
+            return Err("unexpected end of pattern");
+        };
+        self.position += 1;
+        match character {
+            '(' => {
+                if self.eat('?') && !self.eat(':') {
+                    return Err("only (?: groups are supported");
+                }
+                if self.depth == MAX_GROUP_DEPTH {
+                    return Err("groups nested too deeply");
+                }
+                self.depth += 1;
+                let alternatives = self.parse_alternatives()?;
+                self.depth -= 1;
+                if !self.eat(')') {
+                    return Err("unclosed group");
+                }
+                Ok(Node::Group(alternatives))
+            }
+            '[' => self.parse_class(),
+            '.' => Ok(Node::Any),
+            '^' => Ok(Node::Start),
+            '$' => Ok(Node::End),
+            '\\' => match self.parse_escape()? {
+                ClassItem::Range(character, _) => Ok(Node::Char(character)),
+                item => Ok(Node::Class(vec![item], false)),
+            },
+            '*' | '+' | '?' => Err("quantifier without anything to repeat"),
+            character => Ok(Node::Char(character)),
+        }
+    }
+
+    /// parse_escape reads what follows a backslash, a single character is returned as a one character range.
+    fn parse_escape(&mut self) -> Result<ClassItem, &'static str> {
+        let Some(character) = self.peek() else {
+            return Err("pattern ends with a backslash");
+        };
+        self.position += 1;
+        let literal = match character {
+            'd' | 'D' => return Ok(ClassItem::Digit(character == 'D')),
+            'w' | 'W' => return Ok(ClassItem::Word(character == 'W')),
+            's' | 'S' => return Ok(ClassItem::Space(character == 'S')),
+            'n' => '\n',
+            'r' => '\r',
+            't' => '\t',
+            'f' => '\u{0C}',
+            'v' => '\u{0B}',
+            '0' => '\0',
+            'u' => {
+                let digits: String = self.chars.iter().skip(self.position).take(4).collect();
+                let code = u32::from_str_radix(&digits, 16).ok().filter(|_| digits.len() == 4);
+                self.position += 4;
+                code.and_then(char::from_u32).ok_or("invalid \\u escape")?
+            }
+            character if character.is_ascii_alphanumeric() => return Err("unsupported escape"),
+            character => character,
+        };
+        Ok(ClassItem::Range(literal, literal))
+    }
+
+    fn parse_class(&mut self) -> Result<Node, &'static str> {
+        let negated = self.eat('^');
+        let mut items = Vec::new();
+        loop {
+            let low = match self.peek() {
+                None => return Err("unclosed character class"),
+                Some(']') => {
+                    self.position += 1;
+                    return Ok(Node::Class(items, negated));
+                }
+                Some('\\') => {
+                    self.position += 1;
+                    self.parse_escape()?
+                }
+                Some(character) => {
+                    self.position += 1;
+                    ClassItem::Range(character, character)
+                }
+            };
+            // A '-' first, last or next to a class escape is a literal.
+            let is_range = self.peek() == Some('-') && !matches!(self.chars.get(self.position + 1), None | Some(']'));
+            match low {
+                ClassItem::Range(low, _) if is_range => {
+                    self.position += 1;
+                    let high = match self.parse_class_char()? {
+                        ClassItem::Range(high, _) if low <= high => high,
+                        _ => return Err("invalid range in character class"),
+                    };
+                    items.push(ClassItem::Range(low, high));
+                }
+                item => items.push(item),
+            }
+        }
+    }
+
+    fn parse_class_char(&mut self) -> Result<ClassItem, &'static str> {
+        match self.peek() {
+            Some('\\') => {
+                self.position += 1;
+                self.parse_escape()
+            }
+            Some(character) => {
+                self.position += 1;
+                Ok(ClassItem::Range(character, character))
+            }
+            None => Err("unclosed character class"),
+        }
+    }
+
+    /// parse_quantifier wraps atom in the repetition that follows it, if any. A '{' that
+    /// does not start a valid quantifier is a literal, as in ECMAScript.
+    fn parse_quantifier(&mut self, atom: Node) -> Result<Node, &'static str> {
+        let (min, max) = match self.peek() {
+            Some('{') => match self.parse_braces() {
+                Some(bounds) => bounds,
+                None => return Ok(atom),
+            },
+            Some(quantifier @ ('*' | '+' | '?')) => {
+                self.position += 1;
+                match quantifier {
+                    '*' => (0, None),
+                    '+' => (1, None),
+                    _ => (0, Some(1)),
+                }
+            }
+            _ => return Ok(atom),
+        };
+        if matches!(atom, Node::Start | Node::End) {
+            return Err("quantifier without anything to repeat");
+        }
+        if max.is_some_and(|max| max < min) {
+            return Err("quantifier range out of order");
+        }
+        let greedy = !self.eat('?');
+        Ok(Node::Repeat { node: Box::new(atom), min, max, greedy })
+    }
+
+    /// parse_braces reads {n}, {n,} or {n,m}, consuming it only when it is well formed.
+    fn parse_braces(&mut self) -> Option<(usize, Option<usize>)> {
+        let closing = self.chars[self.position..].iter().position(|&character| character == '}')?;
+        let inside: String = self.chars[self.position + 1..self.position + closing].iter().collect();
+        let bounds = match inside.split_once(',') {
+            None => inside.parse().ok().map(|count| (count, Some(count))),
+            Some((min, "")) => min.parse().ok().map(|min| (min, None)),
+            Some((min, max)) => min.parse().ok().zip(max.parse().ok()).map(|(min, max)| (min, Some(max))),
+        }?;
+        self.position += closing + 1;
+        Some(bounds)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_match() {
+        let cases = [
+            ("abc", "xabcx", true),
+            ("^abc$", "xabc", false),
+            ("^\\d{3}-\\d{4}$", "555-1234", true),
+            ("^\\d{3}-\\d{4}$", "555-12345", false),
+            ("^[A-Za-z_][\\w-]*$", "snake_case-9", true),
+            ("^[A-Za-z_][\\w-]*$", "9lives", false),
+            ("^[^@\\s]+@[^@\\s]+\\.[a-z]{2,}$", "a.b@example.org", true),
+            ("^[^@\\s]+@[^@\\s]+\\.[a-z]{2,}$", "a b@example.org", false),
+            ("^(?:red|green|blue)$", "green", true),
+            ("^(red|green|blue)$", "greenish", false),
+            ("^(ab)+$", "ababab", true),
+            ("^(ab)+$", "ababa", false),
+            ("^a.c$", "a\u{e9}c", true),
+            ("^a.c$", "a\nc", false),
+            ("^(a*)*b$", "aaaab", true),
+            ("^a{2,3}?$", "aaa", true),
+            ("^.*?x", "abxcx", true),
+            ("^[-+]?\\d+(\\.\\d+)?$", "-12.5", true),
+            ("^[\\u0041-\\u0043]+$", "ABCA", true),
+            ("x{,2}", "x{,2}", true),
+            ("^$", "", true),
+            ("", "anything", true),
+        ];
+        for (pattern, text, expected) in cases {
+            assert_eq!(Pattern::new(pattern).unwrap().is_match(text), expected, "{pattern} on {text:?}");
+        }
+
+        for invalid in ["(a", "a)", "[a-", "[z-a]", "*a", "a{3,1}", "\\", "\\q", "(?=a)"] {
+            assert!(Pattern::new(invalid).is_err(), "{invalid}");
+        }
+    }
+
+    #[test]
+    fn test_matching_takes_linear_time() {
+        let letters = "a".repeat(100_000);
+        let lowercase = Pattern::new("^[a-z]*$").unwrap();
+        assert!(lowercase.is_match(&letters));
+        assert!(!lowercase.is_match(&format!("{letters}1")));
+        assert!(Pattern::new("(a|aa)*c").unwrap().is_match(&format!("{letters}c")));
+
+        let nested = Pattern::new("^(a+)+$").unwrap();
+        assert!(!nested.is_match(&format!("{}b", "a".repeat(24))));
+        assert!(!nested.is_match(&format!("{letters}b")));
+
+        assert_eq!(Pattern::new("(a{1000}){1000}"), Err("pattern too large"));
+        assert!(Pattern::new("^(?:){10000000000}$").unwrap().is_match(""));
+        assert!(Pattern::new("^((?:){100000}){100000}a$").unwrap().is_match("a"));
+        assert_eq!(Pattern::new("(|){10000000000}"), Err("pattern too large"));
+        assert_eq!(Pattern::new(&"(".repeat(100_000)), Err("groups nested too deeply"));
+    }
+}
@@ -0,0 +1,222 @@
+use std::fmt::Write;
+
+use crate::value::{Number, Value};
+
+/// Printer writes a Value out as JSON text, compactly, indented or in canonical form.
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Printer {
+    indent: Option<String>,
+    canonical: bool,
+}
+
+impl Printer {
+    /// compact writes no whitespace at all, object members come in map order.
+    pub fn compact() -> Self {
+        Printer::default()
+    }
+
+    /// pretty puts every array element and object member on its own line, indented by two spaces.
+    pub fn pretty() -> Self {
+        Printer { indent: Some("  ".to_string()), canonical: false }
+    }
+
+    /// canonical writes the RFC 8785 form meant for hashing and signing: no whitespace,
+    /// members sorted by the UTF-16 code units of their keys and numbers written as ECMAScript does.
+    /// Integers beyond 2^53 lose precision there, as they would in any JSON number read as a double.
+    pub fn canonical() -> Self {
+        Printer { indent: None, canonical: true }
+    }
+
+    /// indent sets what each level of nesting is indented by, such as "\t", and turns on
+    /// one line per element.
+    #[must_use]
+    pub fn indent(mut self, indent: &str) -> Self {
+        self.indent = Some(indent.to_string());
+        self
+    }
+
+    pub fn print(&self, value: &Value) -> String {
+        let mut output = String::new();
+        self.write_value(value, 0, &mut output);
+        output
+    }
+
+    fn write_value(&self, value: &Value, depth: usize, output: &mut String) {
+        match value {
+            Value::String(string) => write_string(string, output),
+            Value::Number(number) if self.canonical => write_canonical_number(*number, output),
+            Value::Number(Number::I64(integer)) => {
+                let _ = write!(output, "{integer}");
+            }
+            // Debug keeps a fraction or exponent on every float, so it reads back as a float.
+            Value::Number(Number::F64(float)) if float.is_finite() => {
+                let _ = write!(output, "{float:?}");
+            }
+            // JSON has no representation for NaN and the infinities.
+            Value::Number(Number::F64(_)) => output.push_str("null"),
+            Value::Boolean(boolean) => {
+                let _ = write!(output, "{boolean}");
+            }
+            Value::Null => output.push_str("null"),
+            Value::Array(values) => {
+                output.push('[');
+                for (position, value) in values.iter().enumerate() {
+                    self.write_separator(position, depth + 1, output);
+                    self.write_value(value, depth + 1, output);
+                }
+                self.write_close(values.is_empty(), depth, ']', output);
+            }
+            Value::Object(members) => {
+                let mut members: Vec<(&String, &Value)> = members.iter().collect();
+                if self.canonical {
+                    members.sort_by(|(a, _), (b, _)| a.encode_utf16().cmp(b.encode_utf16()));
+                }
+                output.push('{');
+                for (position, (key, value)) in members.iter().enumerate() {
+                    self.write_separator(position, depth + 1, output);
+                    write_string(key, output);
+                    output.push(':');
+                    if self.indent.is_some() {
+                        output.push(' ');
+                    }
+                    self.write_value(value, depth + 1, output);
+                }
+                self.write_close(members.is_empty(), depth, '}', output);
+            }
+        }
+    }
+
+    /// write_separator goes before the element at position, a comma after the first and a new line when indenting.
+    fn write_separator(&self, position: usize, depth: usize, output: &mut String) {
+        if position > 0 {
+            output.push(',');
+        }
+        self.write_newline(depth, output);
+    }
+
+    fn write_close(&self, empty: bool, depth: usize, close: char, output: &mut String) {
+        if !empty {
+            self.write_newline(depth, output);
+        }
+        output.push(close);
+    }
+
+    fn write_newline(&self, depth: usize, output: &mut String) {
+        if let Some(indent) = &self.indent {
+            output.push('\n');
+            output.push_str(&indent.repeat(depth));
+        }
+    }
+}
+
+/// write_string quotes a string, escaping the quote, the backslash and the control characters.
+fn write_string(string: &str, output: &mut String) {
+    output.push('"');
+    for character in string.chars() {
+        match character {
+            '"' => output.push_str("\\\""),
+            '\\' => output.push_str("\\\\"),
+            '\n' => output.push_str("\\n"),
+            '\r' => output.push_str("\\r"),
+            '\t' => output.push_str("\\t"),
+            '\u{08}' => output.push_str("\\b"),
+            '\u{0C}' => output.push_str("\\f"),
+            '\u{00}'..='\u{1F}' => {
+                let _ = write!(output, "\\u{:04x}", u32::from(character));
+            }
+            character => output.push(character),
+        }
+    }
+    output.push('"');
+}
+
+/// write_canonical_number writes a number the way ECMAScript's Number.prototype.toString does.
+fn write_canonical_number(number: Number, output: &mut String) {
+    let float = match number {
+        // Integers a double holds exactly are written as they are.
+        Number::I64(integer) if integer.unsigned_abs() <= 1 << 53 => {
+            let _ = write!(output, "{integer}");
+            return;
+        }
+        Number::I64(integer) => integer as f64,
+        Number::F64(float) => float,
+    };
+    if !float.is_finite() {
+        output.push_str("null");
+        return;
+    }
+    if float == 0.0 {
+        output.push('0');
+        return;
+    }
+    if float < 0.0 {
+        output.push('-');
+    }
+    // The shortest digits that read back as the same double, and the decimal exponent n
+    // placing the point so the value is 0.digits * 10^n.
+    let scientific = format!("{:e}", float.abs());
+    let (mantissa, exponent) = scientific.split_once('e').unwrap_or((&scientific, "0"));
+    let digits = mantissa.replace('.', "");
+    let n = exponent.parse::<i32>().unwrap_or(0) + 1;
+    let k = digits.len() as i32;
+    if k <= n && n <= 21 {
+        output.push_str(&digits);
+        output.push_str(&"0".repeat((n - k) as usize));
+    } else if 0 < n && n <= 21 {
+        let (whole, fraction) = digits.split_at(n as usize);
+        let _ = write!(output, "{whole}.{fraction}");
+    } else if -6 < n && n <= 0 {
+        let _ = write!(output, "0.{}{digits}", "0".repeat(-n as usize));
+    } else {
+        let (first, rest) = digits.split_at(1);
+        output.push_str(first);
+        if !rest.is_empty() {
+            let _ = write!(output, ".{rest}");
+        }
+        let _ = write!(output, "e{}{}", if n > 0 { "+" } else { "-" }, (n - 1).abs());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{map::Map, parser::JsonParser};
+
+    #[test]
+    fn test_pretty_and_compact() {
+        let value = Value::Array(vec![
+            Value::Object(Map::from([("a".to_string(), Value::Array(vec![Value::Number(Number::I64(1)), Value::Null]))])),
+            Value::Array(vec![]),
+            Value::Object(Map::new()),
+            Value::String("x\ny".to_string()),
+        ]);
+        assert_eq!(Printer::compact().print(&value), "[{\"a\":[1,null]},[],{},\"x\\ny\"]");
+        assert_eq!(Printer::pretty().print(&value), "[\n  {\n    \"a\": [\n      1,\n      null\n    ]\n  },\n  [],\n  {},\n  \"x\\ny\"\n]");
+        assert_eq!(Printer::pretty().indent("\t").print(&Value::Array(vec![Value::Boolean(true)])), "[\n\ttrue\n]");
+        assert_eq!(Printer::pretty().print(&Value::Null), "null");
+    }
+
+    #[test]
+    fn test_canonical() {
+        let input = "{\"\u{20ac}\": 1, \"\u{1F600}\": 2, \"\\r\": 3, \"1\": 4, \"\u{80}\": 5, \"\u{f6}\": 6, \"b\": [1.0, -0.0, 1e21, 1e20, 1e-7, 0.000001, 123.456e-10, 4.5e+300, {\"z\": null, \"y\": \"\\u001f\"}]}";
+        let value = JsonParser::parse_from_bytes(input.as_bytes()).unwrap();
+        assert_eq!(
+            Printer::canonical().print(&value),
+            "{\"\\r\":3,\"1\":4,\"b\":[1,0,1e+21,100000000000000000000,1e-7,0.000001,1.23456e-8,4.5e+300,{\"y\":\"\\u001f\",\"z\":null}],\"\u{80}\":5,\"\u{f6}\":6,\"\u{20ac}\":1,\"\u{1F600}\":2}"
+        );
+
+        let numbers = [
+            (Number::F64(333333333.3333333), "333333333.3333333"),
+            (Number::F64(1e23), "1e+23"),
+            (Number::F64(-5e-324), "-5e-324"),
+            (Number::F64(1.7976931348623157e308), "1.7976931348623157e+308"),
+            (Number::F64(0.1), "0.1"),
+            (Number::I64(-9007199254740992), "-9007199254740992"),
+            (Number::I64(9007199254740993), "9007199254740992"),
+            (Number::I64(i64::MAX), "9223372036854776000"),
+        ];
+        for (number, expected) in numbers {
+            assert_eq!(Printer::canonical().print(&Value::Number(number)), expected);
+        }
+    }
+}
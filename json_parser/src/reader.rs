@@ -1,59 +1,71 @@
-use std::{
-    collections::VecDeque,
-    io::{BufReader, Cursor, Read, Seek}, str::from_utf8,
-};
+use std::io::{BufRead, BufReader, ErrorKind as IoErrorKind, Read};
 
+use crate::error::{ErrorKind, JsonError, Position};
+
+/// JsonReader reads JSON text a byte at a time from any reader, keeping track of the
+/// position for error reporting.
 pub struct JsonReader<T>
 where
-    T: Read + Seek,
+    T: Read,
 {
     reader: BufReader<T>,
-    character_buffer: VecDeque<char>,
+    position: Position,
 }
 
 impl<T> JsonReader<T>
 where
-    T: Read + Seek,
+    T: Read,
 {
-    pub fn new(reader: BufReader<T>) -> Self {
-        JsonReader { reader, character_buffer: VecDeque::with_capacity(4) }
+    pub fn new(reader: T) -> Self {
+        JsonReader { reader: BufReader::new(reader), position: Position::default() }
     }
+
     #[must_use]
-    pub fn from_bytes(bytes: &[u8]) -> JsonReader<Cursor<&[u8]>> {
-        JsonReader {
-            reader: BufReader::new(Cursor::new(bytes)),
-            character_buffer: VecDeque::with_capacity(4),
-        }
+    pub fn from_bytes(bytes: &[u8]) -> JsonReader<&[u8]> {
+        JsonReader::new(bytes)
     }
-}
 
-impl <T> Iterator for JsonReader<T> where T: Read + Seek {
-    
-    type Item =  char;
+    /// position is where the next byte will be read from.
+    pub fn position(&self) -> Position {
+        self.position
+    }
 
-    #[allow(clippy::cast_possible_wrap)]
-    fn next(&mut self) -> Option<Self::Item> {
-        if !self.character_buffer.is_empty() {
-            return self.character_buffer.pop_front();
+    pub fn peek_byte(&mut self) -> Result<Option<u8>, JsonError> {
+        loop {
+            match self.reader.fill_buf() {
+                Ok(buffer) => return Ok(buffer.first().copied()),
+                Err(error) if error.kind() == IoErrorKind::Interrupted => continue,
+                Err(error) => return Err(self.error(ErrorKind::Io(error.kind()))),
+            }
         }
+    }
 
-        let mut utf8_buffer = [0,0,0,0];
-        let _ = self.reader.read(&mut utf8_buffer);
-        match from_utf8(&utf8_buffer) {
-            Ok(string) => {
-                self.character_buffer = string.chars().collect();
-                self.character_buffer.pop_front()
-            }
-            Err(error ) => {
-                let valid_bytes = error.valid_up_to();
-                let string = from_utf8(&utf8_buffer[..valid_bytes]).unwrap();
-                let remaining_bytes = 4 - valid_bytes;
-
-                let _ = self.reader.seek_relative(-(remaining_bytes as i64));
-                self.character_buffer = string.chars().collect();
-                self.character_buffer.pop_back()   
+    pub fn next_byte(&mut self) -> Result<Option<u8>, JsonError> {
+        let byte = self.peek_byte()?;
+        if let Some(byte) = byte {
+            self.reader.consume(1);
+            self.position.offset += 1;
+            if byte == b'\n' {
+                self.position.line += 1;
+                self.position.column = 1;
+            } else if byte & 0xC0 != 0x80 {
+                // Continuation bytes belong to the character already counted.
+                self.position.column += 1;
             }
         }
-    
+        Ok(byte)
+    }
+
+    /// skip_whitespace consumes whitespace and peeks at the byte after it.
+    pub fn skip_whitespace(&mut self) -> Result<Option<u8>, JsonError> {
+        while let Some(b' ' | b'\t' | b'\n' | b'\r') = self.peek_byte()? {
+            self.next_byte()?;
+        }
+        self.peek_byte()
+    }
+
+    /// error reports kind at the current position.
+    pub fn error(&self, kind: ErrorKind) -> JsonError {
+        JsonError { kind, position: self.position }
     }
 }
@@ -0,0 +1,420 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fmt,
+};
+
+use crate::{
+    map::Map,
+    pattern::Pattern,
+    value::{Number, Value},
+};
+
+const TYPES: [&str; 7] = ["null", "boolean", "object", "array", "number", "string", "integer"];
+
+/// Schema validates values against a JSON Schema using the keywords
+/// type, enum, minimum, maximum, exclusiveMinimum, exclusiveMaximum, minLength, maxLength,
+/// pattern, items, minItems, maxItems, properties, required, additionalProperties and $ref
+/// to a JSON Pointer within the same document, such as "#/$defs/address".
+/// As in draft 2020-12 the keywords next to a $ref apply as well, other keywords are ignored.
+#[derive(Debug, Clone)]
+pub struct Schema {
+    root: Value,
+    patterns: HashMap<String, Pattern>,
+}
+
+/// SchemaError is a schema that cannot be used, path is a JSON Pointer into the schema.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SchemaError {
+    pub path: String,
+    pub problem: String,
+}
+
+/// Violation is a part of a value the schema rejects, path is a JSON Pointer into the value.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Violation {
+    pub path: String,
+    pub keyword: &'static str,
+    pub problem: String,
+}
+
+impl Schema {
+    /// new checks every keyword the schema uses and compiles its patterns.
+    pub fn new(schema: Value) -> Result<Schema, SchemaError> {
+        let mut checker = Checker { root: &schema, patterns: HashMap::new(), checked: HashSet::new() };
+        checker.check(String::new())?;
+        let patterns = checker.patterns;
+        Ok(Schema { root: schema, patterns })
+    }
+
+    /// validate returns every violation in value, an empty list when it is valid.
+    pub fn validate(&self, value: &Value) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        self.validate_at(&self.root, value, "", &mut violations);
+        violations
+    }
+
+    pub fn is_valid(&self, value: &Value) -> bool {
+        self.validate(value).is_empty()
+    }
+
+    fn validate_at(&self, schema: &Value, value: &Value, path: &str, violations: &mut Vec<Violation>) {
+        let violation = |keyword, problem| Violation { path: path.to_string(), keyword, problem };
+        let keywords = match schema {
+            Value::Boolean(true) => return,
+            Value::Object(keywords) => keywords,
+            _ => return violations.push(violation("false", "no value is allowed here".to_string())),
+        };
+        let keyword = |name: &str| keywords.get(name);
+
+        if let Some(types) = keyword("type") {
+            let allowed: Vec<&str> = match types {
+                Value::Array(types) => types.iter().filter_map(as_str).collect(),
+                types => as_str(types).into_iter().collect(),
+            };
+            if !allowed.iter().any(|&allowed| has_type(value, allowed)) {
+                violations.push(violation("type", format!("expected {}, found {}", allowed.join(" or "), type_name(value))));
+            }
+        }
+        if let Some(Value::Array(options)) = keyword("enum") {
+            if !options.iter().any(|option| equal(option, value)) {
+                violations.push(violation("enum", format!("{} is not one of the allowed values", value.to_json_string())));
+            }
+        }
+
+        match value {
+            Value::Number(number) => {
+                let number = as_f64(*number);
+                let bounds = [("minimum", "less than"), ("maximum", "greater than"), ("exclusiveMinimum", "at most"), ("exclusiveMaximum", "at least")];
+                for (name, relation) in bounds {
+                    let Some(Value::Number(bound)) = keyword(name) else {
+                        continue;
+                    };
+                    let bound = as_f64(*bound);
+                    let allowed = match name {
+                        "minimum" => number >= bound,
+                        "maximum" => number <= bound,
+                        "exclusiveMinimum" => number > bound,
+                        _ => number < bound,
+                    };
+                    if !allowed {
+                        violations.push(violation(name, format!("{number} is {relation} {bound}")));
+                    }
+                }
+            }
+            Value::String(string) => {
+                let length = string.chars().count();
+                violations.extend(check_count(keywords, ("minLength", "maxLength", "characters"), length).map(|(keyword, problem)| violation(keyword, problem)));
+                if let Some(Value::String(pattern)) = keyword("pattern") {
+                    if !self.patterns.get(pattern).is_some_and(|compiled| compiled.is_match(string)) {
+                        violations.push(violation("pattern", format!("\"{string}\" does not match \"{pattern}\"")));
+                    }
+                }
+            }
+            Value::Array(values) => {
+                violations.extend(check_count(keywords, ("minItems", "maxItems", "items"), values.len()).map(|(keyword, problem)| violation(keyword, problem)));
+                if let Some(items) = keyword("items") {
+                    for (index, item) in values.iter().enumerate() {
+                        self.validate_at(items, item, &format!("{path}/{index}"), violations);
+                    }
+                }
+            }
+            Value::Object(members) => {
+                if let Some(Value::Array(required)) = keyword("required") {
+                    for name in required.iter().filter_map(as_str).filter(|&name| !members.contains_key(name)) {
+                        violations.push(violation("required", format!("missing required property \"{name}\"")));
+                    }
+                }
+                let properties = match keyword("properties") {
+                    Some(Value::Object(properties)) => Some(properties),
+                    _ => None,
+                };
+                for (name, member) in members {
+                    let member_path = format!("{path}/{}", escape_token(name));
+                    match (properties.and_then(|properties| properties.get(name)), keyword("additionalProperties")) {
+                        (Some(property), _) => self.validate_at(property, member, &member_path, violations),
+                        (None, Some(Value::Boolean(false))) => violations.push(Violation {
+                            path: member_path,
+                            keyword: "additionalProperties",
+                            problem: format!("property \"{name}\" is not allowed"),
+                        }),
+                        (None, Some(additional)) => self.validate_at(additional, member, &member_path, violations),
+                        (None, None) => {}
+                    }
+                }
+            }
+            _ => {}
+        }
+
+        if let Some(target) = keyword("$ref").and_then(|reference| resolve(&self.root, reference)) {
+            self.validate_at(target, value, path, violations);
+        }
+    }
+}
+
+/// check_count returns the keyword and problem when a length is outside the bounds the
+/// minimum and maximum keywords named set.
+fn check_count(
+    keywords: &Map,
+    (min, max, unit): (&'static str, &'static str, &str),
+    count: usize,
+) -> Option<(&'static str, String)> {
+    if let Some(bound) = keywords.get(min).and_then(as_count).filter(|&bound| count < bound) {
+        return Some((min, format!("has {count} {unit}, fewer than {bound}")));
+    }
+    let bound = keywords.get(max).and_then(as_count).filter(|&bound| count > bound)?;
+    Some((max, format!("has {count} {unit}, more than {bound}")))
+}
+
+/// Checker walks everything reachable from the root schema, including through $ref, once.
+struct Checker<'a> {
+    root: &'a Value,
+    patterns: HashMap<String, Pattern>,
+    checked: HashSet<String>,
+}
+
+impl Checker<'_> {
+    fn check(&mut self, path: String) -> Result<(), SchemaError> {
+        if !self.checked.insert(path.clone()) {
+            return Ok(());
+        }
+        let error = |keyword: &str, problem: &str| SchemaError {
+            path: format!("{path}/{}", escape_token(keyword)),
+            problem: problem.to_string(),
+        };
+        let keywords = match self.root.pointer(&path) {
+            Some(Value::Boolean(_)) => return Ok(()),
+            Some(Value::Object(keywords)) => keywords,
+            _ => return Err(SchemaError { path, problem: "a schema must be an object or a boolean".to_string() }),
+        };
+        let mut subschemas = Vec::new();
+        for (keyword, value) in keywords {
+            let valid = match (keyword.as_str(), value) {
+                ("type", Value::Array(types)) => types.iter().all(|name| as_str(name).is_some_and(|name| TYPES.contains(&name))),
+                ("type", name) => as_str(name).is_some_and(|name| TYPES.contains(&name)),
+                ("enum", value) => matches!(value, Value::Array(_)),
+                ("minimum" | "maximum" | "exclusiveMinimum" | "exclusiveMaximum", value) => matches!(value, Value::Number(_)),
+                ("minLength" | "maxLength" | "minItems" | "maxItems", count) => as_count(count).is_some(),
+                ("required", Value::Array(names)) => names.iter().all(|name| as_str(name).is_some()),
+                ("required", _) => false,
+                ("pattern", Value::String(pattern)) => {
+                    let compiled = Pattern::new(pattern).map_err(|problem| error(keyword, problem))?;
+                    self.patterns.insert(pattern.clone(), compiled);
+                    true
+                }
+                ("pattern", _) => false,
+                ("items" | "additionalProperties", _) => {
+                    subschemas.push(format!("{path}/{keyword}"));
+                    true
+                }
+                ("properties", Value::Object(properties)) => {
+                    subschemas.extend(properties.keys().map(|name| format!("{path}/properties/{}", escape_token(name))));
+                    true
+                }
+                ("properties", _) => false,
+                ("$ref", reference) => {
+                    let target = as_str(reference)
+                        .and_then(|reference| reference.strip_prefix('#'))
+                        .filter(|target| self.root.pointer(target).is_some())
+                        .ok_or_else(|| error(keyword, "$ref must point into this document"))?;
+                    self.check_ref_cycle(&path)?;
+                    subschemas.push(target.to_string());
+                    true
+                }
+                _ => true,
+            };
+            if !valid {
+                return Err(error(keyword, "invalid value for this keyword"));
+            }
+        }
+        subschemas.into_iter().try_for_each(|subschema| self.check(subschema))
+    }
+
+    /// check_ref_cycle rejects a chain of $ref leading back to itself, which would
+    /// validate forever without ever moving into the value.
+    fn check_ref_cycle(&self, start: &str) -> Result<(), SchemaError> {
+        let mut seen = HashSet::from([start.to_string()]);
+        let mut path = start.to_string();
+        while let Some(target) = self.root.pointer(&path).and_then(|schema| schema.pointer("/$ref")).and_then(as_str) {
+            path = target.trim_start_matches('#').to_string();
+            if !seen.insert(path.clone()) {
+                return Err(SchemaError { path: format!("{start}/$ref"), problem: "$ref refers back to itself".to_string() });
+            }
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "invalid schema at \"{}\": {}", self.path, self.problem)
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "\"{}\" fails {}: {}", self.path, self.keyword, self.problem)
+    }
+}
+
+fn resolve<'a>(root: &'a Value, reference: &Value) -> Option<&'a Value> {
+    root.pointer(as_str(reference)?.strip_prefix('#')?)
+}
+
+fn escape_token(token: &str) -> String {
+    token.replace('~', "~0").replace('/', "~1")
+}
+
+fn as_str(value: &Value) -> Option<&str> {
+    match value {
+        Value::String(string) => Some(string),
+        _ => None,
+    }
+}
+
+fn as_f64(number: Number) -> f64 {
+    match number {
+        Number::I64(number) => number as f64,
+        Number::F64(number) => number,
+    }
+}
+
+/// as_count reads a non-negative integer, which may be written as a float such as 2.0.
+fn as_count(value: &Value) -> Option<usize> {
+    match value {
+        Value::Number(Number::I64(count)) => usize::try_from(*count).ok(),
+        Value::Number(Number::F64(count)) if count.fract() == 0.0 && *count >= 0.0 => Some(*count as usize),
+        _ => None,
+    }
+}
+
+fn has_type(value: &Value, name: &str) -> bool {
+    match (name, value) {
+        ("integer", Value::Number(number)) => as_f64(*number).fract() == 0.0,
+        (name, value) => name == type_name(value) || (name == "number" && type_name(value) == "integer"),
+    }
+}
+
+fn type_name(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Boolean(_) => "boolean",
+        Value::Object(_) => "object",
+        Value::Array(_) => "array",
+        Value::String(_) => "string",
+        Value::Number(Number::I64(_)) => "integer",
+        Value::Number(Number::F64(_)) => "number",
+    }
+}
+
+/// equal compares values the way JSON Schema does, where 1 and 1.0 are the same number.
+fn equal(a: &Value, b: &Value) -> bool {
+    match (a, b) {
+        (Value::Number(a), Value::Number(b)) => as_f64(*a) == as_f64(*b),
+        (Value::Array(a), Value::Array(b)) => a.len() == b.len() && a.iter().zip(b).all(|(a, b)| equal(a, b)),
+        (Value::Object(a), Value::Object(b)) => {
+            a.len() == b.len() && a.iter().all(|(key, a)| b.get(key).is_some_and(|b| equal(a, b)))
+        }
+        (a, b) => a == b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::JsonParser;
+
+    fn parse(json: &str) -> Value {
+        JsonParser::parse_from_bytes(json.as_bytes()).unwrap()
+    }
+
+    #[test]
+    fn test_validate_reports_every_violation() {
+        let schema = Schema::new(parse(
+            r##"{
+                "type": "object",
+                "required": ["id", "name", "tags"],
+                "properties": {
+                    "id": {"type": "integer", "minimum": 1},
+                    "name": {"type": "string", "minLength": 2, "maxLength": 8, "pattern": "^[A-Z]"},
+                    "ratio": {"type": "number", "exclusiveMinimum": 0, "maximum": 1},
+                    "kind": {"enum": ["a", "b", 3]},
+                    "tags": {"type": "array", "maxItems": 2, "items": {"type": "string"}},
+                    "home": {"$ref": "#/$defs/address"},
+                    "a/b": {"type": ["string", "null"]}
+                },
+                "additionalProperties": false,
+                "$defs": {
+                    "address": {"type": "object", "required": ["city"], "properties": {"city": {"type": "string"}}}
+                }
+            }"##,
+        ))
+        .unwrap();
+
+        let valid = parse(r#"{"id": 7, "name": "Ada", "ratio": 1, "kind": 3.0, "tags": ["x"], "home": {"city": "Paris"}, "a/b": null}"#);
+        assert_eq!(schema.validate(&valid), []);
+        assert!(schema.is_valid(&parse(r#"{"id": 1.0, "name": "Bo", "tags": []}"#)));
+
+        let invalid = parse(
+            r#"{"id": 0, "name": "a very long name", "ratio": 0, "kind": "c", "tags": ["x", 2, "z"], "home": {"city": 1}, "a/b": 5, "extra": true}"#,
+        );
+        let mut found: Vec<(String, &str)> = schema.validate(&invalid).into_iter().map(|violation| (violation.path, violation.keyword)).collect();
+        found.sort();
+        let expected = [
+            ("/a~1b", "type"),
+            ("/extra", "additionalProperties"),
+            ("/home/city", "type"),
+            ("/id", "minimum"),
+            ("/kind", "enum"),
+            ("/name", "maxLength"),
+            ("/name", "pattern"),
+            ("/ratio", "exclusiveMinimum"),
+            ("/tags", "maxItems"),
+            ("/tags/1", "type"),
+        ];
+        assert_eq!(found, expected.map(|(path, keyword)| (path.to_string(), keyword)));
+
+        let missing = schema.validate(&parse("{\"home\": {}}"));
+        let problems: Vec<String> = missing.iter().map(ToString::to_string).collect();
+        assert_eq!(
+            problems,
+            [
+                "\"\" fails required: missing required property \"id\"",
+                "\"\" fails required: missing required property \"name\"",
+                "\"\" fails required: missing required property \"tags\"",
+                "\"/home\" fails required: missing required property \"city\"",
+            ]
+        );
+        assert_eq!(schema.validate(&parse("[]"))[0].problem, "expected object, found array");
+    }
+
+    #[test]
+    fn test_recursive_and_boolean_schemas() {
+        let tree = Schema::new(parse(r##"{"type": "object", "properties": {"children": {"items": {"$ref": "#"}}, "leaf": false}}"##)).unwrap();
+        assert!(tree.is_valid(&parse(r#"{"children": [{"children": []}, {}]}"#)));
+        let violations = tree.validate(&parse(r#"{"children": [{"children": [3]}], "leaf": 1}"#));
+        let mut paths: Vec<&str> = violations.iter().map(|violation| violation.path.as_str()).collect();
+        paths.sort();
+        assert_eq!(paths, ["/children/0/children/0", "/leaf"]);
+        assert!(Schema::new(Value::Boolean(true)).unwrap().is_valid(&Value::Null));
+    }
+
+    #[test]
+    fn test_new_rejects_unusable_schemas() {
+        let cases = [
+            (r#"{"type": "text"}"#, "/type"),
+            (r#"{"properties": {"a": {"pattern": "(unclosed"}}}"#, "/properties/a/pattern"),
+            (r##"{"items": {"$ref": "#/$defs/missing"}}"##, "/items/$ref"),
+            (r#"{"$ref": "other.json#/a"}"#, "/$ref"),
+            (r##"{"$defs": {"a": {"$ref": "#/$defs/b"}, "b": {"$ref": "#/$defs/a"}}, "$ref": "#/$defs/a"}"##, "/$ref"),
+            (r#"{"required": [1]}"#, "/required"),
+            (r#"{"minLength": -1}"#, "/minLength"),
+            (r#"{"properties": {"a": 3}}"#, "/properties/a"),
+            ("[]", ""),
+        ];
+        for (schema, path) in cases {
+            assert_eq!(Schema::new(parse(schema)).unwrap_err().path, path, "{schema}");
+        }
+    }
+}
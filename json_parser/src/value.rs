@@ -1,4 +1,8 @@
-use std::collections::HashMap;
+use crate::{
+    map::Map,
+    path::{JsonPath, PathError},
+    printer::Printer,
+};
 
 
 #[derive(Debug, Copy, Clone, PartialEq)]
@@ -13,38 +17,63 @@ pub enum Value {
     Number(Number),
     Boolean(bool),
     Array(Vec<Value>),
-    Object(HashMap<String, Value>),
+    Object(Map),
     Null
 }
 
 impl Value {
+    /// to_json_string writes the value compactly, see Printer for indented and canonical output.
     pub fn to_json_string(&self) -> String {
-        match self {
-            Value::String(s) => format!("\"{}\"", s), // JSON strings are wrapped in quotes
-            Value::Number(Number::I64(i)) => i.to_string(),
-            Value::Number(Number::F64(f)) => f.to_string(),
-            Value::Boolean(b) => b.to_string(),
-            Value::Array(arr) => {
-                let elements: Vec<String> = arr.iter().map(|v| v.to_json_string()).collect();
-                format!("[{}]", elements.join(","))
-            }
-            Value::Object(obj) => {
-                let members: Vec<String> = obj
-                    .iter()
-                    .map(|(k, v)| format!("\"{}\":{}", k, v.to_json_string()))
-                    .collect();
-                format!("{{{}}}", members.join(","))
-            }
-            Value::Null => "null".to_string(),
-        }
+        Printer::compact().print(self)
     }
 
     pub fn to_json_bytes(&self) -> Vec<u8> {
         self.to_json_string().into_bytes()
     }
+
+    /// pointer looks up the value an RFC 6901 JSON Pointer such as "/a/0/b" refers to,
+    /// the empty pointer refers to the whole value.
+    pub fn pointer(&self, pointer: &str) -> Option<&Value> {
+        if pointer.is_empty() {
+            return Some(self);
+        }
+        pointer.strip_prefix('/')?.split('/').map(unescape_token).try_fold(self, |value, token| match value {
+            Value::Object(members) => members.get(&token),
+            Value::Array(values) => values.get(array_index(&token)?),
+            _ => None,
+        })
+    }
+
+    /// pointer_mut is pointer returning a mutable reference.
+    pub fn pointer_mut(&mut self, pointer: &str) -> Option<&mut Value> {
+        if pointer.is_empty() {
+            return Some(self);
+        }
+        pointer.strip_prefix('/')?.split('/').map(unescape_token).try_fold(self, |value, token| match value {
+            Value::Object(members) => members.get_mut(&token),
+            Value::Array(values) => values.get_mut(array_index(&token)?),
+            _ => None,
+        })
+    }
+
+    /// query returns the values a JSONPath expression such as "$.store..price" selects.
+    pub fn query(&self, path: &str) -> Result<Vec<&Value>, PathError> {
+        Ok(JsonPath::parse(path)?.query(self))
+    }
 }
 
+/// unescape_token decodes ~1 and ~0 in a JSON Pointer reference token, in that order.
+fn unescape_token(token: &str) -> String {
+    token.replace("~1", "/").replace("~0", "~")
+}
 
+/// array_index parses a reference token as an array index, which has no sign or leading zeros.
+fn array_index(token: &str) -> Option<usize> {
+    if token.is_empty() || !token.bytes().all(|byte| byte.is_ascii_digit()) || (token.len() > 1 && token.starts_with('0')) {
+        return None;
+    }
+    token.parse().ok()
+}
 
 impl TryFrom<&Value> for String {
     type Error = ();
@@ -109,7 +138,7 @@ impl<'a> TryFrom<&'a Value> for &'a Vec<Value> {
     }
 }
 
-impl<'a> TryFrom<&'a Value> for &'a HashMap<String, Value> {
+impl<'a> TryFrom<&'a Value> for &'a Map {
     type Error = ();
 
     fn try_from(value: &'a Value) -> Result<Self, Self::Error> {
@@ -120,3 +149,57 @@ impl<'a> TryFrom<&'a Value> for &'a HashMap<String, Value> {
     }
 }
 
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::JsonParser;
+
+    #[test]
+    fn test_to_json_string_round_trips() {
+        let string = Value::String("quote \" backslash \\ slash / \n\r\t\u{08}\u{0C}\u{00}\u{1F} é 𝄞".to_string());
+        assert_eq!(
+            string.to_json_string(),
+            "\"quote \\\" backslash \\\\ slash / \\n\\r\\t\\b\\f\\u0000\\u001f é 𝄞\""
+        );
+        let floats = Value::Array(vec![
+            Value::Number(Number::F64(1.0)),
+            Value::Number(Number::F64(-0.5)),
+            Value::Number(Number::F64(1e300)),
+            Value::Number(Number::F64(0.1 + 0.2)),
+            Value::Number(Number::I64(i64::MIN)),
+        ]);
+        assert_eq!(floats.to_json_string(), "[1.0,-0.5,1e300,0.30000000000000004,-9223372036854775808]");
+        let object = Value::Object(Map::from([("k\"\n".to_string(), Value::Array(vec![string, floats]))]));
+        for value in [&object, &Value::Number(Number::F64(f64::MIN_POSITIVE))] {
+            assert_eq!(&JsonParser::parse_from_bytes(&value.to_json_bytes()).unwrap(), value);
+        }
+        assert_eq!(Value::Number(Number::F64(f64::NAN)).to_json_string(), "null");
+    }
+
+    #[test]
+    fn test_pointer() {
+        let mut value = JsonParser::parse_from_bytes(br#"{"a": [{"b": 1}, 2], "c/d": {"e~f": true}, "": 3, "10": 4}"#).unwrap();
+        let cases: [(&str, Option<Value>); 12] = [
+            ("", Some(value.clone())),
+            ("/a/0/b", Some(Value::Number(Number::I64(1)))),
+            ("/a/1", Some(Value::Number(Number::I64(2)))),
+            ("/c~1d/e~0f", Some(Value::Boolean(true))),
+            ("/", Some(Value::Number(Number::I64(3)))),
+            ("/10", Some(Value::Number(Number::I64(4)))),
+            ("/a/2", None),
+            ("/a/-", None),
+            ("/a/01", None),
+            ("/a/+1", None),
+            ("/a/0/b/c", None),
+            ("a", None),
+        ];
+        for (pointer, expected) in cases {
+            assert_eq!(value.pointer(pointer).cloned(), expected, "{pointer}");
+        }
+
+        *value.pointer_mut("/a/0/b").unwrap() = Value::Null;
+        assert_eq!(value.pointer("/a/0/b"), Some(&Value::Null));
+        assert!(value.pointer_mut("/x").is_none());
+    }
+}
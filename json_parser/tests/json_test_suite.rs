@@ -0,0 +1,45 @@
+use std::{fs, path::Path};
+
+use sv2_serde_json::parser::JsonParser;
+
+/// The implementation defined cases this parser accepts, every other one is rejected.
+/// Numbers are accepted as long as they round to a finite float, strings only when they
+/// are valid UTF-8 without unpaired surrogates.
+const ACCEPTED_IMPLEMENTATION_DEFINED: &[&str] = &[
+    "i_number_double_huge_neg_exp.json",
+    "i_number_real_underflow.json",
+    "i_number_too_big_neg_int.json",
+    "i_number_too_big_pos_int.json",
+    "i_number_very_big_negative_int.json",
+    "i_structure_500_nested_arrays.json",
+];
+
+#[test]
+fn test_json_test_suite() {
+    let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/JSONTestSuite/test_parsing");
+    let mut names: Vec<String> = fs::read_dir(&directory)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().into_string().unwrap())
+        .filter(|name| name.ends_with(".json"))
+        .collect();
+    names.sort();
+    assert!(!names.is_empty(), "no cases found in {}", directory.display());
+
+    let mut failures = Vec::new();
+    for name in &names {
+        let input = fs::read(directory.join(name)).unwrap();
+        let result = JsonParser::parse_from_bytes(&input);
+        let accept = match name.as_bytes()[0] {
+            b'y' => true,
+            b'n' => false,
+            b'i' => ACCEPTED_IMPLEMENTATION_DEFINED.contains(&name.as_str()),
+            _ => continue,
+        };
+        match (accept, result) {
+            (true, Err(error)) => failures.push(format!("{name}: rejected with {error}")),
+            (false, Ok(value)) => failures.push(format!("{name}: accepted as {}", value.to_json_string())),
+            _ => {}
+        }
+    }
+    assert!(failures.is_empty(), "{} cases failed:\n{}", failures.len(), failures.join("\n"));
+}
@@ -1,57 +1,216 @@
-use std::fmt;
+use std::{fmt, ops::Range};
 
-use crate::expr::Expr;
+use crate::{
+    ddl::CreateTable,
+    dml::{Delete, Insert, Update},
+    expr::Expr,
+};
 
-#[derive(Debug, Clone)]
+/// Byte range of a node in the SQL text it was parsed from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize) -> Self {
+        Span { start, end }
+    }
+
+    /// The smallest span covering both `self` and `other`
+    pub fn to(self, other: Span) -> Span {
+        Span::new(self.start.min(other.start), self.end.max(other.end))
+    }
+
+    pub fn range(self) -> Range<usize> {
+        self.start..self.end
+    }
+}
+
+impl From<Range<usize>> for Span {
+    fn from(range: Range<usize>) -> Self {
+        Span::new(range.start, range.end)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub enum Statement<'a> {
     Query(Query<'a>),
+    Insert(Insert<'a>),
+    Update(Update<'a>),
+    Delete(Delete<'a>),
+    CreateTable(CreateTable<'a>),
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum Query<'a> {
     Select(Box<SelectStmt<'a>>),
     With {
         with: With<'a>,
         query: Box<Query<'a>>,
     },
+    /// A trailing ORDER BY, LIMIT or OFFSET applies to the rows of the whole UNION, so it is
+    /// held here rather than on the last operand
     Union {
         left: Box<Query<'a>>,
         all: bool,
         right: Box<Query<'a>>,
+        order_by: Vec<OrderByExpr<'a>>,
+        limit: Option<Expr<'a>>,
+        offset: Option<Expr<'a>>,
     },
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 pub struct SelectStmt<'a> {
     pub projection: Vec<Expr<'a>>,
-    pub from: Option<TableRef<'a>>,
+    pub from: Vec<TableWithJoins<'a>>,
     pub where_clause: Option<Expr<'a>>,
+    pub group_by: Vec<Expr<'a>>,
+    pub having: Option<Expr<'a>>,
+    pub order_by: Vec<OrderByExpr<'a>>,
+    pub limit: Option<Expr<'a>>,
+    pub offset: Option<Expr<'a>>,
+    pub span: Span,
 }
 
-#[derive(Debug, Clone)]
+/// A sort key in ORDER BY; `None` means the direction or null placement was left implicit
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderByExpr<'a> {
+    pub expr: Expr<'a>,
+    pub asc: Option<bool>,
+    pub nulls_first: Option<bool>,
+    pub span: Span,
+}
+
+/// A bare name, such as a column in an INSERT column list, and where it was written
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Ident<'a> {
+    pub name: &'a str,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct TableRef<'a> {
     pub name: &'a str,
     pub alias: Option<&'a str>,
+    pub span: Span,
+}
+
+/// A single relation in a FROM list: a named table or a parenthesized subquery
+#[derive(Debug, Clone, PartialEq)]
+pub enum TableFactor<'a> {
+    Table(TableRef<'a>),
+    Derived {
+        subquery: Box<Query<'a>>,
+        alias: Option<&'a str>,
+        span: Span,
+    },
+}
+
+/// One comma-separated FROM item together with the joins chained onto it
+#[derive(Debug, Clone, PartialEq)]
+pub struct TableWithJoins<'a> {
+    pub relation: TableFactor<'a>,
+    pub joins: Vec<Join<'a>>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Join<'a> {
+    pub kind: JoinKind,
+    pub relation: TableFactor<'a>,
+    pub constraint: JoinConstraint<'a>,
+    pub span: Span,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinKind {
+    Inner,
+    Left,
+    Right,
+    Full,
+    Cross,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum JoinConstraint<'a> {
+    On(Expr<'a>),
+    Using(Vec<Ident<'a>>),
+    None,
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct With<'a> {
     pub recursive: bool,
     pub ctes: Vec<CTE<'a>>,
+    pub span: Span,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, PartialEq)]
 #[allow(clippy::upper_case_acronyms)]
 pub struct CTE<'a> {
     pub name: &'a str,
     pub columns: Option<Vec<&'a str>>,
     pub query: Box<Query<'a>>,
+    pub span: Span,
+}
+
+impl<'a> Statement<'a> {
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::Query(query) => query.span(),
+            Statement::Insert(insert) => insert.span,
+            Statement::Update(update) => update.span,
+            Statement::Delete(delete) => delete.span,
+            Statement::CreateTable(create) => create.span,
+        }
+    }
+}
+
+impl<'a> Query<'a> {
+    pub fn span(&self) -> Span {
+        match self {
+            Query::Select(select) => select.span,
+            Query::With { with, query } => with.span.to(query.span()),
+            Query::Union {
+                left,
+                right,
+                order_by,
+                limit,
+                offset,
+                ..
+            } => {
+                let end = offset
+                    .as_ref()
+                    .or(limit.as_ref())
+                    .map(|expr| expr.span)
+                    .or(order_by.last().map(|order| order.span))
+                    .unwrap_or_else(|| right.span());
+                left.span().to(end)
+            }
+        }
+    }
+}
+
+impl<'a> TableFactor<'a> {
+    pub fn span(&self) -> Span {
+        match self {
+            TableFactor::Table(table) => table.span,
+            TableFactor::Derived { span, .. } => *span,
+        }
+    }
 }
 
 impl<'a> fmt::Display for Statement<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match self {
             Statement::Query(q) => write!(f, "{}", q),
+            Statement::Insert(insert) => write!(f, "{}", insert),
+            Statement::Update(update) => write!(f, "{}", update),
+            Statement::Delete(delete) => write!(f, "{}", delete),
+            Statement::CreateTable(create) => write!(f, "{}", create),
         }
     }
 }
@@ -63,14 +222,22 @@ impl<'a> fmt::Display for Query<'a> {
             Query::With { with, query } => {
                 write!(f, "{} {}", with, query)
             }
-            Query::Union { left, all, right } => {
+            Query::Union {
+                left,
+                all,
+                right,
+                order_by,
+                limit,
+                offset,
+            } => {
                 write!(
                     f,
-                    "{} UNION {} {}",
+                    "{} UNION {}{}",
                     left,
-                    if *all { "ALL" } else { "" },
+                    if *all { "ALL " } else { "" },
                     right
-                )
+                )?;
+                fmt_order_and_limit(f, order_by, limit.as_ref(), offset.as_ref())
             }
         }
     }
@@ -87,18 +254,80 @@ impl<'a> fmt::Display for SelectStmt<'a> {
             write!(f, "{}", expr)?;
         }
 
-        if let Some(from) = &self.from {
-            write!(f, " FROM {}", from)?;
+        for (i, table) in self.from.iter().enumerate() {
+            write!(f, "{}", if i == 0 { " FROM " } else { ", " })?;
+            write!(f, "{}", table)?;
         }
 
         if let Some(where_clause) = &self.where_clause {
             write!(f, " WHERE {}", where_clause)?;
         }
 
+        for (i, expr) in self.group_by.iter().enumerate() {
+            write!(f, "{}", if i == 0 { " GROUP BY " } else { ", " })?;
+            write!(f, "{}", expr)?;
+        }
+
+        if let Some(having) = &self.having {
+            write!(f, " HAVING {}", having)?;
+        }
+
+        fmt_order_and_limit(f, &self.order_by, self.limit.as_ref(), self.offset.as_ref())
+    }
+}
+
+/// The ORDER BY, LIMIT and OFFSET clauses that close a SELECT or a UNION
+fn fmt_order_and_limit(
+    f: &mut fmt::Formatter<'_>,
+    order_by: &[OrderByExpr],
+    limit: Option<&Expr>,
+    offset: Option<&Expr>,
+) -> fmt::Result {
+    for (i, order) in order_by.iter().enumerate() {
+        write!(f, "{}", if i == 0 { " ORDER BY " } else { ", " })?;
+        write!(f, "{}", order)?;
+    }
+
+    if let Some(limit) = limit {
+        write!(f, " LIMIT {}", limit)?;
+    }
+
+    if let Some(offset) = offset {
+        write!(f, " OFFSET {}", offset)?;
+    }
+
+    Ok(())
+}
+
+impl<'a> fmt::Display for OrderByExpr<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.expr)?;
+        match self.asc {
+            Some(true) => write!(f, " ASC")?,
+            Some(false) => write!(f, " DESC")?,
+            None => {}
+        }
+        match self.nulls_first {
+            Some(true) => write!(f, " NULLS FIRST")?,
+            Some(false) => write!(f, " NULLS LAST")?,
+            None => {}
+        }
         Ok(())
     }
 }
 
+impl<'a> fmt::Display for Ident<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name)
+    }
+}
+
+/// The names of `idents` separated by commas, as in a column list
+pub(crate) fn join_idents(idents: &[Ident]) -> String {
+    let names: Vec<_> = idents.iter().map(|ident| ident.name).collect();
+    names.join(", ")
+}
+
 impl<'a> fmt::Display for TableRef<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "{}", self.name)?;
@@ -109,6 +338,57 @@ impl<'a> fmt::Display for TableRef<'a> {
     }
 }
 
+impl<'a> fmt::Display for TableFactor<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TableFactor::Table(table) => write!(f, "{}", table),
+            TableFactor::Derived {
+                subquery, alias, ..
+            } => {
+                write!(f, "({})", subquery)?;
+                if let Some(alias) = alias {
+                    write!(f, " AS {}", alias)?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+impl<'a> fmt::Display for TableWithJoins<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.relation)?;
+        for join in &self.joins {
+            write!(f, " {}", join)?;
+        }
+        Ok(())
+    }
+}
+
+impl<'a> fmt::Display for Join<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.kind, self.relation)?;
+        match &self.constraint {
+            JoinConstraint::On(expr) => write!(f, " ON {}", expr),
+            JoinConstraint::Using(columns) => write!(f, " USING ({})", join_idents(columns)),
+            JoinConstraint::None => Ok(()),
+        }
+    }
+}
+
+impl fmt::Display for JoinKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            JoinKind::Inner => "JOIN",
+            JoinKind::Left => "LEFT JOIN",
+            JoinKind::Right => "RIGHT JOIN",
+            JoinKind::Full => "FULL JOIN",
+            JoinKind::Cross => "CROSS JOIN",
+        };
+        write!(f, "{}", s)
+    }
+}
+
 impl<'a> fmt::Display for With<'a> {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         write!(f, "WITH ")?;
@@ -146,11 +426,13 @@ impl<'a> fmt::Display for CTE<'a> {
     }
 }
 
-
-
 #[cfg(test)]
 mod tests {
-    use crate::parser::parse_sql_to_string;
+    use super::*;
+    use crate::error::Backtrace;
+    use crate::parser::{Parser, parse_sql_to_string};
+    use crate::token::tokenize;
+    use crate::{format::tests::StripSpans, visitor::VisitorMut};
 
     #[test]
     fn test_simple_cte() {
@@ -170,4 +452,118 @@ mod tests {
         let ast_str = result.unwrap();
         assert!(ast_str.contains("recursive: true"));
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_join_display() {
+        let sql = "SELECT name FROM users AS u LEFT OUTER JOIN orders o ON id = user_id \
+                   CROSS JOIN regions, accounts JOIN owners USING (owner_id, region)";
+        let tokens = tokenize(sql);
+        let backtrace = Backtrace::new();
+        let mut parser = Parser::new(&tokens, &backtrace, sql);
+
+        let stmt = parser.parse_statement().unwrap();
+
+        assert_eq!(
+            stmt.to_string(),
+            "SELECT name FROM users AS u LEFT JOIN orders AS o ON id = user_id \
+             CROSS JOIN regions, accounts JOIN owners USING (owner_id, region)"
+        );
+    }
+
+    #[test]
+    fn test_join_constraints() {
+        let sql = "SELECT * FROM a INNER JOIN b ON x = y FULL JOIN c USING (z)";
+        let tokens = tokenize(sql);
+        let backtrace = Backtrace::new();
+        let mut parser = Parser::new(&tokens, &backtrace, sql);
+
+        let Statement::Query(Query::Select(select)) = parser.parse_statement().unwrap() else {
+            panic!("expected a plain SELECT");
+        };
+
+        assert_eq!(select.from.len(), 1);
+        let joins = &select.from[0].joins;
+        assert_eq!(joins[0].kind, JoinKind::Inner);
+        assert!(matches!(joins[0].constraint, JoinConstraint::On(_)));
+        assert_eq!(joins[1].kind, JoinKind::Full);
+        let JoinConstraint::Using(cols) = &joins[1].constraint else {
+            panic!("expected USING");
+        };
+        assert_eq!(cols.len(), 1);
+        assert_eq!(&sql[cols[0].span.range()], "z");
+    }
+
+    #[test]
+    fn test_join_requires_constraint() {
+        for sql in [
+            "SELECT * FROM a JOIN b",
+            "SELECT * FROM a LEFT JOIN b WHERE x = y",
+        ] {
+            let tokens = tokenize(sql);
+            let backtrace = Backtrace::new();
+            let mut parser = Parser::new(&tokens, &backtrace, sql);
+            assert!(parser.parse_statement().is_err(), "{sql}");
+        }
+    }
+
+    #[test]
+    fn test_select_clauses_round_trip() {
+        let sql = "SELECT region, count FROM sales WHERE year = 2024 GROUP BY region, count \
+                   HAVING count > 10 ORDER BY count DESC NULLS LAST, region LIMIT 5 OFFSET 10";
+        let tokens = tokenize(sql);
+        let backtrace = Backtrace::new();
+        let mut parser = Parser::new(&tokens, &backtrace, sql);
+        let mut stmt = parser.parse_statement().unwrap();
+        let printed = stmt.to_string();
+
+        assert_eq!(
+            printed,
+            "SELECT region, count FROM sales WHERE year = 2024 GROUP BY region, count \
+             HAVING count > 10 ORDER BY count DESC NULLS LAST, region LIMIT 5 OFFSET 10"
+        );
+
+        let tokens = tokenize(&printed);
+        let backtrace = Backtrace::new();
+        let mut parser = Parser::new(&tokens, &backtrace, &printed);
+        let mut reparsed = parser.parse_statement().unwrap();
+        StripSpans.visit_statement_mut(&mut stmt);
+        StripSpans.visit_statement_mut(&mut reparsed);
+        assert_eq!(reparsed, stmt);
+    }
+
+    #[test]
+    fn test_order_by_modifiers() {
+        let sql = "SELECT * FROM t ORDER BY a ASC NULLS FIRST, b";
+        let tokens = tokenize(sql);
+        let backtrace = Backtrace::new();
+        let mut parser = Parser::new(&tokens, &backtrace, sql);
+
+        let Statement::Query(Query::Select(select)) = parser.parse_statement().unwrap() else {
+            panic!("expected a plain SELECT");
+        };
+
+        assert_eq!(select.order_by.len(), 2);
+        assert_eq!(select.order_by[0].asc, Some(true));
+        assert_eq!(select.order_by[0].nulls_first, Some(true));
+        assert_eq!(select.order_by[1].asc, None);
+        assert_eq!(select.order_by[1].nulls_first, None);
+        assert!(select.limit.is_none());
+    }
+
+    #[test]
+    fn test_statement_dispatch() {
+        let cases = [
+            "INSERT INTO t (a) VALUES (1)",
+            "UPDATE t SET a = 2",
+            "DELETE FROM t WHERE (a = 2)",
+            "CREATE TABLE t (a INTEGER NOT NULL)",
+        ];
+
+        for sql in cases {
+            let tokens = tokenize(sql);
+            let backtrace = Backtrace::new();
+            let mut parser = Parser::new(&tokens, &backtrace, sql);
+            assert_eq!(parser.parse_statement().unwrap().to_string(), sql);
+        }
+    }
+}
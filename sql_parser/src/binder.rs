@@ -0,0 +1,1162 @@
+use std::{collections::HashMap, fmt::Display};
+
+use colored::Colorize;
+
+use crate::{
+    ast::{
+        CTE, Ident, JoinConstraint, OrderByExpr, Query, SelectStmt, Span, Statement, TableFactor,
+        TableRef, TableWithJoins, With,
+    },
+    ddl::{CreateTable, DataType},
+    dml::{Delete, Insert, InsertSource, Update},
+    error::{get_span_context, position_to_line_col, suggest_similar},
+    expr::{BinaryOp, Expr, ExprKind, Literal, UnaryOp},
+};
+
+/// A named, typed column; `data_type` is `None` when it can't be inferred (e.g. a bare NULL)
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnSchema {
+    pub name: String,
+    pub data_type: Option<DataType>,
+}
+
+/// The tables a statement may reference, keyed case-insensitively by name
+#[derive(Debug, Clone, Default)]
+pub struct Catalog {
+    tables: HashMap<String, Vec<ColumnSchema>>,
+}
+
+impl Catalog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_table(&mut self, name: &str, columns: &[(&str, DataType)]) {
+        let columns = columns
+            .iter()
+            .map(|(name, data_type)| ColumnSchema {
+                name: name.to_string(),
+                data_type: Some(*data_type),
+            })
+            .collect();
+        self.tables.insert(name.to_lowercase(), columns);
+    }
+
+    pub fn add_create_table(&mut self, create: &CreateTable) {
+        let columns: Vec<_> = create
+            .columns
+            .iter()
+            .map(|column| (column.name, column.data_type))
+            .collect();
+        self.add_table(create.name.name, &columns);
+    }
+
+    pub fn table(&self, name: &str) -> Option<&[ColumnSchema]> {
+        self.tables.get(&name.to_lowercase()).map(Vec::as_slice)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SemanticError {
+    pub message: String,
+    pub span: Span,
+    pub line: usize,
+    pub column: usize,
+    pub suggestion: Option<String>,
+    pub context: Option<String>,
+}
+
+impl Display for SemanticError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        writeln!(
+            f,
+            "{} at line {}:{}",
+            "Semantic error".red().bold(),
+            self.line,
+            self.column
+        )?;
+
+        writeln!(f, "  {}", self.message)?;
+
+        if let Some(ref suggestion) = self.suggestion {
+            writeln!(f, " {} {}", "Did you mean:".yellow(), suggestion.green())?;
+        }
+
+        if let Some(ref context) = self.context {
+            writeln!(f, "\n{}", context)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl std::error::Error for SemanticError {}
+
+/// A column reference in the SQL text and the relation column it resolved to
+#[derive(Debug, Clone)]
+pub struct ResolvedColumn<'a> {
+    pub reference: &'a str,
+    pub table: String,
+    pub column: String,
+    pub data_type: Option<DataType>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BoundStatement<'a> {
+    /// Output columns of a query, with `*` expanded; empty for other statements
+    pub output: Vec<ColumnSchema>,
+    pub columns: Vec<ResolvedColumn<'a>>,
+}
+
+/// Resolve names and check types in `stmt`, which must have been parsed from `input`
+pub fn bind_statement<'a>(
+    catalog: &Catalog,
+    input: &'a str,
+    stmt: &Statement<'a>,
+) -> Result<BoundStatement<'a>, Vec<SemanticError>> {
+    let mut binder = Binder {
+        catalog,
+        input,
+        scopes: Vec::new(),
+        ctes: Vec::new(),
+        resolved: Vec::new(),
+        errors: Vec::new(),
+    };
+
+    let output = match stmt {
+        Statement::Query(query) => binder.bind_query(query),
+        Statement::Insert(insert) => {
+            binder.bind_insert(insert);
+            Vec::new()
+        }
+        Statement::Update(update) => {
+            binder.bind_update(update);
+            Vec::new()
+        }
+        Statement::Delete(delete) => {
+            binder.bind_delete(delete);
+            Vec::new()
+        }
+        Statement::CreateTable(_) => Vec::new(),
+    };
+
+    if binder.errors.is_empty() {
+        Ok(BoundStatement {
+            output,
+            columns: binder.resolved,
+        })
+    } else {
+        let mut errors = binder.errors;
+        errors.sort_by_key(|e| (e.line, e.column));
+        Err(errors)
+    }
+}
+
+/// A table visible in a FROM scope under `name` (its alias, if it has one)
+struct Relation {
+    name: String,
+    columns: Vec<ColumnSchema>,
+    /// Set for tables that failed to resolve, so their columns don't cascade into more errors
+    opaque: bool,
+}
+
+struct Binder<'c, 'a> {
+    catalog: &'c Catalog,
+    input: &'a str,
+    /// One entry per enclosing SELECT, innermost last
+    scopes: Vec<Vec<Relation>>,
+    /// One entry per enclosing WITH, innermost last
+    ctes: Vec<HashMap<String, Vec<ColumnSchema>>>,
+    resolved: Vec<ResolvedColumn<'a>>,
+    errors: Vec<SemanticError>,
+}
+
+impl<'c, 'a> Binder<'c, 'a> {
+    fn error(&mut self, span: Span, message: String, suggestion: Option<String>) {
+        let (line, column) = position_to_line_col(self.input, span.start);
+        self.errors.push(SemanticError {
+            message,
+            span,
+            line,
+            column,
+            suggestion,
+            context: get_span_context(self.input, span),
+        });
+    }
+
+    fn lookup_cte(&self, name: &str) -> Option<&Vec<ColumnSchema>> {
+        let key = name.to_lowercase();
+        self.ctes.iter().rev().find_map(|frame| frame.get(&key))
+    }
+
+    fn bind_query(&mut self, query: &Query<'a>) -> Vec<ColumnSchema> {
+        match query {
+            Query::Select(select) => self.bind_select(select),
+            Query::With { with, query } => {
+                self.ctes.push(HashMap::new());
+                self.bind_with(with);
+                let output = self.bind_query(query);
+                self.ctes.pop();
+                output
+            }
+            Query::Union {
+                left,
+                right,
+                order_by,
+                limit,
+                offset,
+                ..
+            } => {
+                let left_columns = self.bind_query(left);
+                let right_columns = self.bind_query(right);
+                self.check_union_arity(&left_columns, &right_columns, right);
+                self.bind_union_tail(&left_columns, order_by, limit, offset);
+                left_columns
+            }
+        }
+    }
+
+    /// Bind the ORDER BY, LIMIT and OFFSET of a UNION, which see only its output columns
+    fn bind_union_tail(
+        &mut self,
+        columns: &[ColumnSchema],
+        order_by: &[OrderByExpr<'a>],
+        limit: &Option<Expr<'a>>,
+        offset: &Option<Expr<'a>>,
+    ) {
+        self.scopes.push(vec![Relation {
+            name: String::new(),
+            columns: columns.to_vec(),
+            opaque: false,
+        }]);
+        self.bind_order_and_limit(order_by, limit, offset);
+        self.scopes.pop();
+    }
+
+    fn bind_order_and_limit(
+        &mut self,
+        order_by: &[OrderByExpr<'a>],
+        limit: &Option<Expr<'a>>,
+        offset: &Option<Expr<'a>>,
+    ) {
+        for order in order_by {
+            self.bind_expr(&order.expr);
+        }
+        for expr in limit.iter().chain(offset) {
+            let data_type = self.bind_expr(expr);
+            if data_type.is_some_and(|t| !is_integer(t)) {
+                self.error(
+                    expr.span,
+                    "LIMIT and OFFSET must be integers".to_string(),
+                    None,
+                );
+            }
+        }
+    }
+
+    fn check_union_arity(&mut self, left: &[ColumnSchema], right: &[ColumnSchema], at: &Query) {
+        if left.len() != right.len() {
+            self.error(
+                at.span(),
+                format!(
+                    "UNION operands have different column counts: {} and {}",
+                    left.len(),
+                    right.len()
+                ),
+                None,
+            );
+        }
+    }
+
+    fn bind_with(&mut self, with: &With<'a>) {
+        for cte in &with.ctes {
+            self.bind_cte(cte, with.recursive);
+        }
+    }
+
+    fn bind_cte(&mut self, cte: &CTE<'a>, recursive: bool) {
+        let columns = match (&*cte.query, recursive) {
+            // The anchor member defines the schema the recursive member may refer to
+            (
+                Query::Union {
+                    left,
+                    right,
+                    order_by,
+                    limit,
+                    offset,
+                    ..
+                },
+                true,
+            ) => {
+                let anchor = self.bind_query(left);
+                let anchor = self.rename_cte_columns(cte, anchor);
+                self.register_cte(cte.name, anchor.clone());
+                let recursive_columns = self.bind_query(right);
+                self.check_union_arity(&anchor, &recursive_columns, right);
+                self.bind_union_tail(&anchor, order_by, limit, offset);
+                return;
+            }
+            _ => self.bind_query(&cte.query),
+        };
+
+        let columns = self.rename_cte_columns(cte, columns);
+        self.register_cte(cte.name, columns);
+    }
+
+    fn rename_cte_columns(
+        &mut self,
+        cte: &CTE<'a>,
+        columns: Vec<ColumnSchema>,
+    ) -> Vec<ColumnSchema> {
+        let Some(names) = &cte.columns else {
+            return columns;
+        };
+
+        if names.len() != columns.len() {
+            self.error(
+                cte.span,
+                format!(
+                    "CTE `{}` declares {} columns but its query produces {}",
+                    cte.name,
+                    names.len(),
+                    columns.len()
+                ),
+                None,
+            );
+        }
+
+        names
+            .iter()
+            .enumerate()
+            .map(|(i, name)| ColumnSchema {
+                name: name.to_string(),
+                data_type: columns.get(i).and_then(|c| c.data_type),
+            })
+            .collect()
+    }
+
+    fn register_cte(&mut self, name: &str, columns: Vec<ColumnSchema>) {
+        if let Some(frame) = self.ctes.last_mut() {
+            frame.insert(name.to_lowercase(), columns);
+        }
+    }
+
+    fn bind_select(&mut self, select: &SelectStmt<'a>) -> Vec<ColumnSchema> {
+        self.scopes.push(Vec::new());
+
+        for table in &select.from {
+            self.bind_table_with_joins(table);
+        }
+
+        if let Some(where_clause) = &select.where_clause {
+            self.bind_predicate(where_clause, "WHERE");
+        }
+
+        let mut output = Vec::new();
+        for expr in &select.projection {
+            if let ExprKind::Star = expr.kind {
+                for relation in self.scopes.last().into_iter().flatten() {
+                    output.extend(relation.columns.iter().cloned());
+                }
+                continue;
+            }
+
+            let data_type = self.bind_expr(expr);
+            let name = match &expr.kind {
+                ExprKind::Column(name) | ExprKind::QualifiedColumn { column: name, .. } => name,
+                ExprKind::Function { name, .. } => name,
+                _ => "?column?",
+            };
+            output.push(ColumnSchema {
+                name: name.to_string(),
+                data_type,
+            });
+        }
+
+        for expr in &select.group_by {
+            self.bind_expr(expr);
+        }
+        if let Some(having) = &select.having {
+            self.bind_predicate(having, "HAVING");
+        }
+        self.bind_order_and_limit(&select.order_by, &select.limit, &select.offset);
+
+        self.scopes.pop();
+        output
+    }
+
+    fn bind_table_with_joins(&mut self, table: &TableWithJoins<'a>) {
+        self.bind_table_factor(&table.relation);
+
+        for join in &table.joins {
+            let left_count = self.scopes.last().map_or(0, Vec::len);
+            self.bind_table_factor(&join.relation);
+
+            match &join.constraint {
+                JoinConstraint::On(expr) => self.bind_predicate(expr, "ON"),
+                JoinConstraint::Using(columns) => {
+                    for column in columns {
+                        self.check_using_column(column, left_count);
+                    }
+                }
+                JoinConstraint::None => {}
+            }
+        }
+    }
+
+    /// A USING column must exist on both sides of the join
+    fn check_using_column(&mut self, column: &Ident<'a>, left_count: usize) {
+        let Some(scope) = self.scopes.last() else {
+            return;
+        };
+        let (left, right) = scope.split_at(left_count.min(scope.len()));
+        let has = |relations: &[Relation]| {
+            relations.iter().any(|r| {
+                r.columns
+                    .iter()
+                    .any(|c| c.name.eq_ignore_ascii_case(column.name))
+            })
+        };
+
+        if !has(left) || !has(right) {
+            self.error(
+                column.span,
+                format!(
+                    "USING column `{}` must appear on both sides of the join",
+                    column.name
+                ),
+                None,
+            );
+        }
+    }
+
+    fn bind_table_factor(&mut self, factor: &TableFactor<'a>) {
+        let relation = match factor {
+            TableFactor::Table(table) => self.bind_table_ref(table),
+            TableFactor::Derived {
+                subquery, alias, ..
+            } => {
+                let columns = self.bind_query(subquery);
+                Some(Relation {
+                    name: alias.unwrap_or("").to_string(),
+                    columns,
+                    opaque: false,
+                })
+            }
+        };
+
+        let Some(relation) = relation else {
+            return;
+        };
+
+        let duplicate = !relation.name.is_empty()
+            && self
+                .scopes
+                .last()
+                .into_iter()
+                .flatten()
+                .any(|r| r.name.eq_ignore_ascii_case(&relation.name));
+        if duplicate {
+            self.error(
+                factor.span(),
+                format!("Table name `{}` specified more than once", relation.name),
+                None,
+            );
+        }
+
+        if let Some(scope) = self.scopes.last_mut() {
+            scope.push(relation);
+        }
+    }
+
+    fn bind_table_ref(&mut self, table: &TableRef<'a>) -> Option<Relation> {
+        let columns = self
+            .lookup_cte(table.name)
+            .or_else(|| self.catalog.tables.get(&table.name.to_lowercase()))
+            .cloned();
+
+        let name = table.alias.unwrap_or(table.name).to_string();
+        match columns {
+            Some(columns) => Some(Relation {
+                name,
+                columns,
+                opaque: false,
+            }),
+            None => {
+                let candidates: Vec<&str> = self
+                    .catalog
+                    .tables
+                    .keys()
+                    .chain(self.ctes.iter().flat_map(|frame| frame.keys()))
+                    .map(String::as_str)
+                    .collect();
+                let suggestion = suggest_similar(table.name, candidates);
+                self.error(
+                    table.span,
+                    format!("Unknown table `{}`", table.name),
+                    suggestion,
+                );
+                Some(Relation {
+                    name,
+                    columns: Vec::new(),
+                    opaque: true,
+                })
+            }
+        }
+    }
+
+    fn bind_predicate(&mut self, expr: &Expr<'a>, clause: &str) {
+        let data_type = self.bind_expr(expr);
+        if let Some(data_type) = data_type
+            && data_type != DataType::Boolean
+        {
+            self.error(
+                expr.span,
+                format!("{} condition must be BOOLEAN, found {}", clause, data_type),
+                None,
+            );
+        }
+    }
+
+    /// Resolve the column `name` written at `span`, optionally qualified by a table or alias
+    fn resolve_column(
+        &mut self,
+        qualifier: Option<&'a str>,
+        name: &'a str,
+        span: Span,
+    ) -> Option<DataType> {
+        let reference = qualifier.unwrap_or(name);
+
+        let found = match qualifier {
+            Some(qualifier) => self.resolve_qualified(qualifier, name, span),
+            None => self.resolve_unqualified(name, span),
+        };
+
+        found.map(|(table, column)| {
+            self.resolved.push(ResolvedColumn {
+                reference,
+                table,
+                column: column.name.clone(),
+                data_type: column.data_type,
+            });
+            column.data_type
+        })?
+    }
+
+    fn resolve_qualified(
+        &mut self,
+        qualifier: &'a str,
+        name: &'a str,
+        span: Span,
+    ) -> Option<(String, ColumnSchema)> {
+        let relation = self
+            .scopes
+            .iter()
+            .rev()
+            .flatten()
+            .find(|r| r.name.eq_ignore_ascii_case(qualifier));
+
+        let Some(relation) = relation else {
+            let candidates: Vec<&str> = self
+                .scopes
+                .iter()
+                .flatten()
+                .map(|r| r.name.as_str())
+                .collect();
+            let suggestion = suggest_similar(qualifier, candidates);
+            self.error(
+                span,
+                format!("Unknown table or alias `{}`", qualifier),
+                suggestion,
+            );
+            return None;
+        };
+
+        match relation
+            .columns
+            .iter()
+            .find(|c| c.name.eq_ignore_ascii_case(name))
+        {
+            Some(column) => Some((relation.name.clone(), column.clone())),
+            None if relation.opaque => None,
+            None => {
+                let suggestion =
+                    suggest_similar(name, relation.columns.iter().map(|c| c.name.as_str()));
+                self.error(
+                    span,
+                    format!("Unknown column `{}.{}`", qualifier, name),
+                    suggestion,
+                );
+                None
+            }
+        }
+    }
+
+    fn resolve_unqualified(&mut self, name: &'a str, span: Span) -> Option<(String, ColumnSchema)> {
+        // Inner scopes shadow outer ones, so correlated subqueries see the nearest match
+        for scope in self.scopes.iter().rev() {
+            let matches: Vec<_> = scope
+                .iter()
+                .filter_map(|r| {
+                    r.columns
+                        .iter()
+                        .find(|c| c.name.eq_ignore_ascii_case(name))
+                        .map(|c| (r.name.clone(), c.clone()))
+                })
+                .collect();
+
+            match matches.len() {
+                0 if scope.iter().any(|r| r.opaque) => return None,
+                0 => continue,
+                1 => return matches.into_iter().next(),
+                _ => {
+                    let tables: Vec<_> = matches.iter().map(|(t, _)| t.as_str()).collect();
+                    self.error(
+                        span,
+                        format!(
+                            "Column reference `{}` is ambiguous (in {})",
+                            name,
+                            tables.join(", ")
+                        ),
+                        None,
+                    );
+                    return None;
+                }
+            }
+        }
+
+        let candidates: Vec<&str> = self
+            .scopes
+            .iter()
+            .flatten()
+            .flat_map(|r| r.columns.iter().map(|c| c.name.as_str()))
+            .collect();
+        let suggestion = suggest_similar(name, candidates);
+        self.error(span, format!("Unknown column `{}`", name), suggestion);
+        None
+    }
+
+    fn bind_expr(&mut self, expr: &Expr<'a>) -> Option<DataType> {
+        match &expr.kind {
+            ExprKind::Column(name) => self.resolve_column(None, name, expr.span),
+            ExprKind::QualifiedColumn { table, column } => {
+                self.resolve_column(Some(table), column, expr.span)
+            }
+            ExprKind::Literal(literal) => match literal {
+                Literal::Number(_) => Some(DataType::Integer),
+                Literal::Float(_) => Some(DataType::Float),
+                Literal::String(_) => Some(DataType::Text),
+                Literal::Boolean(_) => Some(DataType::Boolean),
+                Literal::Null => None,
+            },
+            ExprKind::Binary { left, op, right } => {
+                let left_type = self.bind_expr(left);
+                let right_type = self.bind_expr(right);
+                self.check_binary(*op, left_type, right_type, expr.span)
+            }
+            ExprKind::Unary { op, expr: operand } => {
+                let data_type = self.bind_expr(operand);
+                let ok = match op {
+                    UnaryOp::Not => data_type.is_none_or(|t| t == DataType::Boolean),
+                    UnaryOp::Minus | UnaryOp::Plus => data_type.is_none_or(is_numeric),
+                };
+                if !ok {
+                    self.error(
+                        operand.span,
+                        format!(
+                            "Cannot apply unary `{}` to {}",
+                            op.to_string().trim(),
+                            display_type(data_type)
+                        ),
+                        None,
+                    );
+                }
+                data_type
+            }
+            ExprKind::Function { name, args, .. } => {
+                let arg_types: Vec<_> = args
+                    .iter()
+                    .map(|arg| match arg.kind {
+                        // COUNT(*)
+                        ExprKind::Star => None,
+                        _ => self.bind_expr(arg),
+                    })
+                    .collect();
+                let first = arg_types.first().copied().flatten();
+                match name.to_uppercase().as_str() {
+                    "COUNT" => Some(DataType::BigInt),
+                    "SUM" => first.map(|t| {
+                        if t == DataType::Integer {
+                            DataType::BigInt
+                        } else {
+                            t
+                        }
+                    }),
+                    "AVG" => Some(DataType::Float),
+                    "MIN" | "MAX" | "ABS" | "COALESCE" => first,
+                    "LOWER" | "UPPER" | "TRIM" | "CONCAT" => Some(DataType::Text),
+                    "LENGTH" => Some(DataType::Integer),
+                    _ => None,
+                }
+            }
+            ExprKind::IsNull { expr, .. } => {
+                self.bind_expr(expr);
+                Some(DataType::Boolean)
+            }
+            ExprKind::InList { expr, list, .. } => {
+                let data_type = self.bind_expr(expr);
+                for item in list {
+                    let item_type = self.bind_expr(item);
+                    self.check_comparable(data_type, item_type, item);
+                }
+                Some(DataType::Boolean)
+            }
+            ExprKind::InSubquery { expr, subquery, .. } => {
+                let data_type = self.bind_expr(expr);
+                let columns = self.bind_query(subquery);
+                if columns.len() != 1 {
+                    self.error(
+                        subquery.span(),
+                        format!(
+                            "IN subquery must return exactly one column, found {}",
+                            columns.len()
+                        ),
+                        None,
+                    );
+                } else {
+                    self.check_comparable(data_type, columns[0].data_type, expr);
+                }
+                Some(DataType::Boolean)
+            }
+            ExprKind::Between {
+                expr, low, high, ..
+            } => {
+                let data_type = self.bind_expr(expr);
+                let low_type = self.bind_expr(low);
+                let high_type = self.bind_expr(high);
+                self.check_comparable(data_type, low_type, low);
+                self.check_comparable(data_type, high_type, high);
+                Some(DataType::Boolean)
+            }
+            ExprKind::Like { expr, pattern, .. } => {
+                for operand in [expr, pattern] {
+                    let data_type = self.bind_expr(operand);
+                    if data_type.is_some_and(|t| !is_text(t)) {
+                        self.error(
+                            operand.span,
+                            format!(
+                                "LIKE requires text operands, found {}",
+                                display_type(data_type)
+                            ),
+                            None,
+                        );
+                    }
+                }
+                Some(DataType::Boolean)
+            }
+            ExprKind::Case {
+                operand,
+                when_then,
+                else_result,
+            } => {
+                if let Some(operand) = operand {
+                    self.bind_expr(operand);
+                }
+                let mut result = None;
+                for (when, then) in when_then {
+                    if operand.is_some() {
+                        self.bind_expr(when);
+                    } else {
+                        self.bind_predicate(when, "WHEN");
+                    }
+                    result = result.or(self.bind_expr(then));
+                }
+                if let Some(else_result) = else_result {
+                    result = result.or(self.bind_expr(else_result));
+                }
+                result
+            }
+            ExprKind::Cast { expr, data_type } => {
+                self.bind_expr(expr);
+                Some(*data_type)
+            }
+            ExprKind::Paren(expr) => self.bind_expr(expr),
+            ExprKind::Star => {
+                self.error(
+                    expr.span,
+                    "`*` is only allowed in the select list or COUNT(*)".to_string(),
+                    None,
+                );
+                None
+            }
+        }
+    }
+
+    fn check_binary(
+        &mut self,
+        op: BinaryOp,
+        left: Option<DataType>,
+        right: Option<DataType>,
+        at: Span,
+    ) -> Option<DataType> {
+        let result = match op {
+            BinaryOp::And | BinaryOp::Or => {
+                let ok = left.is_none_or(|t| t == DataType::Boolean)
+                    && right.is_none_or(|t| t == DataType::Boolean);
+                ok.then_some(Some(DataType::Boolean))
+            }
+            BinaryOp::Equal
+            | BinaryOp::NotEqual
+            | BinaryOp::Less
+            | BinaryOp::Greater
+            | BinaryOp::LessEqual
+            | BinaryOp::GreaterEqual => comparable(left, right).then_some(Some(DataType::Boolean)),
+            BinaryOp::Plus | BinaryOp::Minus | BinaryOp::Multiply | BinaryOp::Divide => {
+                match (left, right) {
+                    (Some(l), Some(r)) if is_numeric(l) && is_numeric(r) => {
+                        Some(Some(widen_numeric(l, r)))
+                    }
+                    (None, other) | (other, None) if other.is_none_or(is_numeric) => Some(other),
+                    _ => None,
+                }
+            }
+        };
+
+        match result {
+            Some(data_type) => data_type,
+            None => {
+                self.error(
+                    at,
+                    format!(
+                        "Cannot apply `{}` to {} and {}",
+                        op,
+                        display_type(left),
+                        display_type(right)
+                    ),
+                    None,
+                );
+                None
+            }
+        }
+    }
+
+    fn check_comparable(&mut self, left: Option<DataType>, right: Option<DataType>, at: &Expr<'a>) {
+        if !comparable(left, right) {
+            self.error(
+                at.span,
+                format!(
+                    "Cannot compare {} with {}",
+                    display_type(left),
+                    display_type(right)
+                ),
+                None,
+            );
+        }
+    }
+
+    fn bind_target_table(&mut self, table: &TableRef<'a>) -> bool {
+        match self.bind_table_ref(table) {
+            Some(relation) if !relation.opaque => {
+                self.scopes.push(vec![relation]);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    fn bind_insert(&mut self, insert: &Insert<'a>) {
+        if !self.bind_target_table(&insert.table) {
+            return;
+        }
+
+        let target: Vec<ColumnSchema> = if insert.columns.is_empty() {
+            self.scopes[0][0].columns.clone()
+        } else {
+            // An unknown column keeps its slot with an unknown type, so the values still
+            // line up and only the unknown name is reported
+            insert
+                .columns
+                .iter()
+                .map(|column| ColumnSchema {
+                    name: column.name.to_string(),
+                    data_type: self.resolve_column(None, column.name, column.span),
+                })
+                .collect()
+        };
+        // Values may not refer to the target's columns
+        self.scopes.pop();
+
+        let rows: Vec<(Vec<Option<DataType>>, Span)> = match &insert.source {
+            InsertSource::Values(rows) => rows
+                .iter()
+                .map(|row| {
+                    let span = match (row.first(), row.last()) {
+                        (Some(first), Some(last)) => first.span.to(last.span),
+                        _ => insert.span,
+                    };
+                    (row.iter().map(|expr| self.bind_expr(expr)).collect(), span)
+                })
+                .collect(),
+            InsertSource::Query(query) => {
+                let columns = self.bind_query(query);
+                vec![(
+                    columns.into_iter().map(|c| c.data_type).collect(),
+                    query.span(),
+                )]
+            }
+        };
+
+        for (types, span) in rows {
+            if types.len() != target.len() {
+                self.error(
+                    span,
+                    format!(
+                        "INSERT has {} target columns but {} values",
+                        target.len(),
+                        types.len()
+                    ),
+                    None,
+                );
+                continue;
+            }
+            for (column, data_type) in target.iter().zip(types) {
+                if !assignable(column.data_type, data_type) {
+                    self.error(
+                        span,
+                        format!(
+                            "Cannot insert {} into column `{}` of type {}",
+                            display_type(data_type),
+                            column.name,
+                            display_type(column.data_type)
+                        ),
+                        None,
+                    );
+                }
+            }
+        }
+    }
+
+    fn bind_update(&mut self, update: &Update<'a>) {
+        if !self.bind_target_table(&update.table) {
+            return;
+        }
+
+        for assignment in &update.assignments {
+            let column = assignment.column;
+            let column_type = self.resolve_column(None, column.name, column.span);
+            let value_type = self.bind_expr(&assignment.value);
+            if !assignable(column_type, value_type) {
+                self.error(
+                    assignment.span,
+                    format!(
+                        "Cannot assign {} to column `{}` of type {}",
+                        display_type(value_type),
+                        assignment.column,
+                        display_type(column_type)
+                    ),
+                    None,
+                );
+            }
+        }
+
+        if let Some(where_clause) = &update.where_clause {
+            self.bind_predicate(where_clause, "WHERE");
+        }
+        self.scopes.pop();
+    }
+
+    fn bind_delete(&mut self, delete: &Delete<'a>) {
+        if !self.bind_target_table(&delete.table) {
+            return;
+        }
+
+        if let Some(where_clause) = &delete.where_clause {
+            self.bind_predicate(where_clause, "WHERE");
+        }
+        self.scopes.pop();
+    }
+}
+
+fn is_integer(data_type: DataType) -> bool {
+    matches!(data_type, DataType::Integer | DataType::BigInt)
+}
+
+fn is_numeric(data_type: DataType) -> bool {
+    is_integer(data_type) || data_type == DataType::Float
+}
+
+fn is_text(data_type: DataType) -> bool {
+    matches!(data_type, DataType::Text | DataType::Varchar(_))
+}
+
+fn is_temporal(data_type: DataType) -> bool {
+    matches!(data_type, DataType::Date | DataType::Timestamp)
+}
+
+fn widen_numeric(left: DataType, right: DataType) -> DataType {
+    if left == DataType::Float || right == DataType::Float {
+        DataType::Float
+    } else if left == DataType::BigInt || right == DataType::BigInt {
+        DataType::BigInt
+    } else {
+        DataType::Integer
+    }
+}
+
+fn comparable(left: Option<DataType>, right: Option<DataType>) -> bool {
+    let (Some(left), Some(right)) = (left, right) else {
+        return true;
+    };
+    (is_numeric(left) && is_numeric(right))
+        || (is_text(left) && is_text(right))
+        // Date and time values are commonly written as string literals
+        || (is_temporal(left) && (is_text(right) || left == right))
+        || (is_temporal(right) && is_text(left))
+        || left == right
+}
+
+fn assignable(column: Option<DataType>, value: Option<DataType>) -> bool {
+    match (column, value) {
+        (Some(column), Some(value)) if is_numeric(column) => is_numeric(value),
+        _ => comparable(column, value),
+    }
+}
+
+fn display_type(data_type: Option<DataType>) -> String {
+    data_type.map_or_else(|| "NULL".to_string(), |t| t.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{error::Backtrace, parser::Parser, token::tokenize};
+
+    fn catalog() -> Catalog {
+        let mut catalog = Catalog::new();
+        catalog.add_table(
+            "users",
+            &[
+                ("id", DataType::Integer),
+                ("name", DataType::Text),
+                ("age", DataType::Integer),
+            ],
+        );
+        catalog.add_table(
+            "orders",
+            &[
+                ("id", DataType::Integer),
+                ("user_id", DataType::Integer),
+                ("total", DataType::Float),
+            ],
+        );
+        catalog
+    }
+
+    fn bind(sql: &str) -> Result<BoundStatement<'_>, Vec<SemanticError>> {
+        let tokens = tokenize(sql);
+        let backtrace = Backtrace::new();
+        let mut parser = Parser::new(&tokens, &backtrace, sql);
+        let stmt = parser.parse_statement().unwrap();
+        bind_statement(&catalog(), sql, &stmt)
+    }
+
+    #[test]
+    fn test_resolve_columns_and_expand_star() {
+        let bound = bind(
+            "SELECT *, SUM(o.total) FROM users u JOIN orders o ON u.id = o.user_id \
+             WHERE age > 18 GROUP BY u.id",
+        )
+        .unwrap();
+
+        let names: Vec<_> = bound.output.iter().map(|c| c.name.as_str()).collect();
+        assert_eq!(
+            names,
+            ["id", "name", "age", "id", "user_id", "total", "SUM"]
+        );
+        assert_eq!(bound.output[6].data_type, Some(DataType::Float));
+
+        let age = bound.columns.iter().find(|c| c.column == "age").unwrap();
+        assert_eq!(age.table, "u");
+        assert_eq!(age.data_type, Some(DataType::Integer));
+    }
+
+    #[test]
+    fn test_unknown_names_with_suggestions() {
+        let errors =
+            bind("SELECT nmae FROM users WHERE\n  id IN (SELECT user_id FROM ordrs)").unwrap_err();
+
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].message, "Unknown column `nmae`");
+        assert_eq!(errors[0].suggestion.as_deref(), Some("name"));
+        assert_eq!((errors[0].line, errors[0].column), (1, 8));
+        assert_eq!(errors[1].message, "Unknown table `ordrs`");
+        assert_eq!(errors[1].suggestion.as_deref(), Some("orders"));
+        assert_eq!((errors[1].line, errors[1].column), (2, 30));
+        assert!(errors[1].context.is_some());
+    }
+
+    #[test]
+    fn test_ambiguous_column_and_type_errors() {
+        let errors = bind("SELECT id FROM users, orders WHERE name + 1 > 2").unwrap_err();
+
+        assert_eq!(errors.len(), 2);
+        assert!(errors[0].message.contains("ambiguous"));
+        assert_eq!(errors[1].message, "Cannot apply `+` to TEXT and INTEGER");
+    }
+
+    #[test]
+    fn test_union_order_by_sees_union_columns() {
+        let bound = bind(
+            "SELECT user_id FROM orders UNION SELECT id FROM users ORDER BY user_id DESC LIMIT 2",
+        )
+        .unwrap();
+        assert_eq!(bound.output[0].name, "user_id");
+
+        let errors = bind("SELECT user_id FROM orders UNION SELECT name FROM users ORDER BY name")
+            .unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "Unknown column `name`");
+        assert_eq!((errors[0].line, errors[0].column), (1, 66));
+    }
+
+    #[test]
+    fn test_cte_column_counts() {
+        let errors = bind("WITH t(a, b) AS (SELECT id FROM users) SELECT a FROM t").unwrap_err();
+        assert_eq!(
+            errors[0].message,
+            "CTE `t` declares 2 columns but its query produces 1"
+        );
+
+        let bound = bind(
+            "WITH RECURSIVE nums(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM nums WHERE n < 5) \
+             SELECT n FROM nums",
+        )
+        .unwrap();
+        assert_eq!(bound.output[0].name, "n");
+        assert_eq!(bound.output[0].data_type, Some(DataType::Integer));
+    }
+
+    #[test]
+    fn test_dml_checks() {
+        assert!(bind("INSERT INTO users (id, name) VALUES (1, 'ann')").is_ok());
+        assert!(bind("UPDATE users SET age = age + 1 WHERE id = 3").is_ok());
+
+        let errors = bind("INSERT INTO users (id, name) VALUES (1)").unwrap_err();
+        assert_eq!(
+            errors[0].message,
+            "INSERT has 2 target columns but 1 values"
+        );
+
+        let errors = bind("INSERT INTO users (id, nmae) VALUES (1, 'ann')").unwrap_err();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].message, "Unknown column `nmae`");
+        assert_eq!((errors[0].line, errors[0].column), (1, 24));
+
+        let errors = bind("UPDATE users SET age = 'old'").unwrap_err();
+        assert!(errors[0].message.contains("Cannot assign TEXT"));
+
+        let errors = bind("UPDATE users SET agee = 1").unwrap_err();
+        assert_eq!(errors[0].message, "Unknown column `agee`");
+        assert_eq!((errors[0].line, errors[0].column), (1, 18));
+    }
+}
@@ -0,0 +1,261 @@
+use std::fmt;
+
+use crate::{
+    ast::{Span, TableRef},
+    expr::Expr,
+    parser::{ParseResult, Parser},
+    token::TokenKind,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct CreateTable<'a> {
+    pub name: TableRef<'a>,
+    pub if_not_exists: bool,
+    pub columns: Vec<ColumnDef<'a>>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ColumnDef<'a> {
+    pub name: &'a str,
+    pub data_type: DataType,
+    pub constraints: Vec<ColumnConstraint<'a>>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataType {
+    Integer,
+    BigInt,
+    Float,
+    Boolean,
+    Text,
+    Varchar(Option<u32>),
+    Date,
+    Timestamp,
+}
+
+impl DataType {
+    /// Map a type name (and its aliases) onto a `DataType`, ignoring any length argument
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_uppercase().as_str() {
+            "INT" | "INTEGER" | "SMALLINT" => Some(DataType::Integer),
+            "BIGINT" => Some(DataType::BigInt),
+            "FLOAT" | "REAL" | "DOUBLE" => Some(DataType::Float),
+            "BOOL" | "BOOLEAN" => Some(DataType::Boolean),
+            "TEXT" => Some(DataType::Text),
+            "VARCHAR" | "CHAR" => Some(DataType::Varchar(None)),
+            "DATE" => Some(DataType::Date),
+            "TIMESTAMP" => Some(DataType::Timestamp),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColumnConstraint<'a> {
+    PrimaryKey,
+    NotNull,
+    Null,
+    Unique,
+    Default(Expr<'a>),
+}
+
+impl<'a> fmt::Display for CreateTable<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "CREATE TABLE ")?;
+        if self.if_not_exists {
+            write!(f, "IF NOT EXISTS ")?;
+        }
+        write!(f, "{} (", self.name)?;
+
+        for (i, column) in self.columns.iter().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", column)?;
+        }
+
+        write!(f, ")")
+    }
+}
+
+impl<'a> fmt::Display for ColumnDef<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} {}", self.name, self.data_type)?;
+        for constraint in &self.constraints {
+            write!(f, " {}", constraint)?;
+        }
+        Ok(())
+    }
+}
+
+impl fmt::Display for DataType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DataType::Integer => write!(f, "INTEGER"),
+            DataType::BigInt => write!(f, "BIGINT"),
+            DataType::Float => write!(f, "FLOAT"),
+            DataType::Boolean => write!(f, "BOOLEAN"),
+            DataType::Text => write!(f, "TEXT"),
+            DataType::Varchar(None) => write!(f, "VARCHAR"),
+            DataType::Varchar(Some(len)) => write!(f, "VARCHAR({})", len),
+            DataType::Date => write!(f, "DATE"),
+            DataType::Timestamp => write!(f, "TIMESTAMP"),
+        }
+    }
+}
+
+impl<'a> fmt::Display for ColumnConstraint<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ColumnConstraint::PrimaryKey => write!(f, "PRIMARY KEY"),
+            ColumnConstraint::NotNull => write!(f, "NOT NULL"),
+            ColumnConstraint::Null => write!(f, "NULL"),
+            ColumnConstraint::Unique => write!(f, "UNIQUE"),
+            ColumnConstraint::Default(expr) => write!(f, "DEFAULT {}", expr),
+        }
+    }
+}
+
+impl<'a> Parser<'_, 'a> {
+    /// Parse `CREATE TABLE [IF NOT EXISTS] name (column type [constraints], ...)`
+    pub fn parse_create_table(&mut self) -> ParseResult<CreateTable<'a>> {
+        let start = self.start();
+        self.expect(TokenKind::Create)?;
+        self.expect(TokenKind::Table)?;
+
+        let if_not_exists = if self.try_consume(TokenKind::If) {
+            self.expect(TokenKind::Not)?;
+            self.expect(TokenKind::Exists)?;
+            true
+        } else {
+            false
+        };
+
+        let name = self.parse_table_name()?;
+
+        self.expect(TokenKind::LeftParen)?;
+        let mut columns = vec![self.parse_column_def()?];
+        while self.try_consume(TokenKind::Comma) {
+            columns.push(self.parse_column_def()?);
+        }
+        self.expect(TokenKind::RightParen)?;
+
+        Ok(CreateTable {
+            name,
+            if_not_exists,
+            columns,
+            span: self.span_from(start),
+        })
+    }
+
+    fn parse_column_def(&mut self) -> ParseResult<ColumnDef<'a>> {
+        let start = self.start();
+        let name = self.parse_identifier()?;
+        let data_type = self.parse_data_type()?;
+
+        let mut constraints = Vec::new();
+        while let Some(constraint) = self.parse_column_constraint()? {
+            constraints.push(constraint);
+        }
+
+        Ok(ColumnDef {
+            name,
+            data_type,
+            constraints,
+            span: self.span_from(start),
+        })
+    }
+
+    pub(crate) fn parse_data_type(&mut self) -> ParseResult<DataType> {
+        let data_type = match self.current() {
+            Some(token) if token.kind == TokenKind::Identifier => DataType::from_name(token.text),
+            _ => None,
+        };
+        let Some(data_type) = data_type else {
+            return Err(self.error_expected_one_of(&["data type"]));
+        };
+        self.advance();
+
+        if data_type == DataType::Varchar(None) && self.try_consume(TokenKind::LeftParen) {
+            let text = self.expect(TokenKind::Number)?.text;
+            let len = text
+                .parse::<u32>()
+                .map_err(|_| self.error_at_current("Invalid VARCHAR length"))?;
+            self.expect(TokenKind::RightParen)?;
+            return Ok(DataType::Varchar(Some(len)));
+        }
+
+        Ok(data_type)
+    }
+
+    fn parse_column_constraint(&mut self) -> ParseResult<Option<ColumnConstraint<'a>>> {
+        let constraint = match self.current().map(|t| t.kind) {
+            Some(TokenKind::Primary) => {
+                self.advance();
+                self.expect(TokenKind::Key)?;
+                ColumnConstraint::PrimaryKey
+            }
+            Some(TokenKind::Not) => {
+                self.advance();
+                self.expect(TokenKind::Null)?;
+                ColumnConstraint::NotNull
+            }
+            Some(TokenKind::Null) => {
+                self.advance();
+                ColumnConstraint::Null
+            }
+            Some(TokenKind::Unique) => {
+                self.advance();
+                ColumnConstraint::Unique
+            }
+            Some(TokenKind::Default) => {
+                self.advance();
+                ColumnConstraint::Default(self.parse_expr()?)
+            }
+            _ => return Ok(None),
+        };
+        Ok(Some(constraint))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Backtrace;
+    use crate::token::tokenize;
+
+    #[test]
+    fn test_create_table() {
+        let input = "CREATE TABLE IF NOT EXISTS users (id INT PRIMARY KEY, \
+                     name varchar(64) NOT NULL UNIQUE, score float DEFAULT -1 NOT NULL)";
+        let tokens = tokenize(input);
+        let backtrace = Backtrace::new();
+        let mut parser = Parser::new(&tokens, &backtrace, input);
+
+        let create = parser.parse_create_table().unwrap();
+
+        assert!(create.if_not_exists);
+        assert_eq!(create.columns.len(), 3);
+        assert_eq!(create.columns[1].data_type, DataType::Varchar(Some(64)));
+        assert_eq!(
+            create.to_string(),
+            "CREATE TABLE IF NOT EXISTS users (id INTEGER PRIMARY KEY, \
+             name VARCHAR(64) NOT NULL UNIQUE, score FLOAT DEFAULT -1 NOT NULL)"
+        );
+    }
+
+    #[test]
+    fn test_unknown_data_type() {
+        let input = "CREATE TABLE t (id BLOBBY)";
+        let tokens = tokenize(input);
+        let backtrace = Backtrace::new();
+        let mut parser = Parser::new(&tokens, &backtrace, input);
+
+        let err = parser.parse_create_table().unwrap_err();
+
+        assert!(err.message.contains("data type"));
+        assert!(err.message.contains("BLOBBY"));
+    }
+}
@@ -0,0 +1,287 @@
+use std::fmt;
+
+use crate::{
+    ast::{Ident, Query, Span, TableRef},
+    expr::Expr,
+    parser::{ParseResult, Parser},
+    token::TokenKind,
+};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Insert<'a> {
+    pub table: TableRef<'a>,
+    pub columns: Vec<Ident<'a>>,
+    pub source: InsertSource<'a>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum InsertSource<'a> {
+    Values(Vec<Vec<Expr<'a>>>),
+    Query(Box<Query<'a>>),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Update<'a> {
+    pub table: TableRef<'a>,
+    pub assignments: Vec<Assignment<'a>>,
+    pub where_clause: Option<Expr<'a>>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Assignment<'a> {
+    pub column: Ident<'a>,
+    pub value: Expr<'a>,
+    pub span: Span,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Delete<'a> {
+    pub table: TableRef<'a>,
+    pub where_clause: Option<Expr<'a>>,
+    pub span: Span,
+}
+
+fn write_comma_separated<T: fmt::Display>(f: &mut fmt::Formatter<'_>, items: &[T]) -> fmt::Result {
+    for (i, item) in items.iter().enumerate() {
+        if i > 0 {
+            write!(f, ", ")?;
+        }
+        write!(f, "{}", item)?;
+    }
+    Ok(())
+}
+
+impl<'a> fmt::Display for Insert<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "INSERT INTO {}", self.table)?;
+
+        if !self.columns.is_empty() {
+            write!(f, " (")?;
+            write_comma_separated(f, &self.columns)?;
+            write!(f, ")")?;
+        }
+
+        write!(f, " {}", self.source)
+    }
+}
+
+impl<'a> fmt::Display for InsertSource<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            InsertSource::Values(rows) => {
+                write!(f, "VALUES ")?;
+                for (i, row) in rows.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ", ")?;
+                    }
+                    write!(f, "(")?;
+                    write_comma_separated(f, row)?;
+                    write!(f, ")")?;
+                }
+                Ok(())
+            }
+            InsertSource::Query(query) => write!(f, "{}", query),
+        }
+    }
+}
+
+impl<'a> fmt::Display for Update<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "UPDATE {} SET ", self.table)?;
+        write_comma_separated(f, &self.assignments)?;
+
+        if let Some(where_clause) = &self.where_clause {
+            write!(f, " WHERE {}", where_clause)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> fmt::Display for Assignment<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} = {}", self.column, self.value)
+    }
+}
+
+impl<'a> fmt::Display for Delete<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "DELETE FROM {}", self.table)?;
+
+        if let Some(where_clause) = &self.where_clause {
+            write!(f, " WHERE {}", where_clause)?;
+        }
+
+        Ok(())
+    }
+}
+
+impl<'a> Parser<'_, 'a> {
+    /// Parse `INSERT INTO table [(columns)] VALUES (...), ...` or `INSERT INTO table [(columns)] query`
+    pub fn parse_insert(&mut self) -> ParseResult<Insert<'a>> {
+        let start = self.start();
+        self.expect(TokenKind::Insert)?;
+        self.expect(TokenKind::Into)?;
+        let table = self.parse_table_name()?;
+
+        let columns = if self.try_consume(TokenKind::LeftParen) {
+            let columns = self.parse_ident_list()?;
+            self.expect(TokenKind::RightParen)?;
+            columns
+        } else {
+            Vec::new()
+        };
+
+        let source = if self.try_consume(TokenKind::Values) {
+            let mut rows = vec![self.parse_values_row()?];
+            while self.try_consume(TokenKind::Comma) {
+                rows.push(self.parse_values_row()?);
+            }
+            InsertSource::Values(rows)
+        } else {
+            match self.current().map(|t| t.kind) {
+                Some(TokenKind::Select | TokenKind::With) => {
+                    InsertSource::Query(Box::new(self.parse_query()?))
+                }
+                _ => return Err(self.error_expected_one_of(&["VALUES", "SELECT", "WITH"])),
+            }
+        };
+
+        Ok(Insert {
+            table,
+            columns,
+            source,
+            span: self.span_from(start),
+        })
+    }
+
+    fn parse_values_row(&mut self) -> ParseResult<Vec<Expr<'a>>> {
+        self.expect(TokenKind::LeftParen)?;
+        let row = self.parse_expr_list()?;
+        self.expect(TokenKind::RightParen)?;
+        Ok(row)
+    }
+
+    /// Parse `UPDATE table SET column = expr, ... [WHERE expr]`
+    pub fn parse_update(&mut self) -> ParseResult<Update<'a>> {
+        let start = self.start();
+        self.expect(TokenKind::Update)?;
+        let table = self.parse_table_ref()?;
+        self.expect(TokenKind::Set)?;
+
+        let mut assignments = vec![self.parse_assignment()?];
+        while self.try_consume(TokenKind::Comma) {
+            assignments.push(self.parse_assignment()?);
+        }
+
+        let where_clause = if self.try_consume(TokenKind::Where) {
+            Some(self.parse_expr()?)
+        } else {
+            None
+        };
+
+        Ok(Update {
+            table,
+            assignments,
+            where_clause,
+            span: self.span_from(start),
+        })
+    }
+
+    fn parse_assignment(&mut self) -> ParseResult<Assignment<'a>> {
+        let start = self.start();
+        let column = self.parse_ident()?;
+        self.expect(TokenKind::Equal)?;
+        let value = self.parse_expr()?;
+        Ok(Assignment {
+            column,
+            value,
+            span: self.span_from(start),
+        })
+    }
+
+    /// Parse `DELETE FROM table [WHERE expr]`
+    pub fn parse_delete(&mut self) -> ParseResult<Delete<'a>> {
+        let start = self.start();
+        self.expect(TokenKind::Delete)?;
+        self.expect(TokenKind::From)?;
+        let table = self.parse_table_ref()?;
+
+        let where_clause = if self.try_consume(TokenKind::Where) {
+            Some(self.parse_expr()?)
+        } else {
+            None
+        };
+
+        Ok(Delete {
+            table,
+            where_clause,
+            span: self.span_from(start),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::error::Backtrace;
+    use crate::token::tokenize;
+
+    #[test]
+    fn test_insert_values_and_select() {
+        let input = "INSERT INTO users (id, name) VALUES (1, 'ann'), (2, 'bob')";
+        let tokens = tokenize(input);
+        let backtrace = Backtrace::new();
+        let mut parser = Parser::new(&tokens, &backtrace, input);
+
+        let insert = parser.parse_insert().unwrap();
+
+        let columns: Vec<_> = insert
+            .columns
+            .iter()
+            .map(|c| &input[c.span.range()])
+            .collect();
+        assert_eq!(columns, ["id", "name"]);
+        assert_eq!(&input[insert.table.span.range()], "users");
+        assert!(matches!(&insert.source, InsertSource::Values(rows) if rows.len() == 2));
+        assert_eq!(insert.to_string(), input);
+
+        let input = "INSERT INTO archive SELECT * FROM users WHERE id > 10";
+        let tokens = tokenize(input);
+        let backtrace = Backtrace::new();
+        let mut parser = Parser::new(&tokens, &backtrace, input);
+
+        let insert = parser.parse_insert().unwrap();
+
+        assert!(insert.columns.is_empty());
+        assert!(matches!(insert.source, InsertSource::Query(_)));
+    }
+
+    #[test]
+    fn test_update_and_delete() {
+        let input = "UPDATE users SET name = 'x', age = age + 1 WHERE id = 3";
+        let tokens = tokenize(input);
+        let backtrace = Backtrace::new();
+        let mut parser = Parser::new(&tokens, &backtrace, input);
+
+        let update = parser.parse_update().unwrap();
+
+        assert_eq!(update.assignments.len(), 2);
+        assert_eq!(
+            update.to_string(),
+            "UPDATE users SET name = 'x', age = age + 1 WHERE id = 3"
+        );
+
+        let input = "DELETE FROM users";
+        let tokens = tokenize(input);
+        let backtrace = Backtrace::new();
+        let mut parser = Parser::new(&tokens, &backtrace, input);
+
+        let delete = parser.parse_delete().unwrap();
+
+        assert_eq!(delete.table.name, "users");
+        assert!(delete.where_clause.is_none());
+    }
+}
@@ -3,6 +3,8 @@ use std::{cell::RefCell, fmt::Display};
 use colored::Colorize;
 use strsim::jaro_winkler;
 
+use crate::ast::Span;
+
 #[derive(Debug, Clone)]
 pub struct ParseError {
     pub message: String,
@@ -82,10 +84,10 @@ impl Backtrace {
                         line,
                         column,
                     };
-                } else if pos == existing.furthest_pos {
-                    if !existing.expected.contains(&expected.to_string()) {
-                        existing.expected.push(expected.to_string())
-                    }
+                } else if pos == existing.furthest_pos
+                    && !existing.expected.contains(&expected.to_string())
+                {
+                    existing.expected.push(expected.to_string())
                 }
             }
         }
@@ -105,7 +107,7 @@ impl Backtrace {
                 let suggestion = inner
                     .found
                     .as_ref()
-                    .and_then(|found| suggest_keyword(&found));
+                    .and_then(|found| suggest_keyword(found));
                 let context = get_error_context(input, inner.furthest_pos);
                 let expected_str = if inner.expected.len() == 1 {
                     inner.expected[0].clone()
@@ -140,6 +142,11 @@ fn suggest_keyword(input: &str) -> Option<String> {
         "INSERT",
         "UPDATE",
         "DELETE",
+        "INTO",
+        "VALUES",
+        "SET",
+        "CREATE",
+        "TABLE",
         "UNION",
         "ALL",
         "AND",
@@ -159,16 +166,29 @@ fn suggest_keyword(input: &str) -> Option<String> {
         "OFFSET",
     ];
 
+    suggest_similar(input, KEYWORDS.iter().copied())
+}
+
+/// Pick the candidate closest to `input` (case-insensitively), if any is close enough
+pub(crate) fn suggest_similar<'c>(
+    input: &str,
+    candidates: impl IntoIterator<Item = &'c str>,
+) -> Option<String> {
     let input_upper = input.to_uppercase();
-    KEYWORDS
-        .iter()
-        .map(|&keyword| (keyword, jaro_winkler(&input_upper, keyword)))
+    candidates
+        .into_iter()
+        .map(|candidate| {
+            (
+                candidate,
+                jaro_winkler(&input_upper, &candidate.to_uppercase()),
+            )
+        })
         .filter(|(_, score)| *score > 0.8)
         .max_by(|a, b| a.1.partial_cmp(&b.1).unwrap())
-        .map(|(keyword, _)| keyword.to_string())
+        .map(|(candidate, _)| candidate.to_string())
 }
 
-fn position_to_line_col(input: &str, pos: usize) -> (usize, usize) {
+pub(crate) fn position_to_line_col(input: &str, pos: usize) -> (usize, usize) {
     let mut line = 1;
     let mut col = 1;
 
@@ -188,23 +208,103 @@ fn position_to_line_col(input: &str, pos: usize) -> (usize, usize) {
     (line, col)
 }
 
-fn get_error_context(input: &str, pos: usize) -> Option<String> {
+pub(crate) fn get_error_context(input: &str, pos: usize) -> Option<String> {
+    get_span_context(input, Span::new(pos, pos))
+}
+
+/// Quote the lines covered by `span`, underlining the covered part of each one
+pub(crate) fn get_span_context(input: &str, span: Span) -> Option<String> {
     let lines: Vec<&str> = input.lines().collect();
-    let (line_num, col) = position_to_line_col(input, pos);
+    let (start_line, start_col) = position_to_line_col(input, span.start);
+    let (end_line, end_col) = position_to_line_col(input, span.end.max(span.start));
 
-    if line_num > 0 && line_num <= lines.len() {
+    if start_line == 0 || start_line > lines.len() {
+        return None;
+    }
+
+    let end_line = end_line.min(lines.len());
+    let width = end_line.to_string().len();
+    let mut quoted = Vec::new();
+
+    for line_num in start_line..=end_line {
         let line = lines[line_num - 1];
-        let mut result = String::new();
+        let from = if line_num == start_line {
+            start_col - 1
+        } else {
+            line.chars().take_while(|c| c.is_whitespace()).count()
+        };
+        let to = if line_num == end_line {
+            end_col - 1
+        } else {
+            line.chars().count()
+        };
+
+        // A span ending right after a newline doesn't cover the next line
+        if line_num > start_line && to == 0 {
+            break;
+        }
+
+        quoted.push(format!(
+            "  {:>width$} | {}\n  {} | {}{}",
+            line_num,
+            line,
+            " ".repeat(width),
+            " ".repeat(from),
+            "^".repeat(to.saturating_sub(from).max(1)).red(),
+        ));
+    }
+
+    Some(quoted.join("\n"))
+}
+
+/// An error or warning pointing at a range of the original SQL
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub title: String,
+    pub message: String,
+    pub span: Span,
+    pub suggestion: Option<String>,
+}
+
+impl Diagnostic {
+    pub fn new(title: impl Into<String>, message: impl Into<String>, span: Span) -> Self {
+        Self {
+            title: title.into(),
+            message: message.into(),
+            span,
+            suggestion: None,
+        }
+    }
 
-        result.push_str(&format!("  {} | {}\n", line_num, line));
+    pub fn with_suggestion(mut self, suggestion: impl Into<String>) -> Self {
+        self.suggestion = Some(suggestion.into());
+        self
+    }
 
-        result.push_str(&format!("  {} | ", " ".repeat(line_num.to_string().len())));
-        result.push_str(&" ".repeat(col - 1));
-        result.push_str(&"^".red().to_string());
+    /// Render in the same layout as `ParseError`, underlining the span in `input`
+    pub fn render(&self, input: &str) -> String {
+        let (line, column) = position_to_line_col(input, self.span.start);
+        let mut out = format!(
+            "{} at line {}:{}\n  {}\n",
+            self.title.red().bold(),
+            line,
+            column,
+            self.message
+        );
+
+        if let Some(ref suggestion) = self.suggestion {
+            out.push_str(&format!(
+                " {} {}\n",
+                "Did you mean:".yellow(),
+                suggestion.green()
+            ));
+        }
+
+        if let Some(context) = get_span_context(input, self.span) {
+            out.push_str(&format!("\n{}\n", context));
+        }
 
-        Some(result)
-    } else {
-        None
+        out
     }
 }
 
@@ -358,4 +458,44 @@ mod tests {
         assert_eq!(suggest_keyword("WHEER"), Some("WHERE".to_string()));
         assert_eq!(suggest_keyword("xyz"), None);
     }
+
+    /// strip_colors removes the ANSI escape sequences colored adds when it writes to a terminal.
+    fn strip_colors(text: &str) -> String {
+        let mut plain = String::new();
+        let mut chars = text.chars();
+        while let Some(character) = chars.next() {
+            if character == '\x1b' {
+                chars
+                    .by_ref()
+                    .find(|character| character.is_ascii_alphabetic());
+            } else {
+                plain.push(character);
+            }
+        }
+        plain
+    }
+
+    #[test]
+    fn test_span_context_underlines_range() {
+        let input = "SELECT a\nFROM users\nWHERE age > 'x'";
+        let start = input.find("age").unwrap();
+        let context =
+            strip_colors(&get_span_context(input, Span::new(start, input.len())).unwrap());
+        assert_eq!(context, "  3 | WHERE age > 'x'\n    |       ^^^^^^^^^");
+
+        let start = input.find('a').unwrap();
+        let end = input.find("users").unwrap() + "users".len();
+        let context = strip_colors(&get_span_context(input, Span::new(start, end)).unwrap());
+        assert_eq!(
+            context,
+            "  1 | SELECT a\n    |        ^\n  2 | FROM users\n    | ^^^^^^^^^^"
+        );
+
+        let diagnostic = Diagnostic::new("Type error", "bad comparison", Span::new(20, 25))
+            .with_suggestion("age > 1");
+        assert_eq!(
+            strip_colors(&diagnostic.render(input)),
+            "Type error at line 3:1\n  bad comparison\n Did you mean: age > 1\n\n  3 | WHERE age > 'x'\n    | ^^^^^\n"
+        );
+    }
 }
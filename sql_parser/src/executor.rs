@@ -0,0 +1,1425 @@
+use std::{cmp::Ordering, collections::HashMap, fmt};
+
+use thiserror::Error;
+
+use crate::{
+    ast::{
+        CTE, Join, JoinConstraint, JoinKind, OrderByExpr, Query, SelectStmt, TableFactor,
+        TableWithJoins, With,
+    },
+    ddl::DataType,
+    expr::{BinaryOp, Expr, ExprKind, Literal, UnaryOp},
+    visitor::{Visitor, walk_expr},
+};
+
+/// Upper bound on recursive CTE iterations, so a runaway recursion fails instead of hanging
+const MAX_RECURSION_DEPTH: usize = 10_000;
+
+type Row = Vec<Value>;
+
+/// A group's representative row together with every row in the group
+type Group = (Row, Vec<Row>);
+
+const AGGREGATES: &[&str] = &["COUNT", "SUM", "AVG", "MIN", "MAX"];
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Integer(i64),
+    Float(f64),
+    Boolean(bool),
+    Text(String),
+}
+
+impl fmt::Display for Value {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Value::Null => write!(f, "NULL"),
+            Value::Integer(n) => write!(f, "{}", n),
+            Value::Float(fl) => write!(f, "{}", fl),
+            Value::Boolean(b) => write!(f, "{}", b),
+            Value::Text(s) => write!(f, "{}", s),
+        }
+    }
+}
+
+impl From<i64> for Value {
+    fn from(n: i64) -> Self {
+        Value::Integer(n)
+    }
+}
+
+impl From<f64> for Value {
+    fn from(f: f64) -> Self {
+        Value::Float(f)
+    }
+}
+
+impl From<bool> for Value {
+    fn from(b: bool) -> Self {
+        Value::Boolean(b)
+    }
+}
+
+impl From<&str> for Value {
+    fn from(s: &str) -> Self {
+        Value::Text(s.to_string())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct Table {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+/// In-memory tables, keyed case-insensitively by name
+#[derive(Debug, Clone, Default)]
+pub struct Database {
+    tables: HashMap<String, Table>,
+}
+
+impl Database {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add_table(&mut self, name: &str, columns: &[&str], rows: Vec<Vec<Value>>) {
+        let table = Table {
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            rows,
+        };
+        self.tables.insert(name.to_lowercase(), table);
+    }
+
+    pub fn table(&self, name: &str) -> Option<&Table> {
+        self.tables.get(&name.to_lowercase())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Error)]
+pub enum ExecError {
+    #[error("Unknown table `{0}`")]
+    UnknownTable(String),
+    #[error("Unknown column `{0}`")]
+    UnknownColumn(String),
+    #[error("Column reference `{0}` is ambiguous")]
+    AmbiguousColumn(String),
+    #[error("Unknown function `{0}`")]
+    UnknownFunction(String),
+    #[error("Type mismatch: {0}")]
+    TypeMismatch(String),
+    #[error("Aggregate function `{0}` is not allowed here")]
+    MisplacedAggregate(String),
+    #[error("Division by zero")]
+    DivisionByZero,
+    #[error("Integer overflow")]
+    Overflow,
+    #[error("Each UNION query must have the same number of columns")]
+    UnionArity,
+    #[error("Subquery must return exactly one column")]
+    SubqueryArity,
+    #[error("Recursive CTE `{0}` did not terminate")]
+    RecursionLimit(String),
+    #[error("{0}")]
+    Unsupported(String),
+}
+
+pub type ExecResult<T> = Result<T, ExecError>;
+
+/// Run `query` against `db` and return its result rows
+pub fn execute_query(db: &Database, query: &Query) -> ExecResult<Table> {
+    let mut executor = Executor {
+        db,
+        ctes: Vec::new(),
+    };
+    executor.execute_query(query, None)
+}
+
+#[derive(Debug, Clone)]
+struct ColumnRef {
+    table: Option<String>,
+    name: String,
+    /// Right-hand copy of a `USING` column; only reachable through its qualifier
+    hidden: bool,
+}
+
+impl ColumnRef {
+    fn matches(&self, table: Option<&str>, name: &str) -> bool {
+        if !self.name.eq_ignore_ascii_case(name) {
+            return false;
+        }
+        match table {
+            Some(table) => self
+                .table
+                .as_deref()
+                .is_some_and(|t| t.eq_ignore_ascii_case(table)),
+            None => !self.hidden,
+        }
+    }
+}
+
+/// An intermediate relation produced while evaluating FROM
+struct Rows {
+    columns: Vec<ColumnRef>,
+    rows: Vec<Vec<Value>>,
+}
+
+/// The row an expression is evaluated against, plus its group and any enclosing query's row
+struct Env<'r> {
+    columns: &'r [ColumnRef],
+    row: &'r [Value],
+    group: Option<&'r [Vec<Value>]>,
+    outer: Option<&'r Env<'r>>,
+}
+
+impl Env<'_> {
+    fn lookup(&self, table: Option<&str>, name: &str) -> ExecResult<Value> {
+        let mut found = None;
+        for (i, column) in self.columns.iter().enumerate() {
+            if column.matches(table, name) {
+                if found.is_some() {
+                    return Err(ExecError::AmbiguousColumn(name.to_string()));
+                }
+                found = Some(i);
+            }
+        }
+
+        match (found, self.outer) {
+            (Some(i), _) => Ok(self.row[i].clone()),
+            (None, Some(outer)) => outer.lookup(table, name),
+            (None, None) => Err(ExecError::UnknownColumn(match table {
+                Some(table) => format!("{}.{}", table, name),
+                None => name.to_string(),
+            })),
+        }
+    }
+}
+
+struct AggregateFinder {
+    found: bool,
+}
+
+impl<'a> Visitor<'a> for AggregateFinder {
+    fn visit_expr(&mut self, expr: &Expr<'a>) {
+        if let ExprKind::Function { name, .. } = &expr.kind
+            && is_aggregate(name)
+        {
+            self.found = true;
+        }
+        walk_expr(self, expr);
+    }
+
+    // Aggregates inside a subquery belong to the subquery
+    fn visit_query(&mut self, _query: &Query<'a>) {}
+}
+
+fn is_aggregate(name: &str) -> bool {
+    AGGREGATES.iter().any(|agg| agg.eq_ignore_ascii_case(name))
+}
+
+fn contains_aggregate(expr: &Expr) -> bool {
+    let mut finder = AggregateFinder { found: false };
+    finder.visit_expr(expr);
+    finder.found
+}
+
+struct Executor<'d> {
+    db: &'d Database,
+    /// Materialized CTEs, one frame per enclosing WITH, innermost last
+    ctes: Vec<HashMap<String, Table>>,
+}
+
+impl<'d> Executor<'d> {
+    fn execute_query(&mut self, query: &Query, outer: Option<&Env>) -> ExecResult<Table> {
+        match query {
+            Query::Select(select) => self.execute_select(select, outer),
+            Query::With { with, query } => {
+                self.ctes.push(HashMap::new());
+                let result = self
+                    .execute_with(with, outer)
+                    .and_then(|_| self.execute_query(query, outer));
+                self.ctes.pop();
+                result
+            }
+            Query::Union {
+                left,
+                all,
+                right,
+                order_by,
+                limit,
+                offset,
+            } => {
+                let mut left = self.execute_query(left, outer)?;
+                let right = self.execute_query(right, outer)?;
+                if left.columns.len() != right.columns.len() {
+                    return Err(ExecError::UnionArity);
+                }
+                left.rows.extend(right.rows);
+                if !all {
+                    left.rows = distinct(left.rows);
+                }
+                self.order_union(left, order_by, limit.as_ref(), offset.as_ref(), outer)
+            }
+        }
+    }
+
+    /// Apply a UNION's ORDER BY, OFFSET and LIMIT, whose sort keys see only the union's
+    /// output columns
+    fn order_union(
+        &mut self,
+        mut table: Table,
+        order_by: &[OrderByExpr],
+        limit: Option<&Expr>,
+        offset: Option<&Expr>,
+        outer: Option<&Env>,
+    ) -> ExecResult<Table> {
+        if order_by.is_empty() && limit.is_none() && offset.is_none() {
+            return Ok(table);
+        }
+
+        let columns: Vec<ColumnRef> = table
+            .columns
+            .iter()
+            .map(|name| ColumnRef {
+                table: None,
+                name: name.clone(),
+                hidden: false,
+            })
+            .collect();
+        let mut output = Vec::new();
+        for row in table.rows {
+            let env = Env {
+                columns: &columns,
+                row: &row,
+                group: None,
+                outer,
+            };
+            let keys = self.sort_keys(order_by, &row, &env)?;
+            output.push((keys, row));
+        }
+        table.rows = self.order_and_limit(order_by, limit, offset, output, outer)?;
+        Ok(table)
+    }
+
+    fn execute_with(&mut self, with: &With, outer: Option<&Env>) -> ExecResult<()> {
+        for cte in &with.ctes {
+            let table = match (&*cte.query, with.recursive) {
+                (
+                    Query::Union {
+                        left,
+                        all,
+                        right,
+                        order_by,
+                        limit,
+                        offset,
+                    },
+                    true,
+                ) => {
+                    let table = self.execute_recursive_cte(cte, left, *all, right, outer)?;
+                    self.order_union(table, order_by, limit.as_ref(), offset.as_ref(), outer)?
+                }
+                (query, _) => {
+                    let table = self.execute_query(query, outer)?;
+                    rename_columns(cte, table)
+                }
+            };
+            self.register_cte(cte.name, table);
+        }
+        Ok(())
+    }
+
+    /// Evaluate the anchor once, then feed each round's new rows back into the
+    /// recursive member until it produces nothing new
+    fn execute_recursive_cte(
+        &mut self,
+        cte: &CTE,
+        anchor: &Query,
+        all: bool,
+        recursive: &Query,
+        outer: Option<&Env>,
+    ) -> ExecResult<Table> {
+        let mut result = rename_columns(cte, self.execute_query(anchor, outer)?);
+        if !all {
+            result.rows = distinct(result.rows);
+        }
+        let mut working = result.rows.clone();
+
+        for _ in 0..MAX_RECURSION_DEPTH {
+            if working.is_empty() {
+                return Ok(result);
+            }
+
+            self.register_cte(
+                cte.name,
+                Table {
+                    columns: result.columns.clone(),
+                    rows: working,
+                },
+            );
+            let produced = self.execute_query(recursive, outer)?;
+            if produced.columns.len() != result.columns.len() {
+                return Err(ExecError::UnionArity);
+            }
+
+            working = if all {
+                produced.rows
+            } else {
+                distinct(produced.rows)
+                    .into_iter()
+                    .filter(|row| !result.rows.contains(row))
+                    .collect()
+            };
+            result.rows.extend(working.iter().cloned());
+        }
+
+        Err(ExecError::RecursionLimit(cte.name.to_string()))
+    }
+
+    fn register_cte(&mut self, name: &str, table: Table) {
+        if let Some(frame) = self.ctes.last_mut() {
+            frame.insert(name.to_lowercase(), table);
+        }
+    }
+
+    fn lookup_table(&self, name: &str) -> ExecResult<Table> {
+        let key = name.to_lowercase();
+        self.ctes
+            .iter()
+            .rev()
+            .find_map(|frame| frame.get(&key))
+            .or_else(|| self.db.tables.get(&key))
+            .cloned()
+            .ok_or_else(|| ExecError::UnknownTable(name.to_string()))
+    }
+
+    fn execute_select(&mut self, select: &SelectStmt, outer: Option<&Env>) -> ExecResult<Table> {
+        let source = self.execute_from(&select.from, outer)?;
+
+        let mut rows = Vec::new();
+        for row in source.rows {
+            let keep = match &select.where_clause {
+                Some(predicate) => {
+                    let env = Env {
+                        columns: &source.columns,
+                        row: &row,
+                        group: None,
+                        outer,
+                    };
+                    self.eval(predicate, &env)? == Value::Boolean(true)
+                }
+                None => true,
+            };
+            if keep {
+                rows.push(row);
+            }
+        }
+
+        let aggregate = !select.group_by.is_empty()
+            || select.having.is_some()
+            || select.projection.iter().any(contains_aggregate);
+
+        // Each unit is a representative row plus, when aggregating, the rows of its group
+        let units: Vec<(Row, Option<Vec<Row>>)> = if aggregate {
+            self.group_rows(select, &source.columns, rows, outer)?
+                .into_iter()
+                .map(|(row, group)| (row, Some(group)))
+                .collect()
+        } else {
+            rows.into_iter().map(|row| (row, None)).collect()
+        };
+
+        let mut columns = Vec::new();
+        for expr in &select.projection {
+            match &expr.kind {
+                ExprKind::Star => columns.extend(
+                    source
+                        .columns
+                        .iter()
+                        .filter(|c| !c.hidden)
+                        .map(|c| c.name.clone()),
+                ),
+                ExprKind::Column(name) | ExprKind::QualifiedColumn { column: name, .. } => {
+                    columns.push(name.to_string())
+                }
+                ExprKind::Function { name, .. } => columns.push(name.to_string()),
+                _ => columns.push("?column?".to_string()),
+            }
+        }
+
+        let mut output = Vec::new();
+        for (row, group) in &units {
+            let env = Env {
+                columns: &source.columns,
+                row,
+                group: group.as_deref(),
+                outer,
+            };
+
+            if let Some(having) = &select.having
+                && self.eval(having, &env)? != Value::Boolean(true)
+            {
+                continue;
+            }
+
+            let mut out = Vec::new();
+            for expr in &select.projection {
+                if let ExprKind::Star = expr.kind {
+                    out.extend(
+                        source
+                            .columns
+                            .iter()
+                            .zip(row)
+                            .filter(|(c, _)| !c.hidden)
+                            .map(|(_, v)| v.clone()),
+                    );
+                } else {
+                    out.push(self.eval(expr, &env)?);
+                }
+            }
+
+            let keys = self.sort_keys(&select.order_by, &out, &env)?;
+            output.push((keys, out));
+        }
+
+        let rows = self.order_and_limit(
+            &select.order_by,
+            select.limit.as_ref(),
+            select.offset.as_ref(),
+            output,
+            outer,
+        )?;
+        Ok(Table { columns, rows })
+    }
+
+    /// The ORDER BY keys of the output row `out`, produced from the row in `env`
+    fn sort_keys(&mut self, order_by: &[OrderByExpr], out: &Row, env: &Env) -> ExecResult<Row> {
+        let mut keys = Vec::new();
+        for order in order_by {
+            keys.push(match order.expr.kind {
+                // ORDER BY <n> refers to the n-th output column
+                ExprKind::Literal(Literal::Number(n)) if n >= 1 && (n as usize) <= out.len() => {
+                    out[n as usize - 1].clone()
+                }
+                _ => self.eval(&order.expr, env)?,
+            });
+        }
+        Ok(keys)
+    }
+
+    /// Sort `(keys, row)` pairs by `order_by`, then apply `offset` and `limit`
+    fn order_and_limit(
+        &mut self,
+        order_by: &[OrderByExpr],
+        limit: Option<&Expr>,
+        offset: Option<&Expr>,
+        mut output: Vec<(Row, Row)>,
+        outer: Option<&Env>,
+    ) -> ExecResult<Vec<Row>> {
+        if !order_by.is_empty() {
+            let mut failure = None;
+            output.sort_by(|(a, _), (b, _)| {
+                compare_sort_keys(order_by, a, b).unwrap_or_else(|err| {
+                    failure.get_or_insert(err);
+                    Ordering::Equal
+                })
+            });
+            if let Some(err) = failure {
+                return Err(err);
+            }
+        }
+
+        let offset = self.eval_count(offset, outer)?.unwrap_or(0);
+        let limit = self.eval_count(limit, outer)?;
+
+        Ok(output
+            .into_iter()
+            .map(|(_, row)| row)
+            .skip(offset)
+            .take(limit.unwrap_or(usize::MAX))
+            .collect())
+    }
+
+    fn group_rows(
+        &mut self,
+        select: &SelectStmt,
+        columns: &[ColumnRef],
+        rows: Vec<Vec<Value>>,
+        outer: Option<&Env>,
+    ) -> ExecResult<Vec<Group>> {
+        if select.group_by.is_empty() {
+            // A whole-table aggregate yields one row even when there is no input
+            let representative = rows
+                .first()
+                .cloned()
+                .unwrap_or_else(|| vec![Value::Null; columns.len()]);
+            return Ok(vec![(representative, rows)]);
+        }
+
+        let mut groups: Vec<Group> = Vec::new();
+        let mut keys: Vec<Vec<Value>> = Vec::new();
+        for row in rows {
+            let env = Env {
+                columns,
+                row: &row,
+                group: None,
+                outer,
+            };
+            let key = select
+                .group_by
+                .iter()
+                .map(|expr| self.eval(expr, &env))
+                .collect::<ExecResult<Vec<_>>>()?;
+
+            match keys.iter().position(|k| *k == key) {
+                Some(i) => groups[i].1.push(row),
+                None => {
+                    keys.push(key);
+                    groups.push((row.clone(), vec![row]));
+                }
+            }
+        }
+        Ok(groups)
+    }
+
+    fn eval_count(
+        &mut self,
+        expr: Option<&Expr>,
+        outer: Option<&Env>,
+    ) -> ExecResult<Option<usize>> {
+        let Some(expr) = expr else {
+            return Ok(None);
+        };
+        let env = Env {
+            columns: &[],
+            row: &[],
+            group: None,
+            outer,
+        };
+        match self.eval(expr, &env)? {
+            Value::Null => Ok(None),
+            Value::Integer(n) if n >= 0 => Ok(Some(n as usize)),
+            other => Err(ExecError::TypeMismatch(format!(
+                "LIMIT/OFFSET must be a non-negative integer, found {}",
+                other
+            ))),
+        }
+    }
+
+    fn execute_from(&mut self, from: &[TableWithJoins], outer: Option<&Env>) -> ExecResult<Rows> {
+        let mut result = Rows {
+            columns: Vec::new(),
+            rows: vec![Vec::new()],
+        };
+
+        for table in from {
+            let mut rows = self.execute_table_factor(&table.relation, outer)?;
+            for join in &table.joins {
+                rows = self.execute_join(rows, join, outer)?;
+            }
+            result = cross_product(result, rows);
+        }
+
+        Ok(result)
+    }
+
+    fn execute_table_factor(
+        &mut self,
+        factor: &TableFactor,
+        outer: Option<&Env>,
+    ) -> ExecResult<Rows> {
+        let (table, qualifier) = match factor {
+            TableFactor::Table(table_ref) => (
+                self.lookup_table(table_ref.name)?,
+                Some(table_ref.alias.unwrap_or(table_ref.name)),
+            ),
+            TableFactor::Derived {
+                subquery, alias, ..
+            } => (self.execute_query(subquery, outer)?, *alias),
+        };
+
+        Ok(Rows {
+            columns: table
+                .columns
+                .into_iter()
+                .map(|name| ColumnRef {
+                    table: qualifier.map(str::to_string),
+                    name,
+                    hidden: false,
+                })
+                .collect(),
+            rows: table.rows,
+        })
+    }
+
+    fn execute_join(&mut self, left: Rows, join: &Join, outer: Option<&Env>) -> ExecResult<Rows> {
+        let mut right = self.execute_table_factor(&join.relation, outer)?;
+        if join.kind == JoinKind::Cross {
+            return Ok(cross_product(left, right));
+        }
+
+        // (left index, right index) of every USING column
+        let mut using = Vec::new();
+        if let JoinConstraint::Using(names) = &join.constraint {
+            for name in names {
+                let find = |columns: &[ColumnRef]| {
+                    columns
+                        .iter()
+                        .position(|c| c.matches(None, name.name))
+                        .ok_or_else(|| ExecError::UnknownColumn(name.to_string()))
+                };
+                let pair = (find(&left.columns)?, find(&right.columns)?);
+                right.columns[pair.1].hidden = true;
+                using.push(pair);
+            }
+        }
+
+        let columns: Vec<ColumnRef> = left.columns.iter().chain(&right.columns).cloned().collect();
+        let left_width = left.columns.len();
+        let right_width = right.columns.len();
+
+        let mut rows = Vec::new();
+        let mut right_matched = vec![false; right.rows.len()];
+        for left_row in &left.rows {
+            let mut matched = false;
+            for (j, right_row) in right.rows.iter().enumerate() {
+                let mut row = left_row.clone();
+                row.extend(right_row.iter().cloned());
+
+                let keep = match &join.constraint {
+                    JoinConstraint::On(predicate) => {
+                        let env = Env {
+                            columns: &columns,
+                            row: &row,
+                            group: None,
+                            outer,
+                        };
+                        self.eval(predicate, &env)? == Value::Boolean(true)
+                    }
+                    JoinConstraint::Using(_) => using.iter().try_fold(true, |acc, &(l, r)| {
+                        Ok::<_, ExecError>(
+                            acc && sql_compare(&left_row[l], &right_row[r])?
+                                == Some(Ordering::Equal),
+                        )
+                    })?,
+                    JoinConstraint::None => true,
+                };
+
+                if keep {
+                    matched = true;
+                    right_matched[j] = true;
+                    rows.push(row);
+                }
+            }
+
+            if !matched && matches!(join.kind, JoinKind::Left | JoinKind::Full) {
+                let mut row = left_row.clone();
+                row.extend(std::iter::repeat_n(Value::Null, right_width));
+                rows.push(row);
+            }
+        }
+
+        if matches!(join.kind, JoinKind::Right | JoinKind::Full) {
+            for (right_row, _) in right.rows.iter().zip(&right_matched).filter(|(_, m)| !**m) {
+                let mut row = vec![Value::Null; left_width];
+                // The merged USING column takes the value from whichever side has one
+                for &(l, r) in &using {
+                    row[l] = right_row[r].clone();
+                }
+                row.extend(right_row.iter().cloned());
+                rows.push(row);
+            }
+        }
+
+        Ok(Rows { columns, rows })
+    }
+
+    fn eval(&mut self, expr: &Expr, env: &Env) -> ExecResult<Value> {
+        match &expr.kind {
+            ExprKind::Column(name) => env.lookup(None, name),
+            ExprKind::QualifiedColumn { table, column } => env.lookup(Some(table), column),
+            ExprKind::Literal(literal) => Ok(match literal {
+                Literal::Number(n) => Value::Integer(*n),
+                Literal::Float(f) => Value::Float(*f),
+                Literal::String(s) => Value::Text(s.to_string()),
+                Literal::Boolean(b) => Value::Boolean(*b),
+                Literal::Null => Value::Null,
+            }),
+            ExprKind::Binary { left, op, right } => match op {
+                BinaryOp::And => {
+                    let left = as_bool(self.eval(left, env)?)?;
+                    if left == Some(false) {
+                        return Ok(Value::Boolean(false));
+                    }
+                    let right = as_bool(self.eval(right, env)?)?;
+                    Ok(match (left, right) {
+                        (_, Some(false)) => Value::Boolean(false),
+                        (Some(true), Some(true)) => Value::Boolean(true),
+                        _ => Value::Null,
+                    })
+                }
+                BinaryOp::Or => {
+                    let left = as_bool(self.eval(left, env)?)?;
+                    if left == Some(true) {
+                        return Ok(Value::Boolean(true));
+                    }
+                    let right = as_bool(self.eval(right, env)?)?;
+                    Ok(match (left, right) {
+                        (_, Some(true)) => Value::Boolean(true),
+                        (Some(false), Some(false)) => Value::Boolean(false),
+                        _ => Value::Null,
+                    })
+                }
+                _ => {
+                    let left = self.eval(left, env)?;
+                    let right = self.eval(right, env)?;
+                    binary_op(*op, left, right)
+                }
+            },
+            ExprKind::Unary { op, expr } => {
+                let value = self.eval(expr, env)?;
+                match (op, value) {
+                    (_, Value::Null) => Ok(Value::Null),
+                    (UnaryOp::Not, Value::Boolean(b)) => Ok(Value::Boolean(!b)),
+                    (UnaryOp::Minus, Value::Integer(n)) => n
+                        .checked_neg()
+                        .map(Value::Integer)
+                        .ok_or(ExecError::Overflow),
+                    (UnaryOp::Minus, Value::Float(f)) => Ok(Value::Float(-f)),
+                    (UnaryOp::Plus, value @ (Value::Integer(_) | Value::Float(_))) => Ok(value),
+                    (op, value) => Err(ExecError::TypeMismatch(format!(
+                        "cannot apply unary `{}` to {}",
+                        op.to_string().trim(),
+                        value
+                    ))),
+                }
+            }
+            ExprKind::Function {
+                name,
+                distinct,
+                args,
+            } => {
+                if is_aggregate(name) {
+                    self.eval_aggregate(name, *distinct, args, env)
+                } else {
+                    let args = args
+                        .iter()
+                        .map(|arg| self.eval(arg, env))
+                        .collect::<ExecResult<Vec<_>>>()?;
+                    scalar_function(name, args)
+                }
+            }
+            ExprKind::IsNull { expr, negated } => {
+                let is_null = self.eval(expr, env)? == Value::Null;
+                Ok(Value::Boolean(is_null != *negated))
+            }
+            ExprKind::InList {
+                expr,
+                list,
+                negated,
+            } => {
+                let value = self.eval(expr, env)?;
+                let mut candidates = Vec::new();
+                for item in list {
+                    candidates.push(self.eval(item, env)?);
+                }
+                in_values(value, candidates, *negated)
+            }
+            ExprKind::InSubquery {
+                expr,
+                subquery,
+                negated,
+            } => {
+                let value = self.eval(expr, env)?;
+                let table = self.execute_query(subquery, Some(env))?;
+                if table.columns.len() != 1 {
+                    return Err(ExecError::SubqueryArity);
+                }
+                let candidates = table
+                    .rows
+                    .into_iter()
+                    .map(|mut row| row.remove(0))
+                    .collect();
+                in_values(value, candidates, *negated)
+            }
+            ExprKind::Between {
+                expr,
+                low,
+                high,
+                negated,
+            } => {
+                let value = self.eval(expr, env)?;
+                let low = self.eval(low, env)?;
+                let high = self.eval(high, env)?;
+                let above = binary_op(BinaryOp::GreaterEqual, value.clone(), low)?;
+                let below = binary_op(BinaryOp::LessEqual, value, high)?;
+                let result = match (as_bool(above)?, as_bool(below)?) {
+                    (Some(false), _) | (_, Some(false)) => Value::Boolean(false),
+                    (Some(true), Some(true)) => Value::Boolean(true),
+                    _ => Value::Null,
+                };
+                Ok(negate_if(result, *negated))
+            }
+            ExprKind::Like {
+                expr,
+                pattern,
+                negated,
+            } => {
+                let result = match (self.eval(expr, env)?, self.eval(pattern, env)?) {
+                    (Value::Null, _) | (_, Value::Null) => Value::Null,
+                    (Value::Text(text), Value::Text(pattern)) => {
+                        let text: Vec<char> = text.chars().collect();
+                        let pattern: Vec<char> = pattern.chars().collect();
+                        Value::Boolean(like_match(&text, &pattern))
+                    }
+                    (text, pattern) => {
+                        return Err(ExecError::TypeMismatch(format!(
+                            "LIKE requires text operands, found {} and {}",
+                            text, pattern
+                        )));
+                    }
+                };
+                Ok(negate_if(result, *negated))
+            }
+            ExprKind::Case {
+                operand,
+                when_then,
+                else_result,
+            } => {
+                let operand = match operand {
+                    Some(operand) => Some(self.eval(operand, env)?),
+                    None => None,
+                };
+                for (when, then) in when_then {
+                    let when = self.eval(when, env)?;
+                    let hit = match &operand {
+                        Some(operand) => {
+                            binary_op(BinaryOp::Equal, operand.clone(), when)?
+                                == Value::Boolean(true)
+                        }
+                        None => when == Value::Boolean(true),
+                    };
+                    if hit {
+                        return self.eval(then, env);
+                    }
+                }
+                match else_result {
+                    Some(else_result) => self.eval(else_result, env),
+                    None => Ok(Value::Null),
+                }
+            }
+            ExprKind::Cast { expr, data_type } => cast(self.eval(expr, env)?, *data_type),
+            ExprKind::Paren(expr) => self.eval(expr, env),
+            ExprKind::Star => Err(ExecError::Unsupported(
+                "`*` is only allowed in the select list or COUNT(*)".to_string(),
+            )),
+        }
+    }
+
+    fn eval_aggregate(
+        &mut self,
+        name: &str,
+        distinct: bool,
+        args: &[Expr],
+        env: &Env,
+    ) -> ExecResult<Value> {
+        let Some(group) = env.group else {
+            return Err(ExecError::MisplacedAggregate(name.to_string()));
+        };
+        let name = name.to_uppercase();
+
+        if let [
+            Expr {
+                kind: ExprKind::Star,
+                ..
+            },
+        ] = args
+            && name == "COUNT"
+        {
+            return Ok(Value::Integer(group.len() as i64));
+        }
+        let [arg] = args else {
+            return Err(ExecError::Unsupported(format!(
+                "{} takes exactly one argument",
+                name
+            )));
+        };
+
+        let mut values = Vec::new();
+        for row in group {
+            let row_env = Env {
+                columns: env.columns,
+                row,
+                group: None,
+                outer: env.outer,
+            };
+            let value = self.eval(arg, &row_env)?;
+            if value != Value::Null {
+                values.push(value);
+            }
+        }
+        if distinct {
+            values = distinct_values(values);
+        }
+
+        match name.as_str() {
+            "COUNT" => Ok(Value::Integer(values.len() as i64)),
+            "SUM" | "AVG" => {
+                if values.is_empty() {
+                    return Ok(Value::Null);
+                }
+                let count = values.len();
+                let sum = values.into_iter().try_fold(Value::Integer(0), |acc, v| {
+                    binary_op(BinaryOp::Plus, acc, v)
+                })?;
+                if name == "SUM" {
+                    Ok(sum)
+                } else {
+                    binary_op(
+                        BinaryOp::Divide,
+                        cast(sum, DataType::Float)?,
+                        Value::Integer(count as i64),
+                    )
+                }
+            }
+            _ => {
+                let want = if name == "MIN" {
+                    Ordering::Less
+                } else {
+                    Ordering::Greater
+                };
+                let mut best: Option<Value> = None;
+                for value in values {
+                    best = match best {
+                        Some(current) if sql_compare(&value, &current)? != Some(want) => {
+                            Some(current)
+                        }
+                        _ => Some(value),
+                    };
+                }
+                Ok(best.unwrap_or(Value::Null))
+            }
+        }
+    }
+}
+
+fn rename_columns(cte: &CTE, mut table: Table) -> Table {
+    if let Some(names) = &cte.columns {
+        for (column, name) in table.columns.iter_mut().zip(names) {
+            *column = name.to_string();
+        }
+    }
+    table
+}
+
+fn cross_product(left: Rows, right: Rows) -> Rows {
+    let mut rows = Vec::with_capacity(left.rows.len() * right.rows.len());
+    for left_row in &left.rows {
+        for right_row in &right.rows {
+            let mut row = left_row.clone();
+            row.extend(right_row.iter().cloned());
+            rows.push(row);
+        }
+    }
+
+    let mut columns = left.columns;
+    columns.extend(right.columns);
+    Rows { columns, rows }
+}
+
+/// Remove duplicate rows, keeping the first occurrence; NULLs compare equal here
+fn distinct(rows: Vec<Vec<Value>>) -> Vec<Vec<Value>> {
+    let mut unique: Vec<Vec<Value>> = Vec::new();
+    for row in rows {
+        if !unique.contains(&row) {
+            unique.push(row);
+        }
+    }
+    unique
+}
+
+fn distinct_values(values: Vec<Value>) -> Vec<Value> {
+    let mut unique = Vec::new();
+    for value in values {
+        if !unique.contains(&value) {
+            unique.push(value);
+        }
+    }
+    unique
+}
+
+fn compare_sort_keys(order_by: &[OrderByExpr], a: &[Value], b: &[Value]) -> ExecResult<Ordering> {
+    for ((order, a), b) in order_by.iter().zip(a).zip(b) {
+        let asc = order.asc.unwrap_or(true);
+        // NULLs sort as if larger than any value unless told otherwise
+        let nulls_first = order.nulls_first.unwrap_or(!asc);
+
+        let ordering = match (a, b) {
+            (Value::Null, Value::Null) => Ordering::Equal,
+            (Value::Null, _) if nulls_first => Ordering::Less,
+            (Value::Null, _) => Ordering::Greater,
+            (_, Value::Null) if nulls_first => Ordering::Greater,
+            (_, Value::Null) => Ordering::Less,
+            (a, b) => {
+                let ordering = sql_compare(a, b)?.unwrap_or(Ordering::Equal);
+                if asc { ordering } else { ordering.reverse() }
+            }
+        };
+
+        if ordering != Ordering::Equal {
+            return Ok(ordering);
+        }
+    }
+    Ok(Ordering::Equal)
+}
+
+/// Compare two values; `None` when either side is NULL
+fn sql_compare(left: &Value, right: &Value) -> ExecResult<Option<Ordering>> {
+    Ok(match (left, right) {
+        (Value::Null, _) | (_, Value::Null) => None,
+        (Value::Integer(a), Value::Integer(b)) => Some(a.cmp(b)),
+        (Value::Integer(a), Value::Float(b)) => (*a as f64).partial_cmp(b),
+        (Value::Float(a), Value::Integer(b)) => a.partial_cmp(&(*b as f64)),
+        (Value::Float(a), Value::Float(b)) => a.partial_cmp(b),
+        (Value::Text(a), Value::Text(b)) => Some(a.cmp(b)),
+        (Value::Boolean(a), Value::Boolean(b)) => Some(a.cmp(b)),
+        (a, b) => {
+            return Err(ExecError::TypeMismatch(format!(
+                "cannot compare {} with {}",
+                a, b
+            )));
+        }
+    })
+}
+
+fn as_bool(value: Value) -> ExecResult<Option<bool>> {
+    match value {
+        Value::Null => Ok(None),
+        Value::Boolean(b) => Ok(Some(b)),
+        other => Err(ExecError::TypeMismatch(format!(
+            "expected a boolean, found {}",
+            other
+        ))),
+    }
+}
+
+fn negate_if(value: Value, negated: bool) -> Value {
+    match value {
+        Value::Boolean(b) if negated => Value::Boolean(!b),
+        value => value,
+    }
+}
+
+fn in_values(value: Value, candidates: Vec<Value>, negated: bool) -> ExecResult<Value> {
+    if value == Value::Null {
+        return Ok(Value::Null);
+    }
+
+    let mut saw_null = false;
+    for candidate in candidates {
+        match sql_compare(&value, &candidate)? {
+            Some(Ordering::Equal) => return Ok(Value::Boolean(!negated)),
+            None => saw_null = true,
+            Some(_) => {}
+        }
+    }
+
+    // `x IN (..., NULL)` is unknown rather than false when nothing matched
+    Ok(if saw_null {
+        Value::Null
+    } else {
+        Value::Boolean(negated)
+    })
+}
+
+fn binary_op(op: BinaryOp, left: Value, right: Value) -> ExecResult<Value> {
+    if left == Value::Null || right == Value::Null {
+        return Ok(Value::Null);
+    }
+
+    let ordering = |wanted: &[Ordering]| -> ExecResult<Value> {
+        let ordering = sql_compare(&left, &right)?;
+        Ok(Value::Boolean(
+            ordering.is_some_and(|o| wanted.contains(&o)),
+        ))
+    };
+
+    match op {
+        BinaryOp::Equal => ordering(&[Ordering::Equal]),
+        BinaryOp::NotEqual => ordering(&[Ordering::Less, Ordering::Greater]),
+        BinaryOp::Less => ordering(&[Ordering::Less]),
+        BinaryOp::Greater => ordering(&[Ordering::Greater]),
+        BinaryOp::LessEqual => ordering(&[Ordering::Less, Ordering::Equal]),
+        BinaryOp::GreaterEqual => ordering(&[Ordering::Greater, Ordering::Equal]),
+        BinaryOp::And | BinaryOp::Or => {
+            let (left, right) = (as_bool(left)?, as_bool(right)?);
+            let result = if op == BinaryOp::And {
+                left.zip(right).map(|(l, r)| l && r)
+            } else {
+                left.zip(right).map(|(l, r)| l || r)
+            };
+            Ok(result.map_or(Value::Null, Value::Boolean))
+        }
+        BinaryOp::Plus | BinaryOp::Minus | BinaryOp::Multiply | BinaryOp::Divide => {
+            arithmetic(op, left, right)
+        }
+    }
+}
+
+fn arithmetic(op: BinaryOp, left: Value, right: Value) -> ExecResult<Value> {
+    match (left, right) {
+        (Value::Integer(a), Value::Integer(b)) => {
+            let result = match op {
+                BinaryOp::Plus => a.checked_add(b),
+                BinaryOp::Minus => a.checked_sub(b),
+                BinaryOp::Multiply => a.checked_mul(b),
+                _ if b == 0 => return Err(ExecError::DivisionByZero),
+                _ => a.checked_div(b),
+            };
+            result.map(Value::Integer).ok_or(ExecError::Overflow)
+        }
+        (Value::Integer(a), Value::Float(b)) => {
+            arithmetic(op, Value::Float(a as f64), Value::Float(b))
+        }
+        (Value::Float(a), Value::Integer(b)) => {
+            arithmetic(op, Value::Float(a), Value::Float(b as f64))
+        }
+        (Value::Float(a), Value::Float(b)) => Ok(Value::Float(match op {
+            BinaryOp::Plus => a + b,
+            BinaryOp::Minus => a - b,
+            BinaryOp::Multiply => a * b,
+            _ if b == 0.0 => return Err(ExecError::DivisionByZero),
+            _ => a / b,
+        })),
+        (left, right) => Err(ExecError::TypeMismatch(format!(
+            "cannot apply `{}` to {} and {}",
+            op, left, right
+        ))),
+    }
+}
+
+fn scalar_function(name: &str, args: Vec<Value>) -> ExecResult<Value> {
+    let name = name.to_uppercase();
+
+    if name == "COALESCE" {
+        return Ok(args
+            .into_iter()
+            .find(|v| *v != Value::Null)
+            .unwrap_or(Value::Null));
+    }
+
+    let [arg] = args.as_slice() else {
+        return Err(ExecError::Unsupported(format!(
+            "{} takes exactly one argument",
+            name
+        )));
+    };
+
+    match (name.as_str(), arg) {
+        (_, Value::Null) if ["LOWER", "UPPER", "LENGTH", "ABS"].contains(&name.as_str()) => {
+            Ok(Value::Null)
+        }
+        ("LOWER", Value::Text(s)) => Ok(Value::Text(s.to_lowercase())),
+        ("UPPER", Value::Text(s)) => Ok(Value::Text(s.to_uppercase())),
+        ("LENGTH", Value::Text(s)) => Ok(Value::Integer(s.chars().count() as i64)),
+        ("ABS", Value::Integer(n)) => n
+            .checked_abs()
+            .map(Value::Integer)
+            .ok_or(ExecError::Overflow),
+        ("ABS", Value::Float(f)) => Ok(Value::Float(f.abs())),
+        ("LOWER" | "UPPER" | "LENGTH" | "ABS", value) => Err(ExecError::TypeMismatch(format!(
+            "invalid argument {} for {}",
+            value, name
+        ))),
+        _ => Err(ExecError::UnknownFunction(name)),
+    }
+}
+
+fn cast(value: Value, data_type: DataType) -> ExecResult<Value> {
+    let invalid =
+        |value: &Value| ExecError::TypeMismatch(format!("cannot cast {} to {}", value, data_type));
+
+    Ok(match (value, data_type) {
+        (Value::Null, _) => Value::Null,
+        (value, DataType::Integer | DataType::BigInt) => match value {
+            Value::Integer(n) => Value::Integer(n),
+            Value::Float(f) => {
+                // `as` would saturate, so reject what an i64 can't hold like checked arithmetic does
+                let truncated = f.trunc();
+                if !(i64::MIN as f64..-(i64::MIN as f64)).contains(&truncated) {
+                    return Err(ExecError::Overflow);
+                }
+                Value::Integer(truncated as i64)
+            }
+            Value::Boolean(b) => Value::Integer(b as i64),
+            Value::Text(ref s) => Value::Integer(s.trim().parse().map_err(|_| invalid(&value))?),
+            Value::Null => Value::Null,
+        },
+        (value, DataType::Float) => match value {
+            Value::Integer(n) => Value::Float(n as f64),
+            Value::Float(f) => Value::Float(f),
+            Value::Text(ref s) => Value::Float(s.trim().parse().map_err(|_| invalid(&value))?),
+            value => return Err(invalid(&value)),
+        },
+        (value, DataType::Boolean) => match value {
+            Value::Boolean(b) => Value::Boolean(b),
+            Value::Integer(n) => Value::Boolean(n != 0),
+            Value::Text(ref s) => match s.trim().to_lowercase().as_str() {
+                "true" | "t" | "1" => Value::Boolean(true),
+                "false" | "f" | "0" => Value::Boolean(false),
+                _ => return Err(invalid(&value)),
+            },
+            value => return Err(invalid(&value)),
+        },
+        // Dates and timestamps are carried as their text representation
+        (value, DataType::Text | DataType::Varchar(_) | DataType::Date | DataType::Timestamp) => {
+            Value::Text(value.to_string())
+        }
+    })
+}
+
+/// SQL LIKE: `%` matches any run of characters and `_` exactly one
+fn like_match(text: &[char], pattern: &[char]) -> bool {
+    match pattern.split_first() {
+        None => text.is_empty(),
+        Some(('%', rest)) => (0..=text.len()).any(|i| like_match(&text[i..], rest)),
+        Some(('_', rest)) => !text.is_empty() && like_match(&text[1..], rest),
+        Some((c, rest)) => text.first() == Some(c) && like_match(&text[1..], rest),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ast::Statement, error::Backtrace, parser::Parser, token::tokenize};
+
+    fn database() -> Database {
+        let mut db = Database::new();
+        db.add_table(
+            "users",
+            &["id", "name", "age"],
+            vec![
+                vec![1.into(), "ann".into(), 31.into()],
+                vec![2.into(), "bob".into(), 17.into()],
+                vec![3.into(), "cid".into(), Value::Null],
+            ],
+        );
+        db.add_table(
+            "orders",
+            &["id", "user_id", "total"],
+            vec![
+                vec![10.into(), 1.into(), 9.5.into()],
+                vec![11.into(), 1.into(), 20.0.into()],
+                vec![12.into(), 2.into(), 5.0.into()],
+                vec![13.into(), 4.into(), 1.0.into()],
+            ],
+        );
+        db
+    }
+
+    fn run(sql: &str) -> ExecResult<Table> {
+        let tokens = tokenize(sql);
+        let backtrace = Backtrace::new();
+        let mut parser = Parser::new(&tokens, &backtrace, sql);
+        let Statement::Query(query) = parser.parse_statement().unwrap() else {
+            panic!("expected a query");
+        };
+        execute_query(&database(), &query)
+    }
+
+    #[test]
+    fn test_filter_order_and_limit() {
+        let table =
+            run("SELECT name FROM users WHERE age > 18 OR age IS NULL ORDER BY name DESC LIMIT 5")
+                .unwrap();
+
+        assert_eq!(table.columns, ["name"]);
+        assert_eq!(table.rows, vec![vec!["cid".into()], vec!["ann".into()]]);
+    }
+
+    #[test]
+    fn test_three_valued_logic() {
+        // age > 18 is unknown for cid, so neither the predicate nor its negation keeps the row
+        let kept = run("SELECT id FROM users WHERE age > 18").unwrap();
+        let negated = run("SELECT id FROM users WHERE NOT age > 18").unwrap();
+        assert_eq!(kept.rows.len() + negated.rows.len(), 2);
+
+        let table =
+            run("SELECT 1 IN (2, NULL), NULL AND FALSE, NULL OR TRUE, 3 NOT IN (1, 2)").unwrap();
+        assert_eq!(
+            table.rows,
+            vec![vec![Value::Null, false.into(), true.into(), true.into()]]
+        );
+    }
+
+    #[test]
+    fn test_joins_and_aggregates() {
+        let table = run("SELECT u.name, COUNT(o.id), SUM(o.total) FROM users u \
+             LEFT JOIN orders o ON u.id = o.user_id GROUP BY u.name ORDER BY u.name")
+        .unwrap();
+
+        assert_eq!(
+            table.rows,
+            vec![
+                vec!["ann".into(), 2.into(), 29.5.into()],
+                vec!["bob".into(), 1.into(), 5.0.into()],
+                vec!["cid".into(), 0.into(), Value::Null],
+            ]
+        );
+
+        let table = run("SELECT * FROM users u FULL JOIN orders o ON u.id = o.user_id").unwrap();
+        assert_eq!(table.rows.len(), 5);
+    }
+
+    #[test]
+    fn test_union_and_recursive_cte() {
+        let table =
+            run("SELECT user_id FROM orders UNION SELECT id FROM users ORDER BY 1").unwrap();
+        assert_eq!(
+            table.rows,
+            vec![
+                vec![1.into()],
+                vec![2.into()],
+                vec![3.into()],
+                vec![4.into()]
+            ]
+        );
+
+        let table = run(
+            "SELECT user_id FROM orders UNION SELECT id FROM users ORDER BY user_id DESC LIMIT 2",
+        )
+        .unwrap();
+        assert_eq!(table.rows, vec![vec![4.into()], vec![3.into()]]);
+
+        let table = run("SELECT user_id FROM orders UNION ALL SELECT id FROM users").unwrap();
+        assert_eq!(table.rows.len(), 7);
+
+        let table = run(
+            "WITH RECURSIVE nums(n) AS (SELECT 1 UNION ALL SELECT n + 1 FROM nums WHERE n < 5) \
+             SELECT SUM(n), MAX(n) FROM nums",
+        )
+        .unwrap();
+        assert_eq!(table.rows, vec![vec![15.into(), 5.into()]]);
+    }
+
+    #[test]
+    fn test_runtime_errors() {
+        assert_eq!(
+            run("SELECT missing FROM users"),
+            Err(ExecError::UnknownColumn("missing".to_string()))
+        );
+        assert_eq!(
+            run("SELECT id FROM users, orders"),
+            Err(ExecError::AmbiguousColumn("id".to_string()))
+        );
+        assert_eq!(run("SELECT 1 / 0"), Err(ExecError::DivisionByZero));
+        assert_eq!(
+            run("SELECT CAST(9223372036854775808.0 AS BIGINT)"),
+            Err(ExecError::Overflow)
+        );
+        assert_eq!(
+            run("SELECT CAST(-2.7 AS INTEGER)").unwrap().rows,
+            vec![vec![(-2).into()]]
+        );
+    }
+}
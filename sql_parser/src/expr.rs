@@ -1,4 +1,7 @@
 use crate::{
+    ast::{Query, Span},
+    ddl::DataType,
+    format::{FormatOptions, format_expr},
     parser::{ParseResult, Parser},
     token::TokenKind,
 };
@@ -8,6 +11,15 @@ pub enum Literal<'a> {
     Number(i64),
     Float(f64),
     String(&'a str),
+    Boolean(bool),
+    Null,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UnaryOp {
+    Not,
+    Minus,
+    Plus,
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -44,26 +56,141 @@ impl BinaryOp {
             _ => None,
         }
     }
+
+    pub fn to_token(self) -> TokenKind {
+        match self {
+            BinaryOp::And => TokenKind::And,
+            BinaryOp::Or => TokenKind::Or,
+            BinaryOp::Equal => TokenKind::Equal,
+            BinaryOp::NotEqual => TokenKind::NotEqual,
+            BinaryOp::Less => TokenKind::Less,
+            BinaryOp::Greater => TokenKind::Greater,
+            BinaryOp::LessEqual => TokenKind::LessEqual,
+            BinaryOp::GreaterEqual => TokenKind::GreaterEqual,
+            BinaryOp::Plus => TokenKind::Plus,
+            BinaryOp::Minus => TokenKind::Minus,
+            BinaryOp::Multiply => TokenKind::Star,
+            BinaryOp::Divide => TokenKind::Slash,
+        }
+    }
+
+    /// Binding power the parser gives this operator
+    pub fn precedence(self) -> u8 {
+        get_precedence(self.to_token()).map_or(0, |(prec, _)| prec)
+    }
+}
+
+/// An expression and the source range it was parsed from
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr<'a> {
+    pub kind: ExprKind<'a>,
+    pub span: Span,
 }
 
-#[derive(Debug, Clone)]
-pub enum Expr<'a> {
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExprKind<'a> {
     Column(&'a str),
+    QualifiedColumn {
+        table: &'a str,
+        column: &'a str,
+    },
     Literal(Literal<'a>),
     Binary {
         left: Box<Expr<'a>>,
         op: BinaryOp,
         right: Box<Expr<'a>>,
     },
+    Unary {
+        op: UnaryOp,
+        expr: Box<Expr<'a>>,
+    },
+    Function {
+        name: &'a str,
+        distinct: bool,
+        args: Vec<Expr<'a>>,
+    },
+    IsNull {
+        expr: Box<Expr<'a>>,
+        negated: bool,
+    },
+    InList {
+        expr: Box<Expr<'a>>,
+        list: Vec<Expr<'a>>,
+        negated: bool,
+    },
+    InSubquery {
+        expr: Box<Expr<'a>>,
+        subquery: Box<Query<'a>>,
+        negated: bool,
+    },
+    Between {
+        expr: Box<Expr<'a>>,
+        low: Box<Expr<'a>>,
+        high: Box<Expr<'a>>,
+        negated: bool,
+    },
+    Like {
+        expr: Box<Expr<'a>>,
+        pattern: Box<Expr<'a>>,
+        negated: bool,
+    },
+    Case {
+        operand: Option<Box<Expr<'a>>>,
+        when_then: Vec<(Expr<'a>, Expr<'a>)>,
+        else_result: Option<Box<Expr<'a>>>,
+    },
+    Cast {
+        expr: Box<Expr<'a>>,
+        data_type: DataType,
+    },
     Paren(Box<Expr<'a>>),
     Star,
 }
 
+impl<'a> Expr<'a> {
+    pub fn new(kind: ExprKind<'a>, span: Span) -> Self {
+        Expr { kind, span }
+    }
+
+    /// How tightly this expression binds when printed without parentheses; atoms bind tightest
+    pub(crate) fn precedence(&self) -> u8 {
+        match self.kind {
+            ExprKind::Binary { op, .. } => op.precedence(),
+            ExprKind::IsNull { .. }
+            | ExprKind::InList { .. }
+            | ExprKind::InSubquery { .. }
+            | ExprKind::Between { .. }
+            | ExprKind::Like { .. } => get_precedence(TokenKind::Is).map_or(0, |(prec, _)| prec),
+            ExprKind::Unary {
+                op: UnaryOp::Not, ..
+            } => NOT_PRECEDENCE,
+            ExprKind::Unary { .. } => UNARY_PRECEDENCE,
+            _ => u8::MAX,
+        }
+    }
+}
+
+/// Expressions built outside the parser have no source location
+impl<'a> From<ExprKind<'a>> for Expr<'a> {
+    fn from(kind: ExprKind<'a>) -> Self {
+        Expr::new(kind, Span::default())
+    }
+}
+
+/// Binding power of prefix `NOT`: its operand absorbs comparisons but stops at `AND`/`OR`
+const NOT_PRECEDENCE: u8 = 25;
+/// Binding power of prefix `-`/`+`: tighter than any infix operator
+const UNARY_PRECEDENCE: u8 = 70;
+
 fn get_precedence(token: TokenKind) -> Option<(u8, bool)> {
     match token {
         TokenKind::Or => Some((10, true)),
         TokenKind::And => Some((20, true)),
         TokenKind::Equal | TokenKind::NotEqual => Some((30, true)),
+        // Postfix predicates: `IS [NOT] NULL`, `[NOT] IN`, `[NOT] BETWEEN`, `[NOT] LIKE`
+        TokenKind::Is | TokenKind::In | TokenKind::Between | TokenKind::Like | TokenKind::Not => {
+            Some((30, true))
+        }
         TokenKind::Less | TokenKind::Greater | TokenKind::LessEqual | TokenKind::GreaterEqual => {
             Some((40, true))
         }
@@ -74,16 +201,10 @@ fn get_precedence(token: TokenKind) -> Option<(u8, bool)> {
 }
 
 impl<'a> std::fmt::Display for Expr<'a> {
+    /// Prints the expression on one line with only the parentheses precedence requires, so the
+    /// output re-parses to the same tree
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        match self {
-            Expr::Column(name) => write!(f, "{}", name),
-            Expr::Literal(lit) => write!(f, "{}", lit),
-            Expr::Binary { left, op, right } => {
-                write!(f, "({} {} {})", left, op, right)
-            }
-            Expr::Paren(expr) => write!(f, "({})", expr),
-            Expr::Star => write!(f, "*"),
-        }
+        f.write_str(&format_expr(self, &FormatOptions::default()))
     }
 }
 
@@ -91,12 +212,28 @@ impl<'a> std::fmt::Display for Literal<'a> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             Literal::Number(n) => write!(f, "{}", n),
+            // Keep a fractional part so the literal re-lexes as a float
+            Literal::Float(fl) if fl.fract() == 0.0 => write!(f, "{:.1}", fl),
             Literal::Float(fl) => write!(f, "{}", fl),
             Literal::String(s) => write!(f, "'{}'", s),
+            Literal::Boolean(true) => write!(f, "TRUE"),
+            Literal::Boolean(false) => write!(f, "FALSE"),
+            Literal::Null => write!(f, "NULL"),
         }
     }
 }
 
+impl std::fmt::Display for UnaryOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            UnaryOp::Not => "NOT ",
+            UnaryOp::Minus => "-",
+            UnaryOp::Plus => "+",
+        };
+        write!(f, "{}", s)
+    }
+}
+
 impl std::fmt::Display for BinaryOp {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         let s = match self {
@@ -117,7 +254,7 @@ impl std::fmt::Display for BinaryOp {
     }
 }
 
-impl<'a> Parser<'a> {
+impl<'a> Parser<'_, 'a> {
     /// Parse an expression using precedence climbing
     pub fn parse_expr(&mut self) -> ParseResult<Expr<'a>> {
         self.parse_expr_with_precedence(0)
@@ -133,17 +270,44 @@ impl<'a> Parser<'a> {
                     break;
                 }
 
+                // A NOT that doesn't start `NOT IN/BETWEEN/LIKE` (e.g. `DEFAULT 0 NOT NULL`)
+                // belongs to the enclosing construct
+                if token.kind == TokenKind::Not
+                    && !matches!(
+                        self.peek().map(|t| t.kind),
+                        Some(TokenKind::In | TokenKind::Between | TokenKind::Like)
+                    )
+                {
+                    break;
+                }
+
+                if matches!(
+                    token.kind,
+                    TokenKind::Is
+                        | TokenKind::In
+                        | TokenKind::Between
+                        | TokenKind::Like
+                        | TokenKind::Not
+                ) {
+                    left = self.parse_postfix_predicate(left, prec)?;
+                    continue;
+                }
+
                 let op_kind = token.kind;
                 self.advance();
                 let next_min_prec = if is_left { prec + 1 } else { prec };
                 let right = self.parse_expr_with_precedence(next_min_prec)?;
 
                 if let Some(op) = BinaryOp::from_token(op_kind) {
-                    left = Expr::Binary {
-                        left: Box::new(left),
-                        op,
-                        right: Box::new(right),
-                    };
+                    let span = left.span.to(right.span);
+                    left = Expr::new(
+                        ExprKind::Binary {
+                            left: Box::new(left),
+                            op,
+                            right: Box::new(right),
+                        },
+                        span,
+                    );
                 }
             } else {
                 break;
@@ -153,7 +317,77 @@ impl<'a> Parser<'a> {
         Ok(left)
     }
 
+    /// Parse `IS [NOT] NULL`, `[NOT] IN (...)`, `[NOT] BETWEEN a AND b` or `[NOT] LIKE pattern`
+    fn parse_postfix_predicate(&mut self, expr: Expr<'a>, prec: u8) -> ParseResult<Expr<'a>> {
+        let start = expr.span.start;
+        let kind = self.parse_postfix_predicate_kind(Box::new(expr), prec)?;
+        Ok(Expr::new(kind, self.span_from(start)))
+    }
+
+    fn parse_postfix_predicate_kind(
+        &mut self,
+        expr: Box<Expr<'a>>,
+        prec: u8,
+    ) -> ParseResult<ExprKind<'a>> {
+        if self.try_consume(TokenKind::Is) {
+            let negated = self.try_consume(TokenKind::Not);
+            self.expect(TokenKind::Null)?;
+            return Ok(ExprKind::IsNull { expr, negated });
+        }
+
+        let negated = self.try_consume(TokenKind::Not);
+
+        if self.try_consume(TokenKind::In) {
+            self.expect(TokenKind::LeftParen)?;
+            let in_expr = if matches!(
+                self.current().map(|t| t.kind),
+                Some(TokenKind::Select | TokenKind::With)
+            ) {
+                ExprKind::InSubquery {
+                    expr,
+                    subquery: Box::new(self.parse_query()?),
+                    negated,
+                }
+            } else {
+                ExprKind::InList {
+                    expr,
+                    list: self.parse_expr_list()?,
+                    negated,
+                }
+            };
+            self.expect(TokenKind::RightParen)?;
+            return Ok(in_expr);
+        }
+
+        if self.try_consume(TokenKind::Between) {
+            // Bounds bind tighter than the AND separating them
+            let low = Box::new(self.parse_expr_with_precedence(prec + 1)?);
+            self.expect(TokenKind::And)?;
+            let high = Box::new(self.parse_expr_with_precedence(prec + 1)?);
+            return Ok(ExprKind::Between {
+                expr,
+                low,
+                high,
+                negated,
+            });
+        }
+
+        self.expect(TokenKind::Like)?;
+        let pattern = Box::new(self.parse_expr_with_precedence(prec + 1)?);
+        Ok(ExprKind::Like {
+            expr,
+            pattern,
+            negated,
+        })
+    }
+
     fn parse_primary(&mut self) -> ParseResult<Expr<'a>> {
+        let start = self.start();
+        let kind = self.parse_primary_kind()?;
+        Ok(Expr::new(kind, self.span_from(start)))
+    }
+
+    fn parse_primary_kind(&mut self) -> ParseResult<ExprKind<'a>> {
         match self.current() {
             Some(token) => {
                 match token.kind {
@@ -163,7 +397,7 @@ impl<'a> Parser<'a> {
                         let n = text
                             .parse::<i64>()
                             .map_err(|_| self.error_at_current("Invalid number"))?;
-                        Ok(Expr::Literal(Literal::Number(n)))
+                        Ok(ExprKind::Literal(Literal::Number(n)))
                     }
                     TokenKind::Float => {
                         let text = token.text;
@@ -171,29 +405,92 @@ impl<'a> Parser<'a> {
                         let f = text
                             .parse::<f64>()
                             .map_err(|_| self.error_at_current("Invalid float"))?;
-                        Ok(Expr::Literal(Literal::Float(f)))
+                        Ok(ExprKind::Literal(Literal::Float(f)))
                     }
                     TokenKind::String => {
                         let text = token.text;
                         self.advance();
                         // Remove quotes
                         let s = &text[1..text.len() - 1];
-                        Ok(Expr::Literal(Literal::String(s)))
+                        Ok(ExprKind::Literal(Literal::String(s)))
+                    }
+                    TokenKind::True => {
+                        self.advance();
+                        Ok(ExprKind::Literal(Literal::Boolean(true)))
                     }
-                    TokenKind::Identifier => {
+                    TokenKind::False => {
+                        self.advance();
+                        Ok(ExprKind::Literal(Literal::Boolean(false)))
+                    }
+                    TokenKind::Null => {
+                        self.advance();
+                        Ok(ExprKind::Literal(Literal::Null))
+                    }
+                    kind if kind.is_identifier() => {
                         let text = token.text;
                         self.advance();
-                        Ok(Expr::Column(text))
+                        if self.try_consume(TokenKind::Dot) {
+                            let column = self.parse_identifier()?;
+                            Ok(ExprKind::QualifiedColumn {
+                                table: text,
+                                column,
+                            })
+                        } else if self.try_consume(TokenKind::LeftParen) {
+                            self.parse_function_call(text)
+                        } else {
+                            Ok(ExprKind::Column(text))
+                        }
                     }
                     TokenKind::Star => {
                         self.advance();
-                        Ok(Expr::Star)
+                        Ok(ExprKind::Star)
                     }
                     TokenKind::LeftParen => {
                         self.advance();
                         let expr = self.parse_expr()?;
                         self.expect(TokenKind::RightParen)?;
-                        Ok(Expr::Paren(Box::new(expr)))
+                        Ok(ExprKind::Paren(Box::new(expr)))
+                    }
+                    TokenKind::Not => {
+                        self.advance();
+                        let expr = self.parse_expr_with_precedence(NOT_PRECEDENCE)?;
+                        Ok(ExprKind::Unary {
+                            op: UnaryOp::Not,
+                            expr: Box::new(expr),
+                        })
+                    }
+                    TokenKind::Minus | TokenKind::Plus => {
+                        let op = if token.kind == TokenKind::Minus {
+                            UnaryOp::Minus
+                        } else {
+                            UnaryOp::Plus
+                        };
+                        self.advance();
+                        let expr = self.parse_expr_with_precedence(UNARY_PRECEDENCE)?;
+                        // Fold signs into numeric literals so `-1` stays a literal
+                        let Expr { kind, span } = expr;
+                        Ok(match (op, kind) {
+                            (UnaryOp::Minus, ExprKind::Literal(Literal::Number(n))) => {
+                                ExprKind::Literal(Literal::Number(-n))
+                            }
+                            (UnaryOp::Minus, ExprKind::Literal(Literal::Float(f))) => {
+                                ExprKind::Literal(Literal::Float(-f))
+                            }
+                            (op, kind) => ExprKind::Unary {
+                                op,
+                                expr: Box::new(Expr::new(kind, span)),
+                            },
+                        })
+                    }
+                    TokenKind::Case => self.parse_case(),
+                    TokenKind::Cast => {
+                        self.advance();
+                        self.expect(TokenKind::LeftParen)?;
+                        let expr = Box::new(self.parse_expr()?);
+                        self.expect(TokenKind::As)?;
+                        let data_type = self.parse_data_type()?;
+                        self.expect(TokenKind::RightParen)?;
+                        Ok(ExprKind::Cast { expr, data_type })
                     }
                     _ => Err(self.error_at_current("Expected expression")),
                 }
@@ -201,14 +498,85 @@ impl<'a> Parser<'a> {
             None => Err(self.error_at_current("Unexpected end of input")),
         }
     }
-}
 
+    /// Parse the arguments of `name(...)`; the opening parenthesis is already consumed
+    fn parse_function_call(&mut self, name: &'a str) -> ParseResult<ExprKind<'a>> {
+        let distinct = self.try_consume(TokenKind::Distinct);
+        let args = if self.current().map(|t| t.kind) == Some(TokenKind::RightParen) {
+            Vec::new()
+        } else {
+            self.parse_expr_list()?
+        };
+        self.expect(TokenKind::RightParen)?;
+
+        Ok(ExprKind::Function {
+            name,
+            distinct,
+            args,
+        })
+    }
+
+    /// Parse `CASE [operand] WHEN cond THEN result ... [ELSE result] END`
+    fn parse_case(&mut self) -> ParseResult<ExprKind<'a>> {
+        self.expect(TokenKind::Case)?;
+
+        let operand = if self.current().map(|t| t.kind) == Some(TokenKind::When) {
+            None
+        } else {
+            Some(Box::new(self.parse_expr()?))
+        };
+
+        let mut when_then = Vec::new();
+        while self.try_consume(TokenKind::When) {
+            let when = self.parse_expr()?;
+            self.expect(TokenKind::Then)?;
+            let then = self.parse_expr()?;
+            when_then.push((when, then));
+        }
+        if when_then.is_empty() {
+            return Err(self.error_expected_one_of(&["WHEN"]));
+        }
+
+        let else_result = if self.try_consume(TokenKind::Else) {
+            Some(Box::new(self.parse_expr()?))
+        } else {
+            None
+        };
+        self.expect(TokenKind::End)?;
+
+        Ok(ExprKind::Case {
+            operand,
+            when_then,
+            else_result,
+        })
+    }
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
     use crate::error::Backtrace;
     use crate::token::tokenize;
+    use crate::visitor::{VisitorMut, walk_expr_mut};
+
+    /// Wraps every operator in parentheses, so that printing shows how the parser grouped operands
+    struct Parenthesize;
+
+    impl<'a> VisitorMut<'a> for Parenthesize {
+        fn visit_expr_mut(&mut self, expr: &mut Expr<'a>) {
+            walk_expr_mut(self, expr);
+            if expr.precedence() < u8::MAX {
+                let operator = std::mem::replace(expr, Expr::from(ExprKind::Star));
+                *expr = Expr::new(ExprKind::Paren(Box::new(operator)), expr.span);
+            }
+        }
+    }
+
+    fn grouped(expr: &Expr) -> String {
+        let mut expr = expr.clone();
+        Parenthesize.visit_expr_mut(&mut expr);
+        expr.to_string()
+    }
 
     #[test]
     fn test_precedence() {
@@ -219,7 +587,8 @@ mod tests {
 
         let expr = parser.parse_expr().unwrap();
 
-        assert_eq!(format!("{}", expr), "(2 + (3 * 4))");
+        assert_eq!(format!("{}", expr), "2 + 3 * 4");
+        assert_eq!(grouped(&expr), "(2 + (3 * 4))");
     }
 
     #[test]
@@ -232,6 +601,62 @@ mod tests {
         let expr = parser.parse_expr().unwrap();
 
         let expected = "(((age > 18) AND (status = 'active')) OR (admin = 1))";
-        assert_eq!(format!("{}", expr), expected);
+        assert_eq!(grouped(&expr), expected);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_predicates() {
+        let input = "a IS NOT NULL AND b NOT IN (1, 2) OR c BETWEEN 1 AND 5 AND d LIKE 'x%'";
+        let tokens = tokenize(input);
+        let backtrace = Backtrace::new();
+        let mut parser = Parser::new(&tokens, &backtrace, input);
+
+        let expr = parser.parse_expr().unwrap();
+
+        let expected = "(((a IS NOT NULL) AND (b NOT IN (1, 2))) OR \
+                        ((c BETWEEN 1 AND 5) AND (d LIKE 'x%')))";
+        assert_eq!(grouped(&expr), expected);
+    }
+
+    #[test]
+    fn test_unary_and_literals() {
+        let input = "NOT a = -1 AND -b * 2 > 0 OR flag = TRUE OR x = NULL";
+        let tokens = tokenize(input);
+        let backtrace = Backtrace::new();
+        let mut parser = Parser::new(&tokens, &backtrace, input);
+
+        let expr = parser.parse_expr().unwrap();
+
+        let expected = "((((NOT (a = -1)) AND (((-b) * 2) > 0)) OR (flag = TRUE)) OR (x = NULL))";
+        assert_eq!(grouped(&expr), expected);
+    }
+
+    #[test]
+    fn test_functions_case_cast() {
+        let input = "CASE WHEN COUNT(DISTINCT u.id) > 1 THEN CAST(total AS float) \
+                     ELSE coalesce(x, 0) END";
+        let tokens = tokenize(input);
+        let backtrace = Backtrace::new();
+        let mut parser = Parser::new(&tokens, &backtrace, input);
+
+        let expr = parser.parse_expr().unwrap();
+
+        let expected = "CASE WHEN (COUNT(DISTINCT u.id) > 1) THEN CAST(total AS FLOAT) \
+                        ELSE coalesce(x, 0) END";
+        assert_eq!(grouped(&expr), expected);
+        assert!(matches!(expr.kind, ExprKind::Case { operand: None, .. }));
+    }
+
+    #[test]
+    fn test_count_star_and_in_subquery() {
+        let input = "COUNT(*) + 1 > 0 AND id IN (SELECT user_id FROM orders)";
+        let tokens = tokenize(input);
+        let backtrace = Backtrace::new();
+        let mut parser = Parser::new(&tokens, &backtrace, input);
+
+        let expr = parser.parse_expr().unwrap();
+
+        let expected = "(((COUNT(*) + 1) > 0) AND (id IN (SELECT user_id FROM orders)))";
+        assert_eq!(grouped(&expr), expected);
+    }
+}
@@ -0,0 +1,1190 @@
+use crate::{
+    ast::{
+        CTE, Join, JoinConstraint, OrderByExpr, Query, SelectStmt, Statement, TableFactor,
+        TableRef, TableWithJoins, With, join_idents,
+    },
+    ddl::{ColumnConstraint, ColumnDef, CreateTable},
+    dml::{Delete, Insert, InsertSource, Update},
+    expr::{BinaryOp, Expr, ExprKind, Literal, UnaryOp},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeywordCase {
+    Upper,
+    Lower,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FormatOptions {
+    pub indent_width: usize,
+    pub keyword_case: KeywordCase,
+    /// Clauses that don't fit in this many columns are broken across lines
+    pub line_width: usize,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            indent_width: 4,
+            keyword_case: KeywordCase::Upper,
+            line_width: 80,
+        }
+    }
+}
+
+/// Format a statement; for any AST the parser produces, parsing the output yields an equal AST
+pub fn format_statement(stmt: &Statement, options: &FormatOptions) -> String {
+    SqlFormatter { options }.statement(stmt, Some(0))
+}
+
+pub fn format_query(query: &Query, options: &FormatOptions) -> String {
+    SqlFormatter { options }.query(query, Some(0))
+}
+
+/// Format an expression on one line, adding only the parentheses precedence requires
+pub fn format_expr(expr: &Expr, options: &FormatOptions) -> String {
+    SqlFormatter { options }.expr(expr)
+}
+
+/// Renders nodes at an `Option<usize>` indent: `None` forces a single line, `Some(column)`
+/// allows breaking at that column. The first line of every rendering is left unindented
+/// and continuation lines carry their absolute indentation.
+struct SqlFormatter<'o> {
+    options: &'o FormatOptions,
+}
+
+impl SqlFormatter<'_> {
+    fn kw(&self, keyword: &str) -> String {
+        match self.options.keyword_case {
+            KeywordCase::Upper => keyword.to_uppercase(),
+            KeywordCase::Lower => keyword.to_lowercase(),
+        }
+    }
+
+    fn pad(&self, indent: usize) -> String {
+        " ".repeat(indent)
+    }
+
+    fn nested(&self, indent: Option<usize>) -> Option<usize> {
+        indent.map(|indent| indent + self.options.indent_width)
+    }
+
+    fn fits(&self, indent: usize, text: &str) -> bool {
+        !text.contains('\n') && indent + text.chars().count() <= self.options.line_width
+    }
+
+    /// Use the single-line rendering when it fits, otherwise the broken one
+    fn fit(&self, indent: Option<usize>, render: impl Fn(Option<usize>) -> String) -> String {
+        let flat = render(None);
+        match indent {
+            Some(indent) if !self.fits(indent, &flat) => render(Some(indent)),
+            _ => flat,
+        }
+    }
+
+    /// Join clauses with spaces, or one per line when breaking
+    fn lines(&self, indent: Option<usize>, parts: Vec<String>) -> String {
+        match indent {
+            None => parts.join(" "),
+            Some(indent) => parts.join(&format!("\n{}", self.pad(indent))),
+        }
+    }
+
+    /// `head item, item, ...`, moving the items onto their own lines when they don't fit;
+    /// items should be rendered at `self.nested(indent)`
+    fn list(&self, head: &str, items: &[String], indent: Option<usize>) -> String {
+        let flat = format!("{} {}", head, items.join(", "));
+        match indent {
+            Some(indent) if !self.fits(indent, &flat) => {
+                let inner = self.pad(indent + self.options.indent_width);
+                format!(
+                    "{}\n{}{}",
+                    head,
+                    inner,
+                    items.join(&format!(",\n{}", inner))
+                )
+            }
+            _ => flat,
+        }
+    }
+
+    /// A parenthesized block whose body moves onto its own indented lines when it doesn't fit
+    fn parenthesized(
+        &self,
+        indent: Option<usize>,
+        render: impl Fn(Option<usize>) -> String,
+    ) -> String {
+        self.fit(indent, |broken| match broken {
+            None => format!("({})", render(None)),
+            Some(indent) => format!(
+                "(\n{}{}\n{})",
+                self.pad(indent + self.options.indent_width),
+                render(self.nested(broken)),
+                self.pad(indent)
+            ),
+        })
+    }
+
+    fn statement(&self, stmt: &Statement, indent: Option<usize>) -> String {
+        match stmt {
+            Statement::Query(query) => self.query(query, indent),
+            Statement::Insert(insert) => self.fit(indent, |indent| self.insert(insert, indent)),
+            Statement::Update(update) => self.fit(indent, |indent| self.update(update, indent)),
+            Statement::Delete(delete) => self.fit(indent, |indent| self.delete(delete, indent)),
+            Statement::CreateTable(create) => {
+                self.fit(indent, |indent| self.create_table(create, indent))
+            }
+        }
+    }
+
+    fn query(&self, query: &Query, indent: Option<usize>) -> String {
+        self.fit(indent, |indent| match query {
+            Query::Select(select) => self.select(select, indent),
+            Query::With { with, query } => self.lines(
+                indent,
+                vec![self.with(with, indent), self.query(query, indent)],
+            ),
+            Query::Union {
+                left,
+                all,
+                right,
+                order_by,
+                limit,
+                offset,
+            } => {
+                let union = if *all { "UNION ALL" } else { "UNION" };
+                let mut clauses = vec![
+                    self.query(left, indent),
+                    self.kw(union),
+                    self.query(right, indent),
+                ];
+                self.order_and_limit(
+                    &mut clauses,
+                    order_by,
+                    limit.as_ref(),
+                    offset.as_ref(),
+                    indent,
+                );
+                self.lines(indent, clauses)
+            }
+        })
+    }
+
+    fn with(&self, with: &With, indent: Option<usize>) -> String {
+        let mut head = self.kw("WITH");
+        if with.recursive {
+            head = format!("{} {}", head, self.kw("RECURSIVE"));
+        }
+        let ctes: Vec<String> = with.ctes.iter().map(|cte| self.cte(cte, indent)).collect();
+        format!("{} {}", head, ctes.join(", "))
+    }
+
+    fn cte(&self, cte: &CTE, indent: Option<usize>) -> String {
+        let mut out = cte.name.to_string();
+        if let Some(columns) = &cte.columns {
+            out.push_str(&format!("({})", columns.join(", ")));
+        }
+        format!(
+            "{} {} {}",
+            out,
+            self.kw("AS"),
+            self.parenthesized(indent, |indent| self.query(&cte.query, indent))
+        )
+    }
+
+    fn select(&self, select: &SelectStmt, indent: Option<usize>) -> String {
+        let item_indent = self.nested(indent);
+        let exprs = |exprs: &[Expr]| exprs.iter().map(|e| self.expr(e)).collect::<Vec<_>>();
+
+        let mut clauses = vec![self.list(&self.kw("SELECT"), &exprs(&select.projection), indent)];
+
+        if !select.from.is_empty() {
+            let tables: Vec<String> = select
+                .from
+                .iter()
+                .map(|table| self.table_with_joins(table, item_indent))
+                .collect();
+            clauses.push(self.list(&self.kw("FROM"), &tables, indent));
+        }
+
+        if let Some(where_clause) = &select.where_clause {
+            clauses.push(format!("{} {}", self.kw("WHERE"), self.expr(where_clause)));
+        }
+
+        if !select.group_by.is_empty() {
+            clauses.push(self.list(&self.kw("GROUP BY"), &exprs(&select.group_by), indent));
+        }
+
+        if let Some(having) = &select.having {
+            clauses.push(format!("{} {}", self.kw("HAVING"), self.expr(having)));
+        }
+
+        self.order_and_limit(
+            &mut clauses,
+            &select.order_by,
+            select.limit.as_ref(),
+            select.offset.as_ref(),
+            indent,
+        );
+        self.lines(indent, clauses)
+    }
+
+    /// Push the ORDER BY, LIMIT and OFFSET that close a SELECT or a UNION onto `clauses`
+    fn order_and_limit(
+        &self,
+        clauses: &mut Vec<String>,
+        order_by: &[OrderByExpr],
+        limit: Option<&Expr>,
+        offset: Option<&Expr>,
+        indent: Option<usize>,
+    ) {
+        if !order_by.is_empty() {
+            let keys: Vec<String> = order_by.iter().map(|o| self.order_by(o)).collect();
+            clauses.push(self.list(&self.kw("ORDER BY"), &keys, indent));
+        }
+
+        if let Some(limit) = limit {
+            clauses.push(format!("{} {}", self.kw("LIMIT"), self.expr(limit)));
+        }
+
+        if let Some(offset) = offset {
+            clauses.push(format!("{} {}", self.kw("OFFSET"), self.expr(offset)));
+        }
+    }
+
+    fn order_by(&self, order: &OrderByExpr) -> String {
+        let mut out = self.expr(&order.expr);
+        match order.asc {
+            Some(true) => out.push_str(&format!(" {}", self.kw("ASC"))),
+            Some(false) => out.push_str(&format!(" {}", self.kw("DESC"))),
+            None => {}
+        }
+        match order.nulls_first {
+            Some(true) => out.push_str(&format!(" {}", self.kw("NULLS FIRST"))),
+            Some(false) => out.push_str(&format!(" {}", self.kw("NULLS LAST"))),
+            None => {}
+        }
+        out
+    }
+
+    fn table_with_joins(&self, table: &TableWithJoins, indent: Option<usize>) -> String {
+        self.fit(indent, |indent| {
+            let mut parts = vec![self.table_factor(&table.relation, indent)];
+            parts.extend(table.joins.iter().map(|join| self.join(join, indent)));
+            self.lines(indent, parts)
+        })
+    }
+
+    fn table_factor(&self, factor: &TableFactor, indent: Option<usize>) -> String {
+        match factor {
+            TableFactor::Table(table) => self.table_ref(table),
+            TableFactor::Derived {
+                subquery, alias, ..
+            } => {
+                let subquery = self.parenthesized(indent, |indent| self.query(subquery, indent));
+                match alias {
+                    Some(alias) => format!("{} {} {}", subquery, self.kw("AS"), alias),
+                    None => subquery,
+                }
+            }
+        }
+    }
+
+    fn table_ref(&self, table: &TableRef) -> String {
+        match table.alias {
+            Some(alias) => format!("{} {} {}", table.name, self.kw("AS"), alias),
+            None => table.name.to_string(),
+        }
+    }
+
+    fn join(&self, join: &Join, indent: Option<usize>) -> String {
+        let mut out = format!(
+            "{} {}",
+            self.kw(&join.kind.to_string()),
+            self.table_factor(&join.relation, indent)
+        );
+        match &join.constraint {
+            JoinConstraint::On(expr) => {
+                out.push_str(&format!(" {} {}", self.kw("ON"), self.expr(expr)))
+            }
+            JoinConstraint::Using(columns) => {
+                out.push_str(&format!(" {} ({})", self.kw("USING"), join_idents(columns)))
+            }
+            JoinConstraint::None => {}
+        }
+        out
+    }
+
+    fn insert(&self, insert: &Insert, indent: Option<usize>) -> String {
+        let mut head = format!("{} {}", self.kw("INSERT INTO"), insert.table);
+        if !insert.columns.is_empty() {
+            head.push_str(&format!(" ({})", join_idents(&insert.columns)));
+        }
+
+        let source = match &insert.source {
+            InsertSource::Values(rows) => {
+                let rows: Vec<String> = rows
+                    .iter()
+                    .map(|row| format!("({})", self.expr_list(row)))
+                    .collect();
+                self.list(&self.kw("VALUES"), &rows, indent)
+            }
+            InsertSource::Query(query) => self.query(query, indent),
+        };
+
+        self.lines(indent, vec![head, source])
+    }
+
+    fn update(&self, update: &Update, indent: Option<usize>) -> String {
+        let assignments: Vec<String> = update
+            .assignments
+            .iter()
+            .map(|a| format!("{} = {}", a.column, self.expr(&a.value)))
+            .collect();
+
+        let mut clauses = vec![
+            format!("{} {}", self.kw("UPDATE"), self.table_ref(&update.table)),
+            self.list(&self.kw("SET"), &assignments, indent),
+        ];
+        if let Some(where_clause) = &update.where_clause {
+            clauses.push(format!("{} {}", self.kw("WHERE"), self.expr(where_clause)));
+        }
+
+        self.lines(indent, clauses)
+    }
+
+    fn delete(&self, delete: &Delete, indent: Option<usize>) -> String {
+        let mut clauses = vec![format!(
+            "{} {}",
+            self.kw("DELETE FROM"),
+            self.table_ref(&delete.table)
+        )];
+        if let Some(where_clause) = &delete.where_clause {
+            clauses.push(format!("{} {}", self.kw("WHERE"), self.expr(where_clause)));
+        }
+
+        self.lines(indent, clauses)
+    }
+
+    fn create_table(&self, create: &CreateTable, indent: Option<usize>) -> String {
+        let mut head = self.kw("CREATE TABLE");
+        if create.if_not_exists {
+            head = format!("{} {}", head, self.kw("IF NOT EXISTS"));
+        }
+
+        let columns: Vec<String> = create.columns.iter().map(|c| self.column_def(c)).collect();
+        match indent {
+            None => format!("{} {} ({})", head, create.name, columns.join(", ")),
+            Some(indent) => {
+                let inner = self.pad(indent + self.options.indent_width);
+                format!(
+                    "{} {} (\n{}{}\n{})",
+                    head,
+                    create.name,
+                    inner,
+                    columns.join(&format!(",\n{}", inner)),
+                    self.pad(indent)
+                )
+            }
+        }
+    }
+
+    fn column_def(&self, column: &ColumnDef) -> String {
+        let mut out = format!("{} {}", column.name, self.kw(&column.data_type.to_string()));
+        for constraint in &column.constraints {
+            let constraint = match constraint {
+                ColumnConstraint::Default(expr) => {
+                    format!("{} {}", self.kw("DEFAULT"), self.expr(expr))
+                }
+                constraint => self.kw(&constraint.to_string()),
+            };
+            out.push(' ');
+            out.push_str(&constraint);
+        }
+        out
+    }
+
+    fn expr_list(&self, exprs: &[Expr]) -> String {
+        exprs
+            .iter()
+            .map(|e| self.expr(e))
+            .collect::<Vec<_>>()
+            .join(", ")
+    }
+
+    fn not(&self, negated: bool) -> String {
+        if negated {
+            format!("{} ", self.kw("NOT"))
+        } else {
+            String::new()
+        }
+    }
+
+    /// Render an operand, parenthesized when it would otherwise bind to the wrong operator
+    fn operand(&self, expr: &Expr, needs_parens: bool) -> String {
+        if needs_parens {
+            format!("({})", self.expr(expr))
+        } else {
+            self.expr(expr)
+        }
+    }
+
+    fn expr(&self, expr: &Expr) -> String {
+        // Left operands may share the parent's precedence, right operands must bind tighter
+        let prec = expr.precedence();
+        let left = |operand: &Expr| self.operand(operand, operand.precedence() < prec);
+        let right = |operand: &Expr| self.operand(operand, operand.precedence() <= prec);
+
+        match &expr.kind {
+            ExprKind::Column(name) => name.to_string(),
+            ExprKind::QualifiedColumn { table, column } => format!("{}.{}", table, column),
+            ExprKind::Literal(literal) => self.literal(literal),
+            ExprKind::Binary {
+                left: lhs,
+                op,
+                right: rhs,
+            } => format!("{} {} {}", left(lhs), self.binary_op(*op), right(rhs)),
+            ExprKind::Unary {
+                op: UnaryOp::Not,
+                expr: operand,
+            } => format!("{} {}", self.kw("NOT"), left(operand)),
+            ExprKind::Unary { op, expr: operand } => {
+                let rendered = self.expr(operand);
+                // `-(1)` must not fold into the literal `-1`, and `- -x` must not become a comment
+                let needs_parens = operand.precedence() < prec
+                    || rendered.starts_with(['-', '+'])
+                    || (*op == UnaryOp::Minus
+                        && matches!(
+                            operand.kind,
+                            ExprKind::Literal(Literal::Number(_) | Literal::Float(_))
+                        ));
+                if needs_parens {
+                    format!("{}({})", op, rendered)
+                } else {
+                    format!("{}{}", op, rendered)
+                }
+            }
+            ExprKind::Function {
+                name,
+                distinct,
+                args,
+            } => {
+                let mut inner = self.expr_list(args);
+                if *distinct {
+                    inner = format!("{} {}", self.kw("DISTINCT"), inner)
+                        .trim_end()
+                        .to_string();
+                }
+                format!("{}({})", name, inner)
+            }
+            ExprKind::IsNull {
+                expr: operand,
+                negated,
+            } => format!(
+                "{} {} {}{}",
+                left(operand),
+                self.kw("IS"),
+                self.not(*negated),
+                self.kw("NULL")
+            ),
+            ExprKind::InList {
+                expr: operand,
+                list,
+                negated,
+            } => format!(
+                "{} {}{} ({})",
+                left(operand),
+                self.not(*negated),
+                self.kw("IN"),
+                self.expr_list(list)
+            ),
+            ExprKind::InSubquery {
+                expr: operand,
+                subquery,
+                negated,
+            } => format!(
+                "{} {}{} ({})",
+                left(operand),
+                self.not(*negated),
+                self.kw("IN"),
+                self.query(subquery, None)
+            ),
+            ExprKind::Between {
+                expr: operand,
+                low,
+                high,
+                negated,
+            } => format!(
+                "{} {}{} {} {} {}",
+                left(operand),
+                self.not(*negated),
+                self.kw("BETWEEN"),
+                right(low),
+                self.kw("AND"),
+                right(high)
+            ),
+            ExprKind::Like {
+                expr: operand,
+                pattern,
+                negated,
+            } => format!(
+                "{} {}{} {}",
+                left(operand),
+                self.not(*negated),
+                self.kw("LIKE"),
+                right(pattern)
+            ),
+            ExprKind::Case {
+                operand,
+                when_then,
+                else_result,
+            } => {
+                let mut parts = vec![self.kw("CASE")];
+                if let Some(operand) = operand {
+                    parts.push(self.expr(operand));
+                }
+                for (when, then) in when_then {
+                    parts.push(format!(
+                        "{} {} {} {}",
+                        self.kw("WHEN"),
+                        self.expr(when),
+                        self.kw("THEN"),
+                        self.expr(then)
+                    ));
+                }
+                if let Some(else_result) = else_result {
+                    parts.push(format!("{} {}", self.kw("ELSE"), self.expr(else_result)));
+                }
+                parts.push(self.kw("END"));
+                parts.join(" ")
+            }
+            ExprKind::Cast {
+                expr: operand,
+                data_type,
+            } => format!(
+                "{}({} {} {})",
+                self.kw("CAST"),
+                self.expr(operand),
+                self.kw("AS"),
+                self.kw(&data_type.to_string())
+            ),
+            ExprKind::Paren(inner) => format!("({})", self.expr(inner)),
+            ExprKind::Star => "*".to_string(),
+        }
+    }
+
+    fn binary_op(&self, op: BinaryOp) -> String {
+        match op {
+            BinaryOp::And | BinaryOp::Or => self.kw(&op.to_string()),
+            op => op.to_string(),
+        }
+    }
+
+    fn literal(&self, literal: &Literal) -> String {
+        match literal {
+            Literal::Boolean(_) | Literal::Null => self.kw(&literal.to_string()),
+            literal => literal.to_string(),
+        }
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use proptest::{prelude::*, sample::select};
+
+    use super::*;
+    use crate::{
+        ast::{Ident, JoinKind, Span},
+        ddl::DataType,
+        dml::Assignment,
+        error::Backtrace,
+        parser::{Parser, parse_script},
+        token::tokenize,
+        visitor::{
+            VisitorMut, walk_assignment_mut, walk_column_def_mut, walk_create_table_mut,
+            walk_cte_mut, walk_delete_mut, walk_expr_mut, walk_insert_mut, walk_join_mut,
+            walk_order_by_mut, walk_select_mut, walk_table_factor_mut, walk_table_with_joins_mut,
+            walk_update_mut, walk_with_mut,
+        },
+    };
+
+    fn parse(sql: &str) -> Statement<'_> {
+        let mut script = parse_script(sql);
+        assert!(script.errors.is_empty(), "{}\n{:?}", sql, script.errors);
+        assert_eq!(script.statements.len(), 1, "{}", sql);
+        script.statements.remove(0)
+    }
+
+    fn parse_expr(sql: &str) -> Expr<'_> {
+        let tokens = tokenize(sql);
+        let backtrace = Backtrace::new();
+        let mut parser = Parser::new(&tokens, &backtrace, sql);
+        parser.parse_expr().unwrap()
+    }
+
+    struct StripParens;
+
+    impl<'a> VisitorMut<'a> for StripParens {
+        fn visit_expr_mut(&mut self, expr: &mut Expr<'a>) {
+            while let ExprKind::Paren(inner) = &mut expr.kind {
+                *expr = std::mem::replace(inner.as_mut(), ExprKind::Star.into());
+            }
+            walk_expr_mut(self, expr);
+        }
+    }
+
+    /// Resets every span, so trees parsed from differently formatted text compare equal
+    pub(crate) struct StripSpans;
+
+    impl<'a> VisitorMut<'a> for StripSpans {
+        fn visit_select_mut(&mut self, select: &mut SelectStmt<'a>) {
+            select.span = Span::default();
+            walk_select_mut(self, select);
+        }
+
+        fn visit_with_mut(&mut self, with: &mut With<'a>) {
+            with.span = Span::default();
+            walk_with_mut(self, with);
+        }
+
+        fn visit_cte_mut(&mut self, cte: &mut CTE<'a>) {
+            cte.span = Span::default();
+            walk_cte_mut(self, cte);
+        }
+
+        fn visit_table_with_joins_mut(&mut self, table: &mut TableWithJoins<'a>) {
+            table.span = Span::default();
+            walk_table_with_joins_mut(self, table);
+        }
+
+        fn visit_table_factor_mut(&mut self, factor: &mut TableFactor<'a>) {
+            if let TableFactor::Derived { span, .. } = factor {
+                *span = Span::default();
+            }
+            walk_table_factor_mut(self, factor);
+        }
+
+        fn visit_table_ref_mut(&mut self, table: &mut TableRef<'a>) {
+            table.span = Span::default();
+        }
+
+        fn visit_column_name_mut(&mut self, column: &mut Ident<'a>) {
+            column.span = Span::default();
+        }
+
+        fn visit_join_mut(&mut self, join: &mut Join<'a>) {
+            join.span = Span::default();
+            walk_join_mut(self, join);
+        }
+
+        fn visit_order_by_mut(&mut self, order: &mut OrderByExpr<'a>) {
+            order.span = Span::default();
+            walk_order_by_mut(self, order);
+        }
+
+        fn visit_expr_mut(&mut self, expr: &mut Expr<'a>) {
+            expr.span = Span::default();
+            walk_expr_mut(self, expr);
+        }
+
+        fn visit_insert_mut(&mut self, insert: &mut Insert<'a>) {
+            insert.span = Span::default();
+            walk_insert_mut(self, insert);
+        }
+
+        fn visit_update_mut(&mut self, update: &mut Update<'a>) {
+            update.span = Span::default();
+            walk_update_mut(self, update);
+        }
+
+        fn visit_assignment_mut(&mut self, assignment: &mut Assignment<'a>) {
+            assignment.span = Span::default();
+            walk_assignment_mut(self, assignment);
+        }
+
+        fn visit_delete_mut(&mut self, delete: &mut Delete<'a>) {
+            delete.span = Span::default();
+            walk_delete_mut(self, delete);
+        }
+
+        fn visit_create_table_mut(&mut self, create: &mut CreateTable<'a>) {
+            create.span = Span::default();
+            walk_create_table_mut(self, create);
+        }
+
+        fn visit_column_def_mut(&mut self, column: &mut ColumnDef<'a>) {
+            column.span = Span::default();
+            walk_column_def_mut(self, column);
+        }
+    }
+
+    #[test]
+    fn test_minimal_parentheses() {
+        let options = FormatOptions::default();
+        let cases = [
+            // Parenthesized source text is part of the AST and survives as written
+            ("((a + b)) * c", "((a + b)) * c"),
+            ("a + (b * c)", "a + (b * c)"),
+            (
+                "NOT (a = b) AND c IS NOT NULL",
+                "NOT (a = b) AND c IS NOT NULL",
+            ),
+            ("- (1) - -2", "-(1) - -2"),
+        ];
+        for (input, expected) in cases {
+            assert_eq!(format_expr(&parse_expr(input), &options), expected);
+        }
+
+        // Without Paren nodes, parentheses come from precedence alone
+        let mut expr = parse_expr("(a - (b - c)) * (x OR NOT y)");
+        StripParens.visit_expr_mut(&mut expr);
+        assert_eq!(format_expr(&expr, &options), "(a - (b - c)) * (x OR NOT y)");
+    }
+
+    #[test]
+    fn test_layout_and_keyword_case() {
+        let sql = "with t as (select a, count(*) from users u join orders o on u.id = o.user_id \
+                   group by a) select * from t where a > 1 order by a desc nulls last limit 10";
+        let stmt = parse(sql);
+
+        let options = FormatOptions {
+            indent_width: 2,
+            keyword_case: KeywordCase::Lower,
+            line_width: 40,
+        };
+        assert_eq!(
+            format_statement(&stmt, &options),
+            "with t as (\n\
+             \x20 select a, count(*)\n\
+             \x20 from\n\
+             \x20   users as u\n\
+             \x20   join orders as o on u.id = o.user_id\n\
+             \x20 group by a\n\
+             )\n\
+             select *\n\
+             from t\n\
+             where a > 1\n\
+             order by a desc nulls last\n\
+             limit 10"
+        );
+
+        let wide = FormatOptions {
+            line_width: 200,
+            ..FormatOptions::default()
+        };
+        assert_eq!(
+            format_statement(&stmt, &wide),
+            "WITH t AS (SELECT a, count(*) FROM users AS u JOIN orders AS o ON u.id = o.user_id \
+             GROUP BY a) SELECT * FROM t WHERE a > 1 ORDER BY a DESC NULLS LAST LIMIT 10"
+        );
+    }
+
+    #[test]
+    fn test_union_display_spacing() {
+        let stmt = parse("SELECT a FROM t UNION SELECT b FROM u");
+        assert_eq!(stmt.to_string(), "SELECT a FROM t UNION SELECT b FROM u");
+    }
+
+    const NAMES: &[&str] = &["a", "b", "total", "t1", "user_id"];
+
+    fn arb_name() -> impl Strategy<Value = &'static str> {
+        select(NAMES)
+    }
+
+    fn arb_literal() -> impl Strategy<Value = Literal<'static>> {
+        prop_oneof![
+            (-1000i64..1000).prop_map(Literal::Number),
+            (-1000i32..1000).prop_map(|n| Literal::Float(n as f64 / 8.0)),
+            select(&["", "abc", "it\\'s"][..]).prop_map(Literal::String),
+            any::<bool>().prop_map(Literal::Boolean),
+            Just(Literal::Null),
+        ]
+    }
+
+    fn arb_expr() -> impl Strategy<Value = Expr<'static>> {
+        let leaf = prop_oneof![
+            arb_name().prop_map(|name| Expr::from(ExprKind::Column(name))),
+            (arb_name(), arb_name()).prop_map(|(table, column)| Expr::from(
+                ExprKind::QualifiedColumn { table, column }
+            )),
+            arb_literal().prop_map(|literal| Expr::from(ExprKind::Literal(literal))),
+        ];
+
+        leaf.prop_recursive(4, 32, 3, |inner| {
+            let op = select(vec![
+                BinaryOp::And,
+                BinaryOp::Or,
+                BinaryOp::Equal,
+                BinaryOp::NotEqual,
+                BinaryOp::Less,
+                BinaryOp::GreaterEqual,
+                BinaryOp::Plus,
+                BinaryOp::Minus,
+                BinaryOp::Multiply,
+                BinaryOp::Divide,
+            ]);
+            let unary = select(vec![UnaryOp::Not, UnaryOp::Minus, UnaryOp::Plus]);
+            let boxed = inner.clone().prop_map(Box::new);
+            prop_oneof![
+                (boxed.clone(), op, boxed.clone())
+                    .prop_map(|(left, op, right)| Expr::from(ExprKind::Binary { left, op, right })),
+                (unary, boxed.clone())
+                    .prop_map(|(op, expr)| Expr::from(ExprKind::Unary { op, expr })),
+                (
+                    select(&["f", "lower", "count"][..]),
+                    any::<bool>(),
+                    prop::collection::vec(inner.clone(), 0..3)
+                )
+                    .prop_map(|(name, distinct, args)| Expr::from(
+                        ExprKind::Function {
+                            name,
+                            distinct,
+                            args
+                        }
+                    )),
+                (boxed.clone(), any::<bool>())
+                    .prop_map(|(expr, negated)| Expr::from(ExprKind::IsNull { expr, negated })),
+                (
+                    boxed.clone(),
+                    prop::collection::vec(inner.clone(), 1..3),
+                    any::<bool>()
+                )
+                    .prop_map(|(expr, list, negated)| Expr::from(
+                        ExprKind::InList {
+                            expr,
+                            list,
+                            negated
+                        }
+                    )),
+                (boxed.clone(), boxed.clone(), boxed.clone(), any::<bool>()).prop_map(
+                    |(expr, low, high, negated)| Expr::from(ExprKind::Between {
+                        expr,
+                        low,
+                        high,
+                        negated
+                    })
+                ),
+                (boxed.clone(), boxed.clone(), any::<bool>()).prop_map(
+                    |(expr, pattern, negated)| Expr::from(ExprKind::Like {
+                        expr,
+                        pattern,
+                        negated
+                    })
+                ),
+                (
+                    prop::option::of(boxed.clone()),
+                    prop::collection::vec((inner.clone(), inner.clone()), 1..3),
+                    prop::option::of(boxed.clone())
+                )
+                    .prop_map(|(operand, when_then, else_result)| Expr::from(
+                        ExprKind::Case {
+                            operand,
+                            when_then,
+                            else_result
+                        }
+                    )),
+                (
+                    boxed,
+                    select(vec![
+                        DataType::Integer,
+                        DataType::Varchar(Some(8)),
+                        DataType::Text
+                    ])
+                )
+                    .prop_map(|(expr, data_type)| Expr::from(ExprKind::Cast { expr, data_type })),
+            ]
+        })
+    }
+
+    fn arb_ident() -> impl Strategy<Value = Ident<'static>> {
+        arb_name().prop_map(|name| Ident {
+            name,
+            span: Span::default(),
+        })
+    }
+
+    fn arb_table_name() -> impl Strategy<Value = TableRef<'static>> {
+        arb_name().prop_map(|name| TableRef {
+            name,
+            alias: None,
+            span: Span::default(),
+        })
+    }
+
+    fn arb_table_ref() -> impl Strategy<Value = TableRef<'static>> {
+        (arb_name(), prop::option::of(arb_name())).prop_map(|(name, alias)| TableRef {
+            name,
+            alias,
+            span: Span::default(),
+        })
+    }
+
+    fn arb_join() -> impl Strategy<Value = Join<'static>> {
+        let kind = select(vec![
+            JoinKind::Inner,
+            JoinKind::Left,
+            JoinKind::Right,
+            JoinKind::Full,
+        ]);
+        let constraint = prop_oneof![
+            arb_expr().prop_map(JoinConstraint::On),
+            prop::collection::vec(arb_ident(), 1..3).prop_map(JoinConstraint::Using),
+        ];
+        prop_oneof![
+            (kind, arb_table_ref(), constraint).prop_map(|(kind, table, constraint)| Join {
+                kind,
+                relation: TableFactor::Table(table),
+                constraint,
+                span: Span::default()
+            }),
+            arb_table_ref().prop_map(|table| Join {
+                kind: JoinKind::Cross,
+                relation: TableFactor::Table(table),
+                constraint: JoinConstraint::None,
+                span: Span::default()
+            }),
+        ]
+    }
+
+    fn arb_select(from: BoxedStrategy<TableFactor<'static>>) -> BoxedStrategy<SelectStmt<'static>> {
+        let exprs = |range| prop::collection::vec(arb_expr(), range);
+        let order_by = (
+            arb_expr(),
+            prop::option::of(any::<bool>()),
+            prop::option::of(any::<bool>()),
+        )
+            .prop_map(|(expr, asc, nulls_first)| OrderByExpr {
+                expr,
+                asc,
+                nulls_first,
+                span: Span::default(),
+            });
+        let table =
+            (from, prop::collection::vec(arb_join(), 0..2)).prop_map(|(relation, joins)| {
+                TableWithJoins {
+                    relation,
+                    joins,
+                    span: Span::default(),
+                }
+            });
+        let count = prop::option::of(
+            (0i64..100).prop_map(|n| Expr::from(ExprKind::Literal(Literal::Number(n)))),
+        );
+
+        (
+            (
+                exprs(1..3),
+                prop::collection::vec(table, 0..3),
+                prop::option::of(arb_expr()),
+            ),
+            (
+                exprs(0..2),
+                prop::option::of(arb_expr()),
+                prop::collection::vec(order_by, 0..2),
+            ),
+            (count.clone(), count),
+        )
+            .prop_map(
+                |(
+                    (projection, from, where_clause),
+                    (group_by, having, order_by),
+                    (limit, offset),
+                )| SelectStmt {
+                    projection,
+                    from,
+                    where_clause,
+                    group_by,
+                    having,
+                    order_by,
+                    limit,
+                    offset,
+                    span: Span::default(),
+                },
+            )
+            .boxed()
+    }
+
+    fn arb_query() -> impl Strategy<Value = Query<'static>> {
+        let simple = arb_select(arb_table_ref().prop_map(TableFactor::Table).boxed())
+            .prop_map(|s| Query::Select(Box::new(s)))
+            .boxed();
+        let factor = prop_oneof![
+            arb_table_ref().prop_map(TableFactor::Table),
+            (simple.clone(), prop::option::of(arb_name())).prop_map(|(q, alias)| {
+                TableFactor::Derived {
+                    subquery: Box::new(q),
+                    alias,
+                    span: Span::default(),
+                }
+            }),
+        ];
+        let select_stmt = arb_select(factor.boxed());
+        let select = select_stmt
+            .clone()
+            .prop_map(|s| Query::Select(Box::new(s)))
+            .boxed();
+        let cte = (
+            arb_name(),
+            prop::option::of(prop::collection::vec(arb_name(), 1..3)),
+            simple.clone(),
+        )
+            .prop_map(|(name, columns, query)| CTE {
+                name,
+                columns,
+                query: Box::new(query),
+                span: Span::default(),
+            });
+
+        prop_oneof![
+            select.clone(),
+            // Trailing clauses belong to the whole UNION, so only the union carries them
+            (select_stmt.clone(), any::<bool>(), select_stmt).prop_map(
+                |(mut left, all, mut right)| {
+                    (left.order_by, left.limit, left.offset) = (Vec::new(), None, None);
+                    let order_by = std::mem::take(&mut right.order_by);
+                    let (limit, offset) = (right.limit.take(), right.offset.take());
+                    Query::Union {
+                        left: Box::new(Query::Select(Box::new(left))),
+                        all,
+                        right: Box::new(Query::Select(Box::new(right))),
+                        order_by,
+                        limit,
+                        offset,
+                    }
+                }
+            ),
+            (any::<bool>(), prop::collection::vec(cte, 1..3), select).prop_map(
+                |(recursive, ctes, query)| {
+                    Query::With {
+                        with: With {
+                            recursive,
+                            ctes,
+                            span: Span::default(),
+                        },
+                        query: Box::new(query),
+                    }
+                }
+            ),
+        ]
+    }
+
+    fn arb_statement() -> impl Strategy<Value = Statement<'static>> {
+        let assignment = (arb_ident(), arb_expr()).prop_map(|(column, value)| Assignment {
+            column,
+            value,
+            span: Span::default(),
+        });
+        let constraint = prop_oneof![
+            Just(ColumnConstraint::PrimaryKey),
+            Just(ColumnConstraint::NotNull),
+            Just(ColumnConstraint::Null),
+            Just(ColumnConstraint::Unique),
+            arb_expr().prop_map(ColumnConstraint::Default),
+        ];
+        let column = (
+            arb_name(),
+            select(vec![
+                DataType::BigInt,
+                DataType::Float,
+                DataType::Varchar(None),
+            ]),
+            prop::collection::vec(constraint, 0..3),
+        )
+            .prop_map(|(name, data_type, constraints)| ColumnDef {
+                name,
+                data_type,
+                constraints,
+                span: Span::default(),
+            });
+        let source = prop_oneof![
+            prop::collection::vec(prop::collection::vec(arb_expr(), 1..3), 1..3)
+                .prop_map(InsertSource::Values),
+            arb_query().prop_map(|q| InsertSource::Query(Box::new(q))),
+        ];
+
+        prop_oneof![
+            arb_query().prop_map(Statement::Query),
+            (
+                arb_table_name(),
+                prop::collection::vec(arb_ident(), 0..3),
+                source
+            )
+                .prop_map(|(table, columns, source)| Statement::Insert(Insert {
+                    table,
+                    columns,
+                    source,
+                    span: Span::default()
+                })),
+            (
+                arb_table_ref(),
+                prop::collection::vec(assignment, 1..3),
+                prop::option::of(arb_expr())
+            )
+                .prop_map(|(table, assignments, where_clause)| Statement::Update(
+                    Update {
+                        table,
+                        assignments,
+                        where_clause,
+                        span: Span::default()
+                    }
+                )),
+            (arb_table_ref(), prop::option::of(arb_expr())).prop_map(|(table, where_clause)| {
+                Statement::Delete(Delete {
+                    table,
+                    where_clause,
+                    span: Span::default(),
+                })
+            }),
+            (
+                arb_table_name(),
+                any::<bool>(),
+                prop::collection::vec(column, 1..3)
+            )
+                .prop_map(|(name, if_not_exists, columns)| Statement::CreateTable(
+                    CreateTable {
+                        name,
+                        if_not_exists,
+                        columns,
+                        span: Span::default()
+                    }
+                )),
+        ]
+    }
+
+    fn arb_options() -> impl Strategy<Value = FormatOptions> {
+        (0usize..6, any::<bool>(), 10usize..120).prop_map(|(indent_width, upper, line_width)| {
+            FormatOptions {
+                indent_width,
+                keyword_case: if upper {
+                    KeywordCase::Upper
+                } else {
+                    KeywordCase::Lower
+                },
+                line_width,
+            }
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn prop_parse_format_round_trip(stmt in arb_statement(), options in arb_options()) {
+            // Parsing once yields a parser-produced AST, which must survive any formatting exactly
+            let sql = format_statement(&stmt, &FormatOptions::default());
+            let mut parsed = parse(&sql);
+            let formatted = format_statement(&parsed, &options);
+            let mut reparsed = parse(&formatted);
+            StripSpans.visit_statement_mut(&mut parsed);
+            StripSpans.visit_statement_mut(&mut reparsed);
+            prop_assert_eq!(reparsed, parsed, "{}", formatted);
+        }
+
+        #[test]
+        fn prop_precedence_preserves_structure(expr in arb_expr()) {
+            let sql = format_expr(&expr, &FormatOptions::default());
+            let mut parsed = parse_expr(&sql);
+            StripParens.visit_expr_mut(&mut parsed);
+            StripSpans.visit_expr_mut(&mut parsed);
+            prop_assert_eq!(parsed, expr, "{}", sql);
+        }
+    }
+}
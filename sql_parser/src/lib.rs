@@ -1,5 +1,11 @@
 pub mod ast;
+pub mod binder;
+pub mod ddl;
+pub mod dml;
 pub mod error;
+pub mod executor;
 pub mod expr;
+pub mod format;
 pub mod parser;
 pub mod token;
+pub mod visitor;
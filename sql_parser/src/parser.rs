@@ -1,21 +1,26 @@
 use crate::{
-    ast::{CTE, Query, SelectStmt, Statement, TableRef, With},
-    error::{Backtrace, ParseError},
+    ast::{
+        CTE, Ident, Join, JoinConstraint, JoinKind, OrderByExpr, Query, SelectStmt, Span,
+        Statement, TableFactor, TableRef, TableWithJoins, With,
+    },
+    error::{Backtrace, ParseError, get_error_context, position_to_line_col},
     expr::Expr,
     token::{Token, TokenKind, tokenize},
 };
 
 pub type ParseResult<T> = Result<T, ParseError>;
 
-pub struct Parser<'a> {
-    tokens: &'a [Token<'a>],
+/// Recursive-descent parser over a token slice; `'t` borrows the tokens and error
+/// tracker, `'a` the SQL text the resulting AST points into
+pub struct Parser<'t, 'a> {
+    tokens: &'t [Token<'a>],
     pos: usize,
-    backtrace: &'a Backtrace,
+    backtrace: &'t Backtrace,
     input: &'a str,
 }
 
-impl<'a> Parser<'a> {
-    pub fn new(tokens: &'a [Token<'a>], backtrace: &'a Backtrace, input: &'a str) -> Self {
+impl<'t, 'a> Parser<'t, 'a> {
+    pub fn new(tokens: &'t [Token<'a>], backtrace: &'t Backtrace, input: &'a str) -> Self {
         Parser {
             tokens,
             pos: 0,
@@ -28,6 +33,10 @@ impl<'a> Parser<'a> {
         self.tokens.get(self.pos)
     }
 
+    pub fn peek(&self) -> Option<&Token<'a>> {
+        self.tokens.get(self.pos + 1)
+    }
+
     pub fn advance(&mut self) -> &Token<'a> {
         let token = &self.tokens[self.pos];
         if self.pos < self.tokens.len() - 1 {
@@ -72,7 +81,7 @@ impl<'a> Parser<'a> {
 
     pub fn parse_identifier(&mut self) -> ParseResult<&'a str> {
         match self.current() {
-            Some(token) if token.kind == TokenKind::Identifier => Ok(self.advance().text),
+            Some(token) if token.kind.is_identifier() => Ok(self.advance().text),
             Some(token) => {
                 self.backtrace.track_error(
                     token.span.start,
@@ -95,10 +104,63 @@ impl<'a> Parser<'a> {
         }
     }
 
+    /// An identifier together with its span
+    pub(crate) fn parse_ident(&mut self) -> ParseResult<Ident<'a>> {
+        let start = self.start();
+        let name = self.parse_identifier()?;
+        Ok(Ident {
+            name,
+            span: self.span_from(start),
+        })
+    }
+
+    /// A table name that takes no alias, as after INSERT INTO or CREATE TABLE
+    pub(crate) fn parse_table_name(&mut self) -> ParseResult<TableRef<'a>> {
+        let name = self.parse_ident()?;
+        Ok(TableRef {
+            name: name.name,
+            alias: None,
+            span: name.span,
+        })
+    }
+
+    /// Byte offset at which the current token starts
+    pub(crate) fn start(&self) -> usize {
+        self.current().map_or(self.input.len(), |t| t.span.start)
+    }
+
+    /// Span from `start` to the end of the last consumed token
+    pub(crate) fn span_from(&self, start: usize) -> Span {
+        let end = match self.pos.checked_sub(1) {
+            Some(prev) => self.tokens[prev].span.end,
+            None => start,
+        };
+        Span::new(start, end.max(start))
+    }
+
+    /// Record every alternative that would have been accepted at the current token
+    pub fn error_expected_one_of(&self, expected: &[&str]) -> ParseError {
+        let (pos, found) = match self.current() {
+            Some(token) => (token.span.start, Some(token.text)),
+            None => (self.input.len(), None),
+        };
+        for expected in expected {
+            self.backtrace.track_error(pos, expected, found, self.input);
+        }
+        self.backtrace.get_error(self.input)
+    }
+
+    /// An error with its own message, located at the current token
     pub fn error_at_current(&self, msg: &str) -> ParseError {
-        let mut error = self.backtrace.get_error(self.input);
-        error.message = msg.to_string();
-        error
+        let pos = self.start();
+        let (line, column) = position_to_line_col(self.input, pos);
+        ParseError {
+            message: msg.to_string(),
+            line,
+            column,
+            suggestion: None,
+            context: get_error_context(self.input, pos),
+        }
     }
 
     /// Check if current token might be a typo for the expected keyword
@@ -107,19 +169,19 @@ impl<'a> Parser<'a> {
         expected_keyword: &str,
         starts_with_chars: &[char],
     ) -> bool {
-        if let Some(token) = self.current() {
-            if token.kind == TokenKind::Identifier {
-                let text_upper = token.text.to_uppercase();
-                for &ch in starts_with_chars {
-                    if text_upper.starts_with(ch) {
-                        self.backtrace.track_error(
-                            token.span.start,
-                            expected_keyword,
-                            Some(token.text),
-                            self.input,
-                        );
-                        return true;
-                    }
+        if let Some(token) = self.current()
+            && token.kind == TokenKind::Identifier
+        {
+            let text_upper = token.text.to_uppercase();
+            for &ch in starts_with_chars {
+                if text_upper.starts_with(ch) {
+                    self.backtrace.track_error(
+                        token.span.start,
+                        expected_keyword,
+                        Some(token.text),
+                        self.input,
+                    );
+                    return true;
                 }
             }
         }
@@ -128,18 +190,14 @@ impl<'a> Parser<'a> {
 
     /// Check if current token might be a typo for WHERE keyword (with substring check)
     fn check_for_where_typo(&mut self) -> bool {
-        if let Some(token) = self.current() {
-            if token.kind == TokenKind::Identifier {
-                let text_upper = token.text.to_uppercase();
-                if text_upper.starts_with('W') || text_upper.contains("HER") {
-                    self.backtrace.track_error(
-                        token.span.start,
-                        "WHERE",
-                        Some(token.text),
-                        self.input,
-                    );
-                    return true;
-                }
+        if let Some(token) = self.current()
+            && token.kind == TokenKind::Identifier
+        {
+            let text_upper = token.text.to_uppercase();
+            if text_upper.starts_with('W') || text_upper.contains("HER") {
+                self.backtrace
+                    .track_error(token.span.start, "WHERE", Some(token.text), self.input);
+                return true;
             }
         }
         false
@@ -147,22 +205,18 @@ impl<'a> Parser<'a> {
 
     /// Check if current token is a specific WHERE typo pattern
     fn check_for_specific_where_typos(&mut self) -> bool {
-        if let Some(token) = self.current() {
-            if token.kind == TokenKind::Identifier {
-                let text = token.text.to_uppercase();
-                if text.starts_with("WHEER")
-                    || text.starts_with("WHER")
-                    || text.starts_with("WHRE")
-                    || text == "WHEER"
-                {
-                    self.backtrace.track_error(
-                        token.span.start,
-                        "WHERE",
-                        Some(token.text),
-                        self.input,
-                    );
-                    return true;
-                }
+        if let Some(token) = self.current()
+            && token.kind == TokenKind::Identifier
+        {
+            let text = token.text.to_uppercase();
+            if text.starts_with("WHEER")
+                || text.starts_with("WHER")
+                || text.starts_with("WHRE")
+                || text == "WHEER"
+            {
+                self.backtrace
+                    .track_error(token.span.start, "WHERE", Some(token.text), self.input);
+                return true;
             }
         }
         false
@@ -174,23 +228,55 @@ impl<'a> Parser<'a> {
             let query = Box::new(self.parse_query()?);
             Ok(Query::With { with, query })
         } else {
-            let select = self.parse_select()?;
-
-            if self.try_consume(TokenKind::Union) {
-                let all = self.try_consume(TokenKind::All);
-                let right = Box::new(self.parse_query()?);
-                Ok(Query::Union {
-                    left: Box::new(Query::Select(Box::new(select))),
-                    all,
-                    right,
-                })
-            } else {
-                Ok(Query::Select(Box::new(select)))
+            let start = self.start();
+            let mut query = self.parse_union_operands()?;
+            let (order_by, limit, offset) = self.parse_order_and_limit()?;
+
+            match &mut query {
+                Query::Select(select) => {
+                    select.order_by = order_by;
+                    select.limit = limit;
+                    select.offset = offset;
+                    select.span = self.span_from(start);
+                }
+                Query::Union {
+                    order_by: union_order_by,
+                    limit: union_limit,
+                    offset: union_offset,
+                    ..
+                } => {
+                    *union_order_by = order_by;
+                    *union_limit = limit;
+                    *union_offset = offset;
+                }
+                Query::With { .. } => unreachable!("a UNION operand is never a WITH"),
             }
+            Ok(query)
+        }
+    }
+
+    /// A SELECT body, or a chain of them joined by UNION, with the trailing clauses that apply
+    /// to the whole query left for the caller
+    fn parse_union_operands(&mut self) -> ParseResult<Query<'a>> {
+        let left = Query::Select(Box::new(self.parse_select_body()?));
+
+        if self.try_consume(TokenKind::Union) {
+            let all = self.try_consume(TokenKind::All);
+            let right = self.parse_union_operands()?;
+            Ok(Query::Union {
+                left: Box::new(left),
+                all,
+                right: Box::new(right),
+                order_by: Vec::new(),
+                limit: None,
+                offset: None,
+            })
+        } else {
+            Ok(left)
         }
     }
 
-    fn parse_identifier_lis(&mut self) -> ParseResult<Vec<&'a str>> {
+    pub(crate) fn parse_identifier_lis(&mut self) -> ParseResult<Vec<&'a str>> {
         let mut idents = vec![self.parse_identifier()?];
 
         while self.try_consume(TokenKind::Comma) {
@@ -199,7 +285,17 @@ impl<'a> Parser<'a> {
         Ok(idents)
     }
 
+    pub(crate) fn parse_ident_list(&mut self) -> ParseResult<Vec<Ident<'a>>> {
+        let mut idents = vec![self.parse_ident()?];
+
+        while self.try_consume(TokenKind::Comma) {
+            idents.push(self.parse_ident()?);
+        }
+        Ok(idents)
+    }
+
     fn parse_cte(&mut self) -> ParseResult<CTE<'a>> {
+        let start = self.start();
         let name = self.parse_identifier()?;
         let columns = if self.current().map(|t| t.kind) == Some(TokenKind::LeftParen) {
             self.advance();
@@ -218,10 +314,12 @@ impl<'a> Parser<'a> {
             name,
             columns,
             query,
+            span: self.span_from(start),
         })
     }
 
     pub fn parse_with(&mut self) -> ParseResult<With<'a>> {
+        let start = self.start();
         self.expect(TokenKind::With)?;
 
         let recursive = self.try_consume(TokenKind::Recursive);
@@ -231,10 +329,14 @@ impl<'a> Parser<'a> {
         while self.try_consume(TokenKind::Comma) {
             ctes.push(self.parse_cte()?);
         }
-        Ok(With { recursive, ctes })
+        Ok(With {
+            recursive,
+            ctes,
+            span: self.span_from(start),
+        })
     }
 
-    fn parse_expr_list(&mut self) -> ParseResult<Vec<Expr<'a>>> {
+    pub(crate) fn parse_expr_list(&mut self) -> ParseResult<Vec<Expr<'a>>> {
         let mut exprs = vec![self.parse_expr()?];
 
         while self.try_consume(TokenKind::Comma) {
@@ -243,28 +345,189 @@ impl<'a> Parser<'a> {
         Ok(exprs)
     }
 
-    fn parse_table_ref(&mut self) -> ParseResult<TableRef<'a>> {
-        let name = self.parse_identifier()?;
+    fn parse_table_alias(&mut self) -> ParseResult<Option<&'a str>> {
+        if self.try_consume(TokenKind::As) {
+            return Ok(Some(self.parse_identifier()?));
+        }
 
-        let alias = if self.try_consume(TokenKind::As) {
-            Some(self.parse_identifier()?)
-        } else if let Some(token) = self.current() {
-            if token.kind == TokenKind::Identifier {
+        match self.current() {
+            Some(token) if token.kind == TokenKind::Identifier => {
                 if self.check_for_specific_where_typos() {
                     return Err(self.backtrace.get_error(self.input));
                 }
-                Some(self.parse_identifier()?)
+                Ok(Some(self.parse_identifier()?))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    pub(crate) fn parse_table_ref(&mut self) -> ParseResult<TableRef<'a>> {
+        let start = self.start();
+        let name = self.parse_identifier()?;
+        let alias = self.parse_table_alias()?;
+
+        Ok(TableRef {
+            name,
+            alias,
+            span: self.span_from(start),
+        })
+    }
+
+    fn parse_table_factor(&mut self) -> ParseResult<TableFactor<'a>> {
+        let start = self.start();
+        if self.try_consume(TokenKind::LeftParen) {
+            let subquery = Box::new(self.parse_query()?);
+            self.expect(TokenKind::RightParen)?;
+            let alias = self.parse_table_alias()?;
+            Ok(TableFactor::Derived {
+                subquery,
+                alias,
+                span: self.span_from(start),
+            })
+        } else {
+            Ok(TableFactor::Table(self.parse_table_ref()?))
+        }
+    }
+
+    /// Consume a join operator such as `JOIN`, `LEFT OUTER JOIN` or `CROSS JOIN`
+    fn parse_join_kind(&mut self) -> ParseResult<Option<JoinKind>> {
+        let kind = match self.current().map(|t| t.kind) {
+            Some(TokenKind::Join) => {
+                self.advance();
+                return Ok(Some(JoinKind::Inner));
+            }
+            Some(TokenKind::Inner) => JoinKind::Inner,
+            Some(TokenKind::Left) => JoinKind::Left,
+            Some(TokenKind::Right) => JoinKind::Right,
+            Some(TokenKind::Full) => JoinKind::Full,
+            Some(TokenKind::Cross) => JoinKind::Cross,
+            _ => return Ok(None),
+        };
+        self.advance();
+
+        if matches!(kind, JoinKind::Left | JoinKind::Right | JoinKind::Full) {
+            self.try_consume(TokenKind::Outer);
+        }
+        self.expect(TokenKind::Join)?;
+        Ok(Some(kind))
+    }
+
+    fn parse_join_constraint(&mut self, kind: JoinKind) -> ParseResult<JoinConstraint<'a>> {
+        if kind == JoinKind::Cross {
+            return Ok(JoinConstraint::None);
+        }
+
+        if self.try_consume(TokenKind::On) {
+            return Ok(JoinConstraint::On(self.parse_expr()?));
+        }
+
+        if self.try_consume(TokenKind::Using) {
+            self.expect(TokenKind::LeftParen)?;
+            let columns = self.parse_ident_list()?;
+            self.expect(TokenKind::RightParen)?;
+            return Ok(JoinConstraint::Using(columns));
+        }
+
+        if let Some(token) = self.current() {
+            for expected in ["ON", "USING"] {
+                self.backtrace.track_error(
+                    token.span.start,
+                    expected,
+                    Some(token.text),
+                    self.input,
+                );
+            }
+        }
+        Err(self.backtrace.get_error(self.input))
+    }
+
+    fn parse_table_with_joins(&mut self) -> ParseResult<TableWithJoins<'a>> {
+        let start = self.start();
+        let relation = self.parse_table_factor()?;
+        let mut joins = Vec::new();
+
+        loop {
+            let join_start = self.start();
+            let Some(kind) = self.parse_join_kind()? else {
+                break;
+            };
+            let relation = self.parse_table_factor()?;
+            let constraint = self.parse_join_constraint(kind)?;
+            joins.push(Join {
+                kind,
+                relation,
+                constraint,
+                span: self.span_from(join_start),
+            });
+        }
+
+        Ok(TableWithJoins {
+            relation,
+            joins,
+            span: self.span_from(start),
+        })
+    }
+
+    fn parse_from(&mut self) -> ParseResult<Vec<TableWithJoins<'a>>> {
+        let mut tables = vec![self.parse_table_with_joins()?];
+
+        while self.try_consume(TokenKind::Comma) {
+            tables.push(self.parse_table_with_joins()?);
+        }
+        Ok(tables)
+    }
+
+    fn parse_order_by_expr(&mut self) -> ParseResult<OrderByExpr<'a>> {
+        let expr = self.parse_expr()?;
+        let start = expr.span.start;
+
+        let asc = if self.try_consume(TokenKind::Asc) {
+            Some(true)
+        } else if self.try_consume(TokenKind::Desc) {
+            Some(false)
+        } else {
+            None
+        };
+
+        let nulls_first = if self.try_consume(TokenKind::Nulls) {
+            if self.try_consume(TokenKind::First) {
+                Some(true)
             } else {
-                None
+                self.expect(TokenKind::Last)?;
+                Some(false)
             }
         } else {
             None
         };
 
-        Ok(TableRef { name, alias })
+        Ok(OrderByExpr {
+            expr,
+            asc,
+            nulls_first,
+            span: self.span_from(start),
+        })
+    }
+
+    fn parse_order_by_list(&mut self) -> ParseResult<Vec<OrderByExpr<'a>>> {
+        let mut items = vec![self.parse_order_by_expr()?];
+
+        while self.try_consume(TokenKind::Comma) {
+            items.push(self.parse_order_by_expr()?);
+        }
+        Ok(items)
     }
 
     pub fn parse_select(&mut self) -> ParseResult<SelectStmt<'a>> {
+        let start = self.start();
+        let mut select = self.parse_select_body()?;
+        (select.order_by, select.limit, select.offset) = self.parse_order_and_limit()?;
+        select.span = self.span_from(start);
+        Ok(select)
+    }
+
+    /// SELECT through HAVING, the part of a SELECT that can be a UNION operand
+    fn parse_select_body(&mut self) -> ParseResult<SelectStmt<'a>> {
+        let start = self.start();
         let mut had_errors = false;
 
         match self.current() {
@@ -307,19 +570,15 @@ impl<'a> Parser<'a> {
             }
         }
 
-        let projection = if self.try_consume(TokenKind::Star) {
-            vec![Expr::Star]
-        } else {
-            self.parse_expr_list()?
-        };
+        let projection = self.parse_expr_list()?;
 
         let from = if self.try_consume(TokenKind::From) {
-            Some(self.parse_table_ref()?)
+            self.parse_from()?
         } else {
             if self.check_for_keyword_typo("FROM", &['F']) {
                 return Err(self.backtrace.get_error(self.input));
             }
-            None
+            Vec::new()
         };
 
         let where_clause = if self.try_consume(TokenKind::Where) {
@@ -331,6 +590,19 @@ impl<'a> Parser<'a> {
             None
         };
 
+        let group_by = if self.try_consume(TokenKind::Group) {
+            self.expect(TokenKind::By)?;
+            self.parse_expr_list()?
+        } else {
+            Vec::new()
+        };
+
+        let having = if self.try_consume(TokenKind::Having) {
+            Some(self.parse_expr()?)
+        } else {
+            None
+        };
+
         if had_errors {
             return Err(self.backtrace.get_error(self.input));
         }
@@ -339,35 +611,60 @@ impl<'a> Parser<'a> {
             projection,
             from,
             where_clause,
+            group_by,
+            having,
+            order_by: Vec::new(),
+            limit: None,
+            offset: None,
+            span: self.span_from(start),
         })
     }
 
-    pub fn parse_statement(&mut self) -> ParseResult<Statement<'a>> {
-        let start_pos = self.pos;
+    /// The ORDER BY, LIMIT and OFFSET that close a SELECT or a UNION
+    fn parse_order_and_limit(
+        &mut self,
+    ) -> ParseResult<(Vec<OrderByExpr<'a>>, Option<Expr<'a>>, Option<Expr<'a>>)> {
+        let order_by = if self.try_consume(TokenKind::Order) {
+            self.expect(TokenKind::By)?;
+            self.parse_order_by_list()?
+        } else {
+            Vec::new()
+        };
 
-        if self.current().map(|t| t.kind) == Some(TokenKind::With) {
-            match self.parse_with() {
-                Ok(with) => match self.parse_query() {
-                    Ok(query) => {
-                        return Ok(Statement::Query(Query::With {
-                            with,
-                            query: Box::new(query),
-                        }));
-                    }
-                    Err(_) => {
-                        self.pos = start_pos;
-                    }
-                },
-                Err(_) => {
-                    self.pos = start_pos;
-                }
+        let limit = if self.try_consume(TokenKind::Limit) {
+            Some(self.parse_expr()?)
+        } else {
+            None
+        };
+
+        let offset = if self.try_consume(TokenKind::Offset) {
+            Some(self.parse_expr()?)
+        } else {
+            None
+        };
+
+        Ok((order_by, limit, offset))
+    }
+
+    pub fn parse_statement(&mut self) -> ParseResult<Statement<'a>> {
+        match self.current().map(|t| t.kind) {
+            Some(TokenKind::Insert) => return self.parse_insert().map(Statement::Insert),
+            Some(TokenKind::Update) => return self.parse_update().map(Statement::Update),
+            Some(TokenKind::Delete) => return self.parse_delete().map(Statement::Delete),
+            Some(TokenKind::Create) => {
+                return self.parse_create_table().map(Statement::CreateTable);
             }
+            // A query that starts right fails with its own error, not the statement keywords
+            Some(TokenKind::Select | TokenKind::With) => {
+                return self.parse_query().map(Statement::Query);
+            }
+            _ => {}
         }
 
-        self.pos = start_pos;
-        match self.parse_select() {
-            Ok(stmt) => return Ok(Statement::Query(Query::Select(Box::new(stmt)))),
-            Err(_) => {}
+        // Anything else may be a misspelled SELECT, which parse_query reports
+        let start_pos = self.pos;
+        if let Ok(query) = self.parse_query() {
+            return Ok(Statement::Query(query));
         }
         self.pos = start_pos;
         if let Some(token) = self.current() {
@@ -377,12 +674,69 @@ impl<'a> Parser<'a> {
                 .track_error(token.span.start, "UPDATE", Some(token.text), self.input);
             self.backtrace
                 .track_error(token.span.start, "DELETE", Some(token.text), self.input);
+            self.backtrace
+                .track_error(token.span.start, "CREATE", Some(token.text), self.input);
             self.backtrace
                 .track_error(token.span.start, "WITH", Some(token.text), self.input);
         }
 
         Err(self.backtrace.get_error(self.input))
     }
+
+    /// Fail unless every token up to the end of the statement has been consumed
+    pub fn expect_end_of_statement(&mut self) -> ParseResult<()> {
+        match self.current() {
+            Some(token) if token.kind != TokenKind::Eof => {
+                self.backtrace.track_error(
+                    token.span.start,
+                    "end of statement",
+                    Some(token.text),
+                    self.input,
+                );
+                Err(self.backtrace.get_error(self.input))
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Result of parsing a multi-statement script: every statement that parsed, in order,
+/// and one error for each statement that did not
+#[derive(Debug, Default)]
+pub struct Script<'a> {
+    pub statements: Vec<Statement<'a>>,
+    pub errors: Vec<ParseError>,
+}
+
+/// Parse a `;`-separated script, recovering at each statement boundary so that one
+/// bad statement doesn't hide errors or successes in the rest of the input
+pub fn parse_script(sql: &str) -> Script<'_> {
+    let tokens = tokenize(sql);
+    let mut script = Script::default();
+
+    // The trailing Eof token is replaced per statement below
+    for segment in tokens[..tokens.len() - 1].split(|t| t.kind == TokenKind::Semicolon) {
+        let Some(last) = segment.last() else {
+            continue;
+        };
+
+        let mut statement_tokens = segment.to_vec();
+        let end = last.span.end;
+        statement_tokens.push(Token::new("", TokenKind::Eof, end..end));
+
+        let backtrace = Backtrace::new();
+        let mut parser = Parser::new(&statement_tokens, &backtrace, sql);
+        let result = parser
+            .parse_statement()
+            .and_then(|stmt| parser.expect_end_of_statement().map(|_| stmt));
+
+        match result {
+            Ok(stmt) => script.statements.push(stmt),
+            Err(err) => script.errors.push(err),
+        }
+    }
+
+    script
 }
 
 pub fn parse_sql(sql: &str) -> Result<(), ParseError> {
@@ -403,3 +757,148 @@ pub fn parse_sql_to_string(sql: &str) -> Result<String, ParseError> {
     let stmt = parser.parse_statement()?;
     Ok(format!("{:?}", stmt))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_script_recovers_after_errors() {
+        let sql = "SELECT a FROM t;\nSELCT * FORM users;\n\nDELETE FROM t WHERE a = 1;;\n\
+                   INSERT INTO t VALUES (1) garbage;\nSELECT 1 UNION SELECT 2";
+
+        let script = parse_script(sql);
+
+        assert_eq!(script.statements.len(), 3);
+        assert!(matches!(script.statements[1], Statement::Delete(_)));
+        assert!(matches!(
+            script.statements[2],
+            Statement::Query(Query::Union { .. })
+        ));
+
+        assert_eq!(script.errors.len(), 2);
+        assert_eq!(script.errors[0].line, 2);
+        assert!(script.errors[0].context.is_some());
+        assert_eq!(script.errors[1].line, 5);
+        assert!(script.errors[1].message.contains("end of statement"));
+        assert!(script.errors[1].message.contains("garbage"));
+    }
+
+    #[test]
+    fn test_parse_script_keeps_semicolons_in_strings() {
+        let script = parse_script("SELECT 'a;b' FROM t; SELECT 2;");
+
+        assert!(script.errors.is_empty());
+        assert_eq!(script.statements.len(), 2);
+        assert_eq!(script.statements[0].to_string(), "SELECT 'a;b' FROM t");
+    }
+
+    #[test]
+    fn test_keywords_name_tables_and_columns() {
+        let script = parse_script(
+            "SELECT key, first, last FROM orders ORDER BY first DESC NULLS LAST LIMIT 1;\
+             SELECT left.key FROM left LEFT JOIN right ON left.key = right.key OFFSET 2;\
+             CREATE TABLE settings (key TEXT PRIMARY KEY, default INTEGER DEFAULT 0);\
+             UPDATE table SET set = 1, limit = 2",
+        );
+
+        assert!(script.errors.is_empty(), "{:?}", script.errors);
+        assert_eq!(
+            script.statements[0].to_string(),
+            "SELECT key, first, last FROM orders ORDER BY first DESC NULLS LAST LIMIT 1"
+        );
+        assert_eq!(
+            script.statements[1].to_string(),
+            "SELECT left.key FROM left LEFT JOIN right ON left.key = right.key OFFSET 2"
+        );
+        assert_eq!(
+            script.statements[3].to_string(),
+            "UPDATE table SET set = 1, limit = 2"
+        );
+    }
+
+    #[test]
+    fn test_query_errors_are_not_replaced() {
+        for (sql, message, column) in [
+            (
+                "SELECT name FROM orders JOIN ON id = 1",
+                "Expected identifier, found: `ON`",
+                30,
+            ),
+            ("SELECT name FROM orders WHERE", "Expected expression", 30),
+            ("SELECT name, FROM orders", "Expected expression", 14),
+        ] {
+            let tokens = tokenize(sql);
+            let backtrace = Backtrace::new();
+            let mut parser = Parser::new(&tokens, &backtrace, sql);
+            let err = parser.parse_statement().unwrap_err();
+
+            assert_eq!(err.message, message, "{sql}");
+            assert_eq!((err.line, err.column), (1, column), "{sql}");
+        }
+    }
+
+    #[test]
+    fn test_spans_cover_source_text() {
+        let sql = "SELECT 1;\nSELECT a + b * 2 FROM users u JOIN (SELECT 1) AS d ON u.id = d.x \
+                   WHERE NOT (a > 1) ORDER BY a DESC";
+        let script = parse_script(sql);
+        let Statement::Query(Query::Select(select)) = &script.statements[1] else {
+            panic!("expected a SELECT");
+        };
+
+        assert_eq!(&sql[script.statements[1].span().range()], &sql[10..]);
+        assert_eq!(&sql[select.projection[0].span.range()], "a + b * 2");
+        let from = &select.from[0];
+        assert_eq!(&sql[from.relation.span().range()], "users u");
+        assert_eq!(
+            &sql[from.joins[0].span.range()],
+            "JOIN (SELECT 1) AS d ON u.id = d.x"
+        );
+        assert_eq!(
+            &sql[from.joins[0].relation.span().range()],
+            "(SELECT 1) AS d"
+        );
+        let where_clause = select.where_clause.as_ref().unwrap();
+        assert_eq!(&sql[where_clause.span.range()], "NOT (a > 1)");
+        assert_eq!(&sql[select.order_by[0].span.range()], "a DESC");
+    }
+
+    #[test]
+    fn test_trailing_clauses_belong_to_the_union() {
+        let sql =
+            "SELECT a FROM t UNION SELECT b FROM u UNION ALL SELECT c FROM v ORDER BY a LIMIT 2";
+        let script = parse_script(sql);
+        let Statement::Query(query) = &script.statements[0] else {
+            panic!("expected a query");
+        };
+        let Query::Union {
+            right,
+            order_by,
+            limit,
+            ..
+        } = query
+        else {
+            panic!("expected a UNION");
+        };
+
+        assert_eq!(order_by.len(), 1);
+        assert!(limit.is_some());
+        assert_eq!(&sql[query.span().range()], sql);
+        let Query::Union {
+            right,
+            order_by,
+            limit,
+            ..
+        } = &**right
+        else {
+            panic!("expected a nested UNION");
+        };
+        assert!(order_by.is_empty() && limit.is_none());
+        let Query::Select(last) = &**right else {
+            panic!("expected a SELECT");
+        };
+        assert!(last.order_by.is_empty() && last.limit.is_none());
+        assert_eq!(&sql[last.span.range()], "SELECT c FROM v");
+    }
+}
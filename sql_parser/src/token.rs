@@ -45,6 +45,144 @@ pub enum TokenKind {
     #[regex("(?i)DELETE")]
     Delete,
 
+    #[regex("(?i)INTO")]
+    Into,
+
+    #[regex("(?i)VALUES")]
+    Values,
+
+    #[regex("(?i)SET")]
+    Set,
+
+    #[regex("(?i)CREATE")]
+    Create,
+
+    #[regex("(?i)TABLE")]
+    Table,
+
+    #[regex("(?i)IF")]
+    If,
+
+    #[regex("(?i)NOT")]
+    Not,
+
+    #[regex("(?i)EXISTS")]
+    Exists,
+
+    #[regex("(?i)NULL")]
+    Null,
+
+    #[regex("(?i)PRIMARY")]
+    Primary,
+
+    #[regex("(?i)KEY")]
+    Key,
+
+    #[regex("(?i)UNIQUE")]
+    Unique,
+
+    #[regex("(?i)DEFAULT")]
+    Default,
+
+    #[regex("(?i)TRUE")]
+    True,
+
+    #[regex("(?i)FALSE")]
+    False,
+
+    #[regex("(?i)IS")]
+    Is,
+
+    #[regex("(?i)IN")]
+    In,
+
+    #[regex("(?i)BETWEEN")]
+    Between,
+
+    #[regex("(?i)LIKE")]
+    Like,
+
+    #[regex("(?i)CASE")]
+    Case,
+
+    #[regex("(?i)WHEN")]
+    When,
+
+    #[regex("(?i)THEN")]
+    Then,
+
+    #[regex("(?i)ELSE")]
+    Else,
+
+    #[regex("(?i)END")]
+    End,
+
+    #[regex("(?i)CAST")]
+    Cast,
+
+    #[regex("(?i)DISTINCT")]
+    Distinct,
+
+    #[regex("(?i)JOIN")]
+    Join,
+
+    #[regex("(?i)INNER")]
+    Inner,
+
+    #[regex("(?i)LEFT")]
+    Left,
+
+    #[regex("(?i)RIGHT")]
+    Right,
+
+    #[regex("(?i)FULL")]
+    Full,
+
+    #[regex("(?i)OUTER")]
+    Outer,
+
+    #[regex("(?i)CROSS")]
+    Cross,
+
+    #[regex("(?i)ON")]
+    On,
+
+    #[regex("(?i)USING")]
+    Using,
+
+    #[regex("(?i)GROUP")]
+    Group,
+
+    #[regex("(?i)BY")]
+    By,
+
+    #[regex("(?i)HAVING")]
+    Having,
+
+    #[regex("(?i)ORDER")]
+    Order,
+
+    #[regex("(?i)ASC")]
+    Asc,
+
+    #[regex("(?i)DESC")]
+    Desc,
+
+    #[regex("(?i)NULLS")]
+    Nulls,
+
+    #[regex("(?i)FIRST")]
+    First,
+
+    #[regex("(?i)LAST")]
+    Last,
+
+    #[regex("(?i)LIMIT")]
+    Limit,
+
+    #[regex("(?i)OFFSET")]
+    Offset,
+
     // Identifiers and literals
     #[regex(r"[a-zA-Z_][a-zA-Z0-9_]*")]
     Identifier,
@@ -52,10 +190,10 @@ pub enum TokenKind {
     #[regex(r"'([^'\\]|\\.)*'")]
     String,
 
-    #[regex(r"-?[0-9]+")]
+    #[regex(r"[0-9]+")]
     Number,
 
-    #[regex(r"-?[0-9]+\.[0-9]+")]
+    #[regex(r"[0-9]+\.[0-9]+")]
     Float,
 
     // Operators
@@ -100,6 +238,9 @@ pub enum TokenKind {
     #[token(",")]
     Comma,
 
+    #[token(".")]
+    Dot,
+
     #[token(";")]
     Semicolon,
 
@@ -107,6 +248,41 @@ pub enum TokenKind {
     Eof,
 }
 
+impl TokenKind {
+    /// Keywords that only mean something at one spot of the grammar; anywhere else they
+    /// name tables and columns like any identifier
+    pub fn is_unreserved_keyword(self) -> bool {
+        matches!(
+            self,
+            TokenKind::Recursive
+                | TokenKind::Table
+                | TokenKind::Set
+                | TokenKind::Primary
+                | TokenKind::Key
+                | TokenKind::Unique
+                | TokenKind::Default
+                | TokenKind::Inner
+                | TokenKind::Left
+                | TokenKind::Right
+                | TokenKind::Full
+                | TokenKind::Outer
+                | TokenKind::Cross
+                | TokenKind::Asc
+                | TokenKind::Desc
+                | TokenKind::Nulls
+                | TokenKind::First
+                | TokenKind::Last
+                | TokenKind::Limit
+                | TokenKind::Offset
+        )
+    }
+
+    /// Whether a token of this kind can name a table or column
+    pub fn is_identifier(self) -> bool {
+        self == TokenKind::Identifier || self.is_unreserved_keyword()
+    }
+}
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Token<'a> {
     pub text: &'a str,
@@ -0,0 +1,742 @@
+use crate::{
+    ast::{
+        CTE, Ident, Join, JoinConstraint, OrderByExpr, Query, SelectStmt, Statement, TableFactor,
+        TableRef, TableWithJoins, With,
+    },
+    ddl::{ColumnConstraint, ColumnDef, CreateTable},
+    dml::{Assignment, Delete, Insert, InsertSource, Update},
+    expr::{Expr, ExprKind},
+};
+
+/// Read-only AST traversal. Each `visit_*` method defaults to the matching `walk_*`
+/// function; override it to act on a node and call `walk_*` to keep descending.
+pub trait Visitor<'a> {
+    fn visit_statement(&mut self, stmt: &Statement<'a>) {
+        walk_statement(self, stmt)
+    }
+
+    fn visit_query(&mut self, query: &Query<'a>) {
+        walk_query(self, query)
+    }
+
+    fn visit_select(&mut self, select: &SelectStmt<'a>) {
+        walk_select(self, select)
+    }
+
+    fn visit_with(&mut self, with: &With<'a>) {
+        walk_with(self, with)
+    }
+
+    fn visit_cte(&mut self, cte: &CTE<'a>) {
+        walk_cte(self, cte)
+    }
+
+    fn visit_table_with_joins(&mut self, table: &TableWithJoins<'a>) {
+        walk_table_with_joins(self, table)
+    }
+
+    fn visit_table_factor(&mut self, factor: &TableFactor<'a>) {
+        walk_table_factor(self, factor)
+    }
+
+    fn visit_table_ref(&mut self, _table: &TableRef<'a>) {}
+
+    /// A column named outside an expression: an INSERT column list, a SET target or USING
+    fn visit_column_name(&mut self, _column: &Ident<'a>) {}
+
+    fn visit_join(&mut self, join: &Join<'a>) {
+        walk_join(self, join)
+    }
+
+    fn visit_order_by(&mut self, order: &OrderByExpr<'a>) {
+        walk_order_by(self, order)
+    }
+
+    fn visit_expr(&mut self, expr: &Expr<'a>) {
+        walk_expr(self, expr)
+    }
+
+    fn visit_insert(&mut self, insert: &Insert<'a>) {
+        walk_insert(self, insert)
+    }
+
+    fn visit_update(&mut self, update: &Update<'a>) {
+        walk_update(self, update)
+    }
+
+    fn visit_assignment(&mut self, assignment: &Assignment<'a>) {
+        walk_assignment(self, assignment)
+    }
+
+    fn visit_delete(&mut self, delete: &Delete<'a>) {
+        walk_delete(self, delete)
+    }
+
+    fn visit_create_table(&mut self, create: &CreateTable<'a>) {
+        walk_create_table(self, create)
+    }
+
+    fn visit_column_def(&mut self, column: &ColumnDef<'a>) {
+        walk_column_def(self, column)
+    }
+}
+
+pub fn walk_statement<'a, V: Visitor<'a> + ?Sized>(v: &mut V, stmt: &Statement<'a>) {
+    match stmt {
+        Statement::Query(query) => v.visit_query(query),
+        Statement::Insert(insert) => v.visit_insert(insert),
+        Statement::Update(update) => v.visit_update(update),
+        Statement::Delete(delete) => v.visit_delete(delete),
+        Statement::CreateTable(create) => v.visit_create_table(create),
+    }
+}
+
+pub fn walk_query<'a, V: Visitor<'a> + ?Sized>(v: &mut V, query: &Query<'a>) {
+    match query {
+        Query::Select(select) => v.visit_select(select),
+        Query::With { with, query } => {
+            v.visit_with(with);
+            v.visit_query(query);
+        }
+        Query::Union {
+            left,
+            right,
+            order_by,
+            limit,
+            offset,
+            ..
+        } => {
+            v.visit_query(left);
+            v.visit_query(right);
+            for order in order_by {
+                v.visit_order_by(order);
+            }
+            if let Some(limit) = limit {
+                v.visit_expr(limit);
+            }
+            if let Some(offset) = offset {
+                v.visit_expr(offset);
+            }
+        }
+    }
+}
+
+pub fn walk_select<'a, V: Visitor<'a> + ?Sized>(v: &mut V, select: &SelectStmt<'a>) {
+    for expr in &select.projection {
+        v.visit_expr(expr);
+    }
+    for table in &select.from {
+        v.visit_table_with_joins(table);
+    }
+    if let Some(where_clause) = &select.where_clause {
+        v.visit_expr(where_clause);
+    }
+    for expr in &select.group_by {
+        v.visit_expr(expr);
+    }
+    if let Some(having) = &select.having {
+        v.visit_expr(having);
+    }
+    for order in &select.order_by {
+        v.visit_order_by(order);
+    }
+    if let Some(limit) = &select.limit {
+        v.visit_expr(limit);
+    }
+    if let Some(offset) = &select.offset {
+        v.visit_expr(offset);
+    }
+}
+
+pub fn walk_with<'a, V: Visitor<'a> + ?Sized>(v: &mut V, with: &With<'a>) {
+    for cte in &with.ctes {
+        v.visit_cte(cte);
+    }
+}
+
+pub fn walk_cte<'a, V: Visitor<'a> + ?Sized>(v: &mut V, cte: &CTE<'a>) {
+    v.visit_query(&cte.query);
+}
+
+pub fn walk_table_with_joins<'a, V: Visitor<'a> + ?Sized>(v: &mut V, table: &TableWithJoins<'a>) {
+    v.visit_table_factor(&table.relation);
+    for join in &table.joins {
+        v.visit_join(join);
+    }
+}
+
+pub fn walk_table_factor<'a, V: Visitor<'a> + ?Sized>(v: &mut V, factor: &TableFactor<'a>) {
+    match factor {
+        TableFactor::Table(table) => v.visit_table_ref(table),
+        TableFactor::Derived { subquery, .. } => v.visit_query(subquery),
+    }
+}
+
+pub fn walk_join<'a, V: Visitor<'a> + ?Sized>(v: &mut V, join: &Join<'a>) {
+    v.visit_table_factor(&join.relation);
+    match &join.constraint {
+        JoinConstraint::On(expr) => v.visit_expr(expr),
+        JoinConstraint::Using(columns) => {
+            for column in columns {
+                v.visit_column_name(column);
+            }
+        }
+        JoinConstraint::None => {}
+    }
+}
+
+pub fn walk_order_by<'a, V: Visitor<'a> + ?Sized>(v: &mut V, order: &OrderByExpr<'a>) {
+    v.visit_expr(&order.expr);
+}
+
+pub fn walk_expr<'a, V: Visitor<'a> + ?Sized>(v: &mut V, expr: &Expr<'a>) {
+    match &expr.kind {
+        ExprKind::Column(_)
+        | ExprKind::QualifiedColumn { .. }
+        | ExprKind::Literal(_)
+        | ExprKind::Star => {}
+        ExprKind::Binary { left, right, .. } => {
+            v.visit_expr(left);
+            v.visit_expr(right);
+        }
+        ExprKind::Unary { expr, .. }
+        | ExprKind::IsNull { expr, .. }
+        | ExprKind::Cast { expr, .. }
+        | ExprKind::Paren(expr) => v.visit_expr(expr),
+        ExprKind::Function { args, .. } => {
+            for arg in args {
+                v.visit_expr(arg);
+            }
+        }
+        ExprKind::InList { expr, list, .. } => {
+            v.visit_expr(expr);
+            for item in list {
+                v.visit_expr(item);
+            }
+        }
+        ExprKind::InSubquery { expr, subquery, .. } => {
+            v.visit_expr(expr);
+            v.visit_query(subquery);
+        }
+        ExprKind::Between {
+            expr, low, high, ..
+        } => {
+            v.visit_expr(expr);
+            v.visit_expr(low);
+            v.visit_expr(high);
+        }
+        ExprKind::Like { expr, pattern, .. } => {
+            v.visit_expr(expr);
+            v.visit_expr(pattern);
+        }
+        ExprKind::Case {
+            operand,
+            when_then,
+            else_result,
+        } => {
+            if let Some(operand) = operand {
+                v.visit_expr(operand);
+            }
+            for (when, then) in when_then {
+                v.visit_expr(when);
+                v.visit_expr(then);
+            }
+            if let Some(else_result) = else_result {
+                v.visit_expr(else_result);
+            }
+        }
+    }
+}
+
+pub fn walk_insert<'a, V: Visitor<'a> + ?Sized>(v: &mut V, insert: &Insert<'a>) {
+    v.visit_table_ref(&insert.table);
+    for column in &insert.columns {
+        v.visit_column_name(column);
+    }
+    match &insert.source {
+        InsertSource::Values(rows) => {
+            for expr in rows.iter().flatten() {
+                v.visit_expr(expr);
+            }
+        }
+        InsertSource::Query(query) => v.visit_query(query),
+    }
+}
+
+pub fn walk_update<'a, V: Visitor<'a> + ?Sized>(v: &mut V, update: &Update<'a>) {
+    v.visit_table_ref(&update.table);
+    for assignment in &update.assignments {
+        v.visit_assignment(assignment);
+    }
+    if let Some(where_clause) = &update.where_clause {
+        v.visit_expr(where_clause);
+    }
+}
+
+pub fn walk_assignment<'a, V: Visitor<'a> + ?Sized>(v: &mut V, assignment: &Assignment<'a>) {
+    v.visit_column_name(&assignment.column);
+    v.visit_expr(&assignment.value);
+}
+
+pub fn walk_delete<'a, V: Visitor<'a> + ?Sized>(v: &mut V, delete: &Delete<'a>) {
+    v.visit_table_ref(&delete.table);
+    if let Some(where_clause) = &delete.where_clause {
+        v.visit_expr(where_clause);
+    }
+}
+
+pub fn walk_create_table<'a, V: Visitor<'a> + ?Sized>(v: &mut V, create: &CreateTable<'a>) {
+    v.visit_table_ref(&create.name);
+    for column in &create.columns {
+        v.visit_column_def(column);
+    }
+}
+
+pub fn walk_column_def<'a, V: Visitor<'a> + ?Sized>(v: &mut V, column: &ColumnDef<'a>) {
+    for constraint in &column.constraints {
+        if let ColumnConstraint::Default(expr) = constraint {
+            v.visit_expr(expr);
+        }
+    }
+}
+
+/// In-place AST rewriting, mirroring `Visitor` over mutable references
+pub trait VisitorMut<'a> {
+    fn visit_statement_mut(&mut self, stmt: &mut Statement<'a>) {
+        walk_statement_mut(self, stmt)
+    }
+
+    fn visit_query_mut(&mut self, query: &mut Query<'a>) {
+        walk_query_mut(self, query)
+    }
+
+    fn visit_select_mut(&mut self, select: &mut SelectStmt<'a>) {
+        walk_select_mut(self, select)
+    }
+
+    fn visit_with_mut(&mut self, with: &mut With<'a>) {
+        walk_with_mut(self, with)
+    }
+
+    fn visit_cte_mut(&mut self, cte: &mut CTE<'a>) {
+        walk_cte_mut(self, cte)
+    }
+
+    fn visit_table_with_joins_mut(&mut self, table: &mut TableWithJoins<'a>) {
+        walk_table_with_joins_mut(self, table)
+    }
+
+    fn visit_table_factor_mut(&mut self, factor: &mut TableFactor<'a>) {
+        walk_table_factor_mut(self, factor)
+    }
+
+    fn visit_table_ref_mut(&mut self, _table: &mut TableRef<'a>) {}
+
+    fn visit_column_name_mut(&mut self, _column: &mut Ident<'a>) {}
+
+    fn visit_join_mut(&mut self, join: &mut Join<'a>) {
+        walk_join_mut(self, join)
+    }
+
+    fn visit_order_by_mut(&mut self, order: &mut OrderByExpr<'a>) {
+        walk_order_by_mut(self, order)
+    }
+
+    fn visit_expr_mut(&mut self, expr: &mut Expr<'a>) {
+        walk_expr_mut(self, expr)
+    }
+
+    fn visit_insert_mut(&mut self, insert: &mut Insert<'a>) {
+        walk_insert_mut(self, insert)
+    }
+
+    fn visit_update_mut(&mut self, update: &mut Update<'a>) {
+        walk_update_mut(self, update)
+    }
+
+    fn visit_assignment_mut(&mut self, assignment: &mut Assignment<'a>) {
+        walk_assignment_mut(self, assignment)
+    }
+
+    fn visit_delete_mut(&mut self, delete: &mut Delete<'a>) {
+        walk_delete_mut(self, delete)
+    }
+
+    fn visit_create_table_mut(&mut self, create: &mut CreateTable<'a>) {
+        walk_create_table_mut(self, create)
+    }
+
+    fn visit_column_def_mut(&mut self, column: &mut ColumnDef<'a>) {
+        walk_column_def_mut(self, column)
+    }
+}
+
+pub fn walk_statement_mut<'a, V: VisitorMut<'a> + ?Sized>(v: &mut V, stmt: &mut Statement<'a>) {
+    match stmt {
+        Statement::Query(query) => v.visit_query_mut(query),
+        Statement::Insert(insert) => v.visit_insert_mut(insert),
+        Statement::Update(update) => v.visit_update_mut(update),
+        Statement::Delete(delete) => v.visit_delete_mut(delete),
+        Statement::CreateTable(create) => v.visit_create_table_mut(create),
+    }
+}
+
+pub fn walk_query_mut<'a, V: VisitorMut<'a> + ?Sized>(v: &mut V, query: &mut Query<'a>) {
+    match query {
+        Query::Select(select) => v.visit_select_mut(select),
+        Query::With { with, query } => {
+            v.visit_with_mut(with);
+            v.visit_query_mut(query);
+        }
+        Query::Union {
+            left,
+            right,
+            order_by,
+            limit,
+            offset,
+            ..
+        } => {
+            v.visit_query_mut(left);
+            v.visit_query_mut(right);
+            for order in order_by {
+                v.visit_order_by_mut(order);
+            }
+            if let Some(limit) = limit {
+                v.visit_expr_mut(limit);
+            }
+            if let Some(offset) = offset {
+                v.visit_expr_mut(offset);
+            }
+        }
+    }
+}
+
+pub fn walk_select_mut<'a, V: VisitorMut<'a> + ?Sized>(v: &mut V, select: &mut SelectStmt<'a>) {
+    for expr in &mut select.projection {
+        v.visit_expr_mut(expr);
+    }
+    for table in &mut select.from {
+        v.visit_table_with_joins_mut(table);
+    }
+    if let Some(where_clause) = &mut select.where_clause {
+        v.visit_expr_mut(where_clause);
+    }
+    for expr in &mut select.group_by {
+        v.visit_expr_mut(expr);
+    }
+    if let Some(having) = &mut select.having {
+        v.visit_expr_mut(having);
+    }
+    for order in &mut select.order_by {
+        v.visit_order_by_mut(order);
+    }
+    if let Some(limit) = &mut select.limit {
+        v.visit_expr_mut(limit);
+    }
+    if let Some(offset) = &mut select.offset {
+        v.visit_expr_mut(offset);
+    }
+}
+
+pub fn walk_with_mut<'a, V: VisitorMut<'a> + ?Sized>(v: &mut V, with: &mut With<'a>) {
+    for cte in &mut with.ctes {
+        v.visit_cte_mut(cte);
+    }
+}
+
+pub fn walk_cte_mut<'a, V: VisitorMut<'a> + ?Sized>(v: &mut V, cte: &mut CTE<'a>) {
+    v.visit_query_mut(&mut cte.query);
+}
+
+pub fn walk_table_with_joins_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    v: &mut V,
+    table: &mut TableWithJoins<'a>,
+) {
+    v.visit_table_factor_mut(&mut table.relation);
+    for join in &mut table.joins {
+        v.visit_join_mut(join);
+    }
+}
+
+pub fn walk_table_factor_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    v: &mut V,
+    factor: &mut TableFactor<'a>,
+) {
+    match factor {
+        TableFactor::Table(table) => v.visit_table_ref_mut(table),
+        TableFactor::Derived { subquery, .. } => v.visit_query_mut(subquery),
+    }
+}
+
+pub fn walk_join_mut<'a, V: VisitorMut<'a> + ?Sized>(v: &mut V, join: &mut Join<'a>) {
+    v.visit_table_factor_mut(&mut join.relation);
+    match &mut join.constraint {
+        JoinConstraint::On(expr) => v.visit_expr_mut(expr),
+        JoinConstraint::Using(columns) => {
+            for column in columns {
+                v.visit_column_name_mut(column);
+            }
+        }
+        JoinConstraint::None => {}
+    }
+}
+
+pub fn walk_order_by_mut<'a, V: VisitorMut<'a> + ?Sized>(v: &mut V, order: &mut OrderByExpr<'a>) {
+    v.visit_expr_mut(&mut order.expr);
+}
+
+pub fn walk_expr_mut<'a, V: VisitorMut<'a> + ?Sized>(v: &mut V, expr: &mut Expr<'a>) {
+    match &mut expr.kind {
+        ExprKind::Column(_)
+        | ExprKind::QualifiedColumn { .. }
+        | ExprKind::Literal(_)
+        | ExprKind::Star => {}
+        ExprKind::Binary { left, right, .. } => {
+            v.visit_expr_mut(left);
+            v.visit_expr_mut(right);
+        }
+        ExprKind::Unary { expr, .. }
+        | ExprKind::IsNull { expr, .. }
+        | ExprKind::Cast { expr, .. }
+        | ExprKind::Paren(expr) => v.visit_expr_mut(expr),
+        ExprKind::Function { args, .. } => {
+            for arg in args {
+                v.visit_expr_mut(arg);
+            }
+        }
+        ExprKind::InList { expr, list, .. } => {
+            v.visit_expr_mut(expr);
+            for item in list {
+                v.visit_expr_mut(item);
+            }
+        }
+        ExprKind::InSubquery { expr, subquery, .. } => {
+            v.visit_expr_mut(expr);
+            v.visit_query_mut(subquery);
+        }
+        ExprKind::Between {
+            expr, low, high, ..
+        } => {
+            v.visit_expr_mut(expr);
+            v.visit_expr_mut(low);
+            v.visit_expr_mut(high);
+        }
+        ExprKind::Like { expr, pattern, .. } => {
+            v.visit_expr_mut(expr);
+            v.visit_expr_mut(pattern);
+        }
+        ExprKind::Case {
+            operand,
+            when_then,
+            else_result,
+        } => {
+            if let Some(operand) = operand {
+                v.visit_expr_mut(operand);
+            }
+            for (when, then) in when_then {
+                v.visit_expr_mut(when);
+                v.visit_expr_mut(then);
+            }
+            if let Some(else_result) = else_result {
+                v.visit_expr_mut(else_result);
+            }
+        }
+    }
+}
+
+pub fn walk_insert_mut<'a, V: VisitorMut<'a> + ?Sized>(v: &mut V, insert: &mut Insert<'a>) {
+    v.visit_table_ref_mut(&mut insert.table);
+    for column in &mut insert.columns {
+        v.visit_column_name_mut(column);
+    }
+    match &mut insert.source {
+        InsertSource::Values(rows) => {
+            for expr in rows.iter_mut().flatten() {
+                v.visit_expr_mut(expr);
+            }
+        }
+        InsertSource::Query(query) => v.visit_query_mut(query),
+    }
+}
+
+pub fn walk_update_mut<'a, V: VisitorMut<'a> + ?Sized>(v: &mut V, update: &mut Update<'a>) {
+    v.visit_table_ref_mut(&mut update.table);
+    for assignment in &mut update.assignments {
+        v.visit_assignment_mut(assignment);
+    }
+    if let Some(where_clause) = &mut update.where_clause {
+        v.visit_expr_mut(where_clause);
+    }
+}
+
+pub fn walk_assignment_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    v: &mut V,
+    assignment: &mut Assignment<'a>,
+) {
+    v.visit_column_name_mut(&mut assignment.column);
+    v.visit_expr_mut(&mut assignment.value);
+}
+
+pub fn walk_delete_mut<'a, V: VisitorMut<'a> + ?Sized>(v: &mut V, delete: &mut Delete<'a>) {
+    v.visit_table_ref_mut(&mut delete.table);
+    if let Some(where_clause) = &mut delete.where_clause {
+        v.visit_expr_mut(where_clause);
+    }
+}
+
+pub fn walk_create_table_mut<'a, V: VisitorMut<'a> + ?Sized>(
+    v: &mut V,
+    create: &mut CreateTable<'a>,
+) {
+    v.visit_table_ref_mut(&mut create.name);
+    for column in &mut create.columns {
+        v.visit_column_def_mut(column);
+    }
+}
+
+pub fn walk_column_def_mut<'a, V: VisitorMut<'a> + ?Sized>(v: &mut V, column: &mut ColumnDef<'a>) {
+    for constraint in &mut column.constraints {
+        if let ColumnConstraint::Default(expr) = constraint {
+            v.visit_expr_mut(expr);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        error::Backtrace,
+        expr::{BinaryOp, Literal},
+        parser::Parser,
+        token::tokenize,
+    };
+
+    struct TableCollector<'a> {
+        tables: Vec<&'a str>,
+    }
+
+    impl<'a> Visitor<'a> for TableCollector<'a> {
+        fn visit_table_ref(&mut self, table: &TableRef<'a>) {
+            self.tables.push(table.name);
+        }
+    }
+
+    struct ColumnRenamer<'a> {
+        from: &'a str,
+        to: &'a str,
+    }
+
+    impl<'a> VisitorMut<'a> for ColumnRenamer<'a> {
+        fn visit_expr_mut(&mut self, expr: &mut Expr<'a>) {
+            if let ExprKind::Column(name) = &mut expr.kind
+                && *name == self.from
+            {
+                *name = self.to;
+            }
+            walk_expr_mut(self, expr);
+        }
+
+        fn visit_column_name_mut(&mut self, column: &mut Ident<'a>) {
+            if column.name == self.from {
+                column.name = self.to;
+            }
+        }
+    }
+
+    struct ConstantFolder;
+
+    impl<'a> VisitorMut<'a> for ConstantFolder {
+        fn visit_expr_mut(&mut self, expr: &mut Expr<'a>) {
+            // Fold children first so nested constants collapse bottom-up
+            walk_expr_mut(self, expr);
+
+            let folded = match &expr.kind {
+                ExprKind::Paren(inner) if matches!(inner.kind, ExprKind::Literal(_)) => {
+                    Some(inner.kind.clone())
+                }
+                ExprKind::Binary { left, op, right } => match (&left.kind, &right.kind) {
+                    (
+                        ExprKind::Literal(Literal::Number(l)),
+                        ExprKind::Literal(Literal::Number(r)),
+                    ) => match op {
+                        BinaryOp::Plus => Some(l + r),
+                        BinaryOp::Minus => Some(l - r),
+                        BinaryOp::Multiply => Some(l * r),
+                        _ => None,
+                    }
+                    .map(|n| ExprKind::Literal(Literal::Number(n))),
+                    _ => None,
+                },
+                _ => None,
+            };
+
+            if let Some(folded) = folded {
+                expr.kind = folded;
+            }
+        }
+    }
+
+    fn collect_tables(sql: &str) -> Vec<&str> {
+        let tokens = tokenize(sql);
+        let backtrace = Backtrace::new();
+        let mut parser = Parser::new(&tokens, &backtrace, sql);
+        let stmt = parser.parse_statement().unwrap();
+
+        let mut collector = TableCollector { tables: Vec::new() };
+        collector.visit_statement(&stmt);
+        collector.tables
+    }
+
+    #[test]
+    fn test_collect_table_references() {
+        assert_eq!(
+            collect_tables(
+                "WITH recent AS (SELECT * FROM orders) SELECT * FROM users u \
+                 JOIN recent ON id = user_id WHERE id IN (SELECT owner FROM admins)"
+            ),
+            ["orders", "users", "recent", "admins"]
+        );
+        assert_eq!(
+            collect_tables("INSERT INTO archive (id) SELECT id FROM users"),
+            ["archive", "users"]
+        );
+        assert_eq!(collect_tables("CREATE TABLE audit (id INT)"), ["audit"]);
+    }
+
+    #[test]
+    fn test_rename_columns_and_fold_constants() {
+        let sql = "SELECT price * (2 + 3) FROM items WHERE price > 10 - 4 ORDER BY price";
+        let tokens = tokenize(sql);
+        let backtrace = Backtrace::new();
+        let mut parser = Parser::new(&tokens, &backtrace, sql);
+        let mut stmt = parser.parse_statement().unwrap();
+
+        ColumnRenamer {
+            from: "price",
+            to: "cost",
+        }
+        .visit_statement_mut(&mut stmt);
+        ConstantFolder.visit_statement_mut(&mut stmt);
+
+        assert_eq!(
+            stmt.to_string(),
+            "SELECT cost * 5 FROM items WHERE cost > 6 ORDER BY cost"
+        );
+
+        let sql = "INSERT INTO items (id, price) VALUES (1, 2)";
+        let tokens = tokenize(sql);
+        let backtrace = Backtrace::new();
+        let mut parser = Parser::new(&tokens, &backtrace, sql);
+        let mut stmt = parser.parse_statement().unwrap();
+        ColumnRenamer {
+            from: "price",
+            to: "cost",
+        }
+        .visit_statement_mut(&mut stmt);
+        assert_eq!(
+            stmt.to_string(),
+            "INSERT INTO items (id, cost) VALUES (1, 2)"
+        );
+    }
+}